@@ -1,3 +1,4 @@
+use crate::user;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
@@ -53,6 +54,22 @@ fn get_default_search_paths() -> Vec<SearchPath> {
             installation_type: InstallationType::System,
         });
 
+        // ============================================================
+        // Less common distro prefixes (Void, Solus, Gentoo)
+        // ============================================================
+        paths.push(SearchPath {
+            path: PathBuf::from("/usr/lib/bitwig-studio"),
+            installation_type: InstallationType::System,
+        });
+        paths.push(SearchPath {
+            path: PathBuf::from("/usr/lib64/bitwig-studio"),
+            installation_type: InstallationType::System,
+        });
+        paths.push(SearchPath {
+            path: PathBuf::from("/opt/bitwig"),
+            installation_type: InstallationType::System,
+        });
+
         // ============================================================
         // Snap installations (Ubuntu, Linux Mint, etc.)
         // ============================================================
@@ -95,7 +112,7 @@ fn get_default_search_paths() -> Vec<SearchPath> {
         // ============================================================
         // User-specific paths
         // ============================================================
-        if let Some(home) = dirs::home_dir() {
+        if let Some(home) = user::home_dir() {
             // User-local installations - no sudo needed
             paths.push(SearchPath {
                 path: home.join(".local/share/bitwig-studio"),
@@ -155,6 +172,15 @@ fn get_default_search_paths() -> Vec<SearchPath> {
                 }
             }
         }
+
+        // ============================================================
+        // Launcher scripts on PATH (packages that only ship a wrapper in /usr/bin)
+        // ============================================================
+        for launcher_path in get_launcher_search_paths() {
+            if !paths.iter().any(|p| p.path == launcher_path.path) {
+                paths.push(launcher_path);
+            }
+        }
     }
 
     #[cfg(target_os = "macos")]
@@ -163,7 +189,7 @@ fn get_default_search_paths() -> Vec<SearchPath> {
             path: PathBuf::from("/Applications"),
             installation_type: InstallationType::System,
         });
-        if let Some(home) = dirs::home_dir() {
+        if let Some(home) = user::home_dir() {
             paths.push(SearchPath {
                 path: home.join("Applications"),
                 installation_type: InstallationType::UserLocal,
@@ -235,6 +261,193 @@ fn get_default_search_paths() -> Vec<SearchPath> {
     paths
 }
 
+/// System prefixes Bitwig packages install into
+#[cfg(target_os = "linux")]
+const LAUNCHER_INSTALL_ROOTS: &[&str] =
+    &["/opt", "/usr/lib", "/usr/lib64", "/usr/local", "/usr/share"];
+
+/// Whether a path a launcher mentions can be an installation
+/// Without a `bitwig.jar` to go on, only paths under the usual install prefixes (or the
+/// user's `~/.local`) count, so config or log paths that merely mention Bitwig don't
+#[cfg(target_os = "linux")]
+fn under_install_root(path: &Path) -> bool {
+    LAUNCHER_INSTALL_ROOTS.iter().any(|root| path.starts_with(root))
+        || user::home_dir().is_some_and(|home| path.starts_with(home.join(".local")))
+}
+
+/// Find the Bitwig path a launcher script refers to
+/// Prefers an explicit `bitwig.jar` reference over other Bitwig paths
+#[cfg(target_os = "linux")]
+fn parse_launcher_script(content: &str) -> Option<PathBuf> {
+    let re = regex::Regex::new(r"/[\w.+\-/]*[Bb]itwig[\w.+\-/]*").unwrap();
+    let mut fallback = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        for m in re.find_iter(line) {
+            let path = m.as_str().trim_end_matches('/');
+            if path.ends_with("bitwig.jar") {
+                return Some(PathBuf::from(path));
+            }
+            if fallback.is_none() && under_install_root(Path::new(path)) {
+                fallback = Some(PathBuf::from(path));
+            }
+        }
+    }
+
+    fallback
+}
+
+/// Work out the installation root from a path referenced by a launcher
+#[cfg(target_os = "linux")]
+fn launcher_install_root(target: &Path) -> Option<PathBuf> {
+    if target.is_dir() {
+        return Some(target.to_path_buf());
+    }
+
+    let parent = target.parent()?;
+    if parent.file_name().is_some_and(|name| name == "bin") {
+        return parent.parent().map(Path::to_path_buf);
+    }
+    Some(parent.to_path_buf())
+}
+
+/// Build search paths from `bitwig-studio` launchers found on PATH
+#[cfg(target_os = "linux")]
+fn get_launcher_search_paths() -> Vec<SearchPath> {
+    let Some(path_var) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<SearchPath> = Vec::new();
+
+    for dir in std::env::split_paths(&path_var) {
+        let launcher = dir.join("bitwig-studio");
+        if !launcher.is_file() {
+            continue;
+        }
+
+        // Wrapper scripts name the install directory; otherwise follow symlinks to the binary
+        let root = std::fs::read_to_string(&launcher)
+            .ok()
+            .and_then(|content| parse_launcher_script(&content))
+            .and_then(|target| launcher_install_root(&target))
+            .or_else(|| {
+                let resolved = launcher.canonicalize().ok()?;
+                launcher_install_root(&resolved).filter(|root| *root != dir)
+            });
+
+        let Some(root) = root else {
+            continue;
+        };
+        if paths.iter().any(|p| p.path == root) {
+            continue;
+        }
+
+        let installation_type = if path_needs_sudo(&root) {
+            InstallationType::System
+        } else {
+            InstallationType::UserLocal
+        };
+        paths.push(SearchPath {
+            path: root,
+            installation_type,
+        });
+    }
+
+    paths
+}
+
+/// Expand a leading `~` and `$VAR`/`${VAR}` references in a user-supplied path
+pub fn expand_path(raw: &str) -> PathBuf {
+    let raw = raw.trim();
+    let var = regex::Regex::new(r"\$\{(\w+)\}|\$(\w+)").unwrap();
+    let expanded = var.replace_all(raw, |caps: &regex::Captures| {
+        let name = caps
+            .get(1)
+            .or_else(|| caps.get(2))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        std::env::var(name).unwrap_or_else(|_| caps[0].to_string())
+    });
+
+    if let Some(home) = user::home_dir() {
+        if expanded == "~" {
+            return home;
+        }
+        if let Some(rest) = expanded
+            .strip_prefix("~/")
+            .or_else(|| expanded.strip_prefix("~\\"))
+        {
+            return home.join(rest);
+        }
+    }
+
+    PathBuf::from(expanded.into_owned())
+}
+
+/// Build search paths from the user's configured custom paths
+/// Paths already covered by the defaults are skipped
+fn get_custom_search_paths(custom: &[String], defaults: &[SearchPath]) -> Vec<SearchPath> {
+    let mut paths: Vec<SearchPath> = Vec::new();
+
+    for raw in custom {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let path = expand_path(raw);
+        if defaults.iter().chain(paths.iter()).any(|p| p.path == path) {
+            continue;
+        }
+        let installation_type = if path_needs_sudo(&path) {
+            InstallationType::System
+        } else {
+            InstallationType::UserLocal
+        };
+        paths.push(SearchPath {
+            path,
+            installation_type,
+        });
+    }
+
+    paths
+}
+
+/// Get the default search paths followed by the custom paths from settings
+fn get_all_search_paths() -> Vec<SearchPath> {
+    let mut paths = get_default_search_paths();
+    let custom = crate::settings::load_settings()
+        .map(|s| s.custom_search_paths)
+        .unwrap_or_default();
+    let extra = get_custom_search_paths(&custom, &paths);
+    paths.extend(extra);
+    paths
+}
+
+/// A detector search path with its existence on this system
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchPathInfo {
+    pub path: String,
+    pub installation_type: InstallationType,
+    pub exists: bool,
+}
+
+/// Report every path the detector searches, including env-var and custom contributions
+pub fn get_search_paths() -> Vec<SearchPathInfo> {
+    get_all_search_paths()
+        .into_iter()
+        .map(|sp| SearchPathInfo {
+            exists: sp.path.exists(),
+            path: sp.path.to_string_lossy().to_string(),
+            installation_type: sp.installation_type,
+        })
+        .collect()
+}
+
 /// Determine if a path requires sudo to modify
 fn path_needs_sudo(path: &Path) -> bool {
     #[cfg(unix)]
@@ -302,28 +515,74 @@ fn extract_version(path: &Path) -> String {
     "unknown".to_string()
 }
 
+/// Read the version Bitwig records next to its JAR
+/// Linux keeps it in `<root>/resources/build-info.sh` (JAR in `<root>/bin`), macOS in
+/// `Contents/Resources` or `Contents/Info.plist` (JAR in `Contents/app/bin`), and Windows
+/// only in the version resource of `Bitwig Studio.exe` next to `bin`
 fn get_version_from_build_info(jar_path: &Path) -> Option<String> {
     let install_root = jar_path.parent()?.parent()?;
-    let candidates = [
-        install_root.join("resources").join("build-info.sh"),
-        install_root.join("Resources").join("build-info.sh"),
+    // `Contents` on macOS, where the JAR sits one level deeper in `Contents/app`
+    let bundle_contents = install_root.parent();
+
+    let build_info = [
+        Some(install_root.join("resources").join("build-info.sh")),
+        Some(install_root.join("Resources").join("build-info.sh")),
+        bundle_contents.map(|c| c.join("Resources").join("build-info.sh")),
     ];
+    let from_build_info = build_info
+        .into_iter()
+        .flatten()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|content| version_from_build_info(&content));
 
-    for path in candidates {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            for line in content.lines() {
-                if let Some(value) = line.strip_prefix("BITWIG_STUDIO_VERSION_NAME=") {
-                    let trimmed = value.trim().trim_matches('"');
-                    if !trimmed.is_empty() {
-                        return Some(trimmed.to_string());
-                    }
-                }
-            }
-        }
-    }
+    from_build_info
+        .or_else(|| {
+            let plist = std::fs::read_to_string(bundle_contents?.join("Info.plist")).ok()?;
+            version_from_info_plist(&plist)
+        })
+        .or_else(|| {
+            let exe = std::fs::read(install_root.join("Bitwig Studio.exe")).ok()?;
+            version_from_exe_resource(&exe)
+        })
+}
 
-    None
+/// Read `BITWIG_STUDIO_VERSION_NAME` from a `build-info.sh`
+fn version_from_build_info(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix("BITWIG_STUDIO_VERSION_NAME=")?;
+        let trimmed = value.trim().trim_matches('"');
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
+    })
 }
+
+/// Read `CFBundleShortVersionString` from a macOS bundle's `Info.plist`
+fn version_from_info_plist(content: &str) -> Option<String> {
+    let after_key = content.split("<key>CFBundleShortVersionString</key>").nth(1)?;
+    let value = after_key.trim_start().strip_prefix("<string>")?;
+    let value = value[..value.find("</string>")?].trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Read the `ProductVersion` string from a Windows executable's version resource
+/// Resource strings are UTF-16LE: the key, NUL padding to a 4-byte boundary, then the value
+fn version_from_exe_resource(bytes: &[u8]) -> Option<String> {
+    let key: Vec<u8> = "ProductVersion\0"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let start = bytes.windows(key.len()).position(|w| w == key.as_slice())? + key.len();
+
+    let units: Vec<u16> = bytes[start..]
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .skip_while(|&unit| unit == 0)
+        .take_while(|&unit| unit != 0)
+        .collect();
+    let value = String::from_utf16(&units).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
 /// Check if bitwig.jar has been patched for theme support
 pub fn is_jar_patched(jar_path: &Path) -> bool {
     // For now, we'll check for the existence of a marker file
@@ -334,8 +593,97 @@ pub fn is_jar_patched(jar_path: &Path) -> bool {
 
 /// Detect all Bitwig Studio installations on the system
 pub fn detect_installations() -> Vec<BitwigInstallation> {
+    let installations = scan_search_paths(get_all_search_paths());
+    if !installations.is_empty() {
+        return installations;
+    }
+
+    // Last resort: a custom install that is running right now
+    detect_from_running_process().into_iter().collect()
+}
+
+/// Find the `bitwig.jar` path in process information
+/// Accepts `/proc/<pid>/maps` lines, NUL-separated `/proc/<pid>/cmdline`
+/// (including `-cp a.jar:b.jar` classpaths) and `lsof -Fn` output
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn jar_path_from_process_info(info: &str) -> Option<PathBuf> {
+    info.split(['\n', '\0']).find_map(|line| {
+        let end = line.find("bitwig.jar")? + "bitwig.jar".len();
+        let path = &line[..end];
+        // Classpath entries are separated by ':'; maps fields come before the path
+        let path = path.rsplit([':', '=']).next()?;
+        let path = &path[path.find('/')?..];
+        Some(PathBuf::from(path))
+    })
+}
+
+/// Installation root for a jar found in a running process
+/// Prefers the enclosing `.app` bundle, then the directory above `bin/`
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn install_root_from_jar(jar: &Path) -> Option<PathBuf> {
+    if let Some(bundle) = jar
+        .ancestors()
+        .find(|dir| dir.extension().is_some_and(|ext| ext == "app"))
+    {
+        return Some(bundle.to_path_buf());
+    }
+
+    let parent = jar.parent()?;
+    if parent.file_name().is_some_and(|name| name == "bin") {
+        return parent.parent().map(Path::to_path_buf);
+    }
+    Some(parent.to_path_buf())
+}
+
+/// Find the jar used by a running Bitwig process via `/proc`
+#[cfg(target_os = "linux")]
+fn running_bitwig_jar() -> Option<PathBuf> {
+    let entries = std::fs::read_dir("/proc").ok()?;
+
+    entries.filter_map(|e| e.ok()).find_map(|entry| {
+        let name = entry.file_name();
+        if !name.to_string_lossy().chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+        let cmdline = String::from_utf8_lossy(&cmdline);
+        if !cmdline.to_lowercase().contains("bitwig") {
+            return None;
+        }
+
+        jar_path_from_process_info(&cmdline).or_else(|| {
+            let maps = std::fs::read_to_string(entry.path().join("maps")).ok()?;
+            jar_path_from_process_info(&maps)
+        })
+    })
+}
+
+/// Find the jar used by a running Bitwig process via `lsof`
+#[cfg(target_os = "macos")]
+fn running_bitwig_jar() -> Option<PathBuf> {
+    let output = std::process::Command::new("lsof")
+        .args(["-c", "Bitwig", "-Fn"])
+        .output()
+        .ok()?;
+    jar_path_from_process_info(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn running_bitwig_jar() -> Option<PathBuf> {
+    None
+}
+
+/// Detect an installation from the jar a running Bitwig process has open
+/// Catches custom install locations that no search path covers
+pub fn detect_from_running_process() -> Option<BitwigInstallation> {
+    let jar = running_bitwig_jar()?;
+    validate_installation(&install_root_from_jar(&jar)?)
+}
+
+/// Scan the given search paths for Bitwig installations
+fn scan_search_paths(search_paths: Vec<SearchPath>) -> Vec<BitwigInstallation> {
     let mut installations = Vec::new();
-    let search_paths = get_default_search_paths();
 
     for search_path in search_paths {
         if !search_path.path.exists() {
@@ -445,7 +793,7 @@ pub fn validate_installation(path: &Path) -> Option<BitwigInstallation> {
         } else if path_str.contains("program files") {
             // Windows system installation
             InstallationType::System
-        } else if let Some(home) = dirs::home_dir() {
+        } else if let Some(home) = user::home_dir() {
             if path.starts_with(&home) {
                 InstallationType::UserLocal
             } else {
@@ -468,18 +816,41 @@ pub fn validate_installation(path: &Path) -> Option<BitwigInstallation> {
     }
 }
 
+/// Check that a string looks like a Bitwig version, e.g. `5.2`, `5.2.4` or `5.3 Beta 2`
+pub fn is_valid_version(version: &str) -> bool {
+    let re = regex::Regex::new(r"(?i)^\d+\.\d+(?:\.\d+)?(?:[ \-]?(?:beta|rc)[ \-]?\d*)?$").unwrap();
+    re.is_match(version)
+}
+
+/// Read the version from Bitwig's `latest-launched-version.txt`
+/// The value ends up in theme directory paths, so anything that doesn't look
+/// like a version is rejected (and logged) rather than trusted
+pub fn read_version_file(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let version = content.trim();
+    if version.is_empty() {
+        return None;
+    }
+    if !is_valid_version(version) {
+        crate::log_event(&format!(
+            "Ignoring unexpected content in {}: {:?}",
+            path.display(),
+            version.chars().take(64).collect::<String>()
+        ));
+        return None;
+    }
+    // Keep the full version string including beta/rc tags
+    // bitwig-theme-editor uses the full version for theme directories
+    Some(version.to_string())
+}
+
 /// Get the latest Bitwig version from the version file or detected installations
 pub fn get_latest_version() -> String {
     // First try to read from Bitwig's own version file
-    if let Some(home) = dirs::home_dir() {
+    if let Some(home) = user::home_dir() {
         let version_file = home.join(".BitwigStudio/latest-launched-version.txt");
-        if let Ok(version) = std::fs::read_to_string(&version_file) {
-            let version = version.trim();
-            if !version.is_empty() {
-                // Return the full version string including beta/rc tags
-                // bitwig-theme-editor uses the full version for theme directories
-                return version.to_string();
-            }
+        if let Some(version) = read_version_file(&version_file) {
+            return version;
         }
     }
 
@@ -497,6 +868,51 @@ pub fn get_latest_version() -> String {
 mod tests {
     use super::*;
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_launcher_script() {
+        let script = "#!/bin/sh\n\
+            # Wrapper installed to /usr/bin/bitwig-studio\n\
+            BITWIG_HOME=/usr/lib/bitwig-studio\n\
+            exec java -jar /usr/lib/bitwig-studio/bin/bitwig.jar \"$@\"\n";
+        assert_eq!(
+            parse_launcher_script(script),
+            Some(PathBuf::from("/usr/lib/bitwig-studio/bin/bitwig.jar"))
+        );
+
+        let script = "#!/bin/sh\nexec /opt/bitwig/bitwig-studio \"$@\"\n";
+        assert_eq!(
+            parse_launcher_script(script),
+            Some(PathBuf::from("/opt/bitwig/bitwig-studio"))
+        );
+
+        assert_eq!(parse_launcher_script("#!/bin/sh\nexec true\n"), None);
+
+        // Paths outside the install prefixes only count when they name the JAR
+        let script = "#!/bin/sh\nexport LOG=/var/log/bitwig-wrapper.log\nexec /srv/bitwig/run\n";
+        assert_eq!(parse_launcher_script(script), None);
+        let script = "#!/bin/sh\nexec java -jar /srv/bitwig/bin/bitwig.jar\n";
+        assert_eq!(
+            parse_launcher_script(script),
+            Some(PathBuf::from("/srv/bitwig/bin/bitwig.jar"))
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_launcher_install_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("bitwig-studio");
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+
+        assert_eq!(
+            launcher_install_root(&root.join("bin/bitwig.jar")),
+            Some(root.clone())
+        );
+        assert_eq!(launcher_install_root(&root.join("bitwig-studio")), Some(root.clone()));
+        assert_eq!(launcher_install_root(&root), Some(root));
+    }
+
     #[test]
     fn test_extract_version() {
         assert_eq!(extract_version(Path::new("/opt/bitwig-studio/5.2")), "5.2");
@@ -515,4 +931,158 @@ mod tests {
         let paths = get_default_search_paths();
         assert!(!paths.is_empty());
     }
+
+    #[test]
+    fn test_get_search_paths_reports_existence() {
+        let report = get_search_paths();
+        assert!(report.len() >= get_default_search_paths().len());
+        for info in report {
+            assert_eq!(info.exists, Path::new(&info.path).exists());
+        }
+    }
+
+    #[test]
+    fn test_expand_path() {
+        if let Some(home) = user::home_dir() {
+            assert_eq!(expand_path("~/bitwig"), home.join("bitwig"));
+            assert_eq!(expand_path("~"), home);
+        }
+        std::env::set_var("BTM_TEST_PREFIX", "/custom/prefix");
+        assert_eq!(
+            expand_path("$BTM_TEST_PREFIX/bitwig"),
+            PathBuf::from("/custom/prefix/bitwig")
+        );
+        assert_eq!(
+            expand_path("${BTM_TEST_PREFIX}/bitwig"),
+            PathBuf::from("/custom/prefix/bitwig")
+        );
+    }
+
+    #[test]
+    fn test_custom_search_path_is_scanned() {
+        let dir = tempfile::tempdir().unwrap();
+        let install = dir.path().join("Bitwig Studio 5.3");
+        std::fs::create_dir_all(install.join("bin")).unwrap();
+        std::fs::write(install.join("bin/bitwig.jar"), b"jar").unwrap();
+
+        let custom = vec![install.to_string_lossy().to_string()];
+        let paths = get_custom_search_paths(&custom, &[]);
+        assert_eq!(paths.len(), 1);
+
+        let installations = scan_search_paths(paths);
+        assert_eq!(installations.len(), 1);
+        assert_eq!(installations[0].version, "5.3");
+        assert_eq!(installations[0].jar_path, install.join("bin/bitwig.jar"));
+    }
+
+    #[test]
+    fn test_custom_search_path_dedups_defaults() {
+        let defaults = vec![SearchPath {
+            path: PathBuf::from("/opt/bitwig-studio"),
+            installation_type: InstallationType::System,
+        }];
+        let custom = vec!["/opt/bitwig-studio".to_string(), "/opt/bitwig-studio".to_string()];
+        assert!(get_custom_search_paths(&custom, &defaults).is_empty());
+    }
+
+    #[test]
+    fn test_version_from_platform_layouts() {
+        let temp = tempfile::tempdir().unwrap();
+        let write = |path: &Path, content: &[u8]| {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+
+        // Linux: <root>/bin/bitwig.jar with <root>/resources/build-info.sh
+        let linux = temp.path().join("bitwig-studio");
+        write(
+            &linux.join("resources/build-info.sh"),
+            b"BITWIG_STUDIO_VERSION_NAME=\"5.2.4\"\n",
+        );
+        let jar = linux.join("bin/bitwig.jar");
+        assert_eq!(get_version_from_build_info(&jar), Some("5.2.4".to_string()));
+
+        // macOS: Contents/app/bin/bitwig.jar with the version in Contents/Info.plist
+        let contents = temp.path().join("Bitwig Studio.app/Contents");
+        write(
+            &contents.join("Info.plist"),
+            b"<dict>\n\t<key>CFBundleShortVersionString</key>\n\t<string>5.1.9</string>\n</dict>",
+        );
+        let jar = contents.join("app/bin/bitwig.jar");
+        assert_eq!(get_version_from_build_info(&jar), Some("5.1.9".to_string()));
+
+        // build-info.sh in Contents/Resources wins over Info.plist
+        write(
+            &contents.join("Resources/build-info.sh"),
+            b"BITWIG_STUDIO_VERSION_NAME=5.1.9 Beta 2\n",
+        );
+        assert_eq!(get_version_from_build_info(&jar), Some("5.1.9 Beta 2".to_string()));
+
+        // Windows: <root>\bin\bitwig.jar with the version resource in Bitwig Studio.exe
+        let windows = temp.path().join("Bitwig Studio");
+        let utf16 = |s: &str| s.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+        let mut exe = b"MZ\x90\x00".to_vec();
+        exe.extend(utf16("ProductVersion\0"));
+        exe.extend([0, 0]);
+        exe.extend(utf16("6.0.1\0"));
+        write(&windows.join("Bitwig Studio.exe"), &exe);
+        let jar = windows.join("bin/bitwig.jar");
+        assert_eq!(get_version_from_build_info(&jar), Some("6.0.1".to_string()));
+
+        assert_eq!(get_version_from_build_info(&temp.path().join("x/bin/bitwig.jar")), None);
+    }
+
+    #[test]
+    fn test_read_version_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("latest-launched-version.txt");
+
+        for (content, expected) in [
+            ("5.2.4\n", Some("5.2.4")),
+            ("  5.3 Beta 2 \n", Some("5.3 Beta 2")),
+            ("6.0-rc1", Some("6.0-rc1")),
+            ("5.2\n../../etc", None),
+            ("garbage", None),
+            ("\n", None),
+        ] {
+            std::fs::write(&file, content).unwrap();
+            assert_eq!(read_version_file(&file).as_deref(), expected, "{:?}", content);
+        }
+        assert_eq!(read_version_file(&dir.path().join("missing.txt")), None);
+    }
+
+    #[test]
+    fn test_jar_path_from_process_info() {
+        let maps = "7f3a1c000000-7f3a1c200000 r--s 00000000 08:01 1837 \
+                    /home/me/apps/bitwig-studio/bin/bitwig.jar\n";
+        assert_eq!(
+            jar_path_from_process_info(maps),
+            Some(PathBuf::from("/home/me/apps/bitwig-studio/bin/bitwig.jar"))
+        );
+
+        let cmdline = "/opt/custom/bitwig/lib/jre/bin/java\0-Xmx4g\0-cp\0\
+                       /opt/custom/bitwig/bin/extra.jar:/opt/custom/bitwig/bin/bitwig.jar\0\
+                       com.bitwig.flt.app.BitwigStudioMain\0";
+        assert_eq!(
+            jar_path_from_process_info(cmdline),
+            Some(PathBuf::from("/opt/custom/bitwig/bin/bitwig.jar"))
+        );
+
+        let lsof = "p4211\nn/Applications/Bitwig Studio.app/Contents/app/bin/bitwig.jar\n";
+        let jar = jar_path_from_process_info(lsof).unwrap();
+        assert_eq!(
+            jar,
+            PathBuf::from("/Applications/Bitwig Studio.app/Contents/app/bin/bitwig.jar")
+        );
+        assert_eq!(
+            install_root_from_jar(&jar),
+            Some(PathBuf::from("/Applications/Bitwig Studio.app"))
+        );
+        assert_eq!(
+            install_root_from_jar(Path::new("/opt/custom/bitwig/bin/bitwig.jar")),
+            Some(PathBuf::from("/opt/custom/bitwig"))
+        );
+
+        assert_eq!(jar_path_from_process_info("/usr/bin/java\0-jar\0other.jar\0"), None);
+    }
 }
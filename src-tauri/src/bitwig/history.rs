@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Size at which the patch history is rotated to `<name>.1`
+pub const MAX_PATCH_HISTORY_BYTES: u64 = 1024 * 1024;
+
+/// One patch or restore operation, stored as a line of JSON
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PatchLogEntry {
+    /// Unix timestamp (seconds) when the operation started
+    pub ts: u64,
+    /// "patch" or "restore"
+    pub op: String,
+    pub jar_path: PathBuf,
+    /// "ok" or "error"
+    pub result: String,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Path the history is moved to when it grows past the size cap
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+/// Append an entry, rotating the file first if it has reached `max_bytes`
+pub fn append_patch_log(path: &Path, entry: &PatchLogEntry, max_bytes: u64) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() >= max_bytes) {
+        fs::rename(path, rotated_path(path))?;
+    }
+
+    let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Read every entry, oldest first, including the rotated file
+/// Lines that don't parse (e.g. a partial write) are skipped
+pub fn read_patch_log(path: &Path) -> Vec<PatchLogEntry> {
+    [rotated_path(path), path.to_path_buf()]
+        .iter()
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(op: &str, error: Option<&str>) -> PatchLogEntry {
+        PatchLogEntry {
+            ts: 1_700_000_000,
+            op: op.to_string(),
+            jar_path: PathBuf::from("/opt/bitwig-studio/bin/bitwig.jar"),
+            result: if error.is_some() { "error" } else { "ok" }.to_string(),
+            duration_ms: 1250,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_patch_log_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patch-history.jsonl");
+
+        append_patch_log(&path, &entry("patch", None), MAX_PATCH_HISTORY_BYTES).unwrap();
+        append_patch_log(&path, &entry("restore", Some("denied")), MAX_PATCH_HISTORY_BYTES)
+            .unwrap();
+
+        assert_eq!(
+            read_patch_log(&path),
+            vec![entry("patch", None), entry("restore", Some("denied"))]
+        );
+    }
+
+    #[test]
+    fn test_patch_log_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patch-history.jsonl");
+
+        append_patch_log(&path, &entry("patch", None), 1).unwrap();
+        append_patch_log(&path, &entry("restore", None), 1).unwrap();
+
+        assert!(rotated_path(&path).exists());
+        assert_eq!(
+            read_patch_log(&path),
+            vec![entry("patch", None), entry("restore", None)]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod detector;
+pub mod history;
 pub mod patcher;
 
 pub use detector::*;
+pub use history::*;
 pub use patcher::*;
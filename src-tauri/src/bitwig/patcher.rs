@@ -1,16 +1,46 @@
-use sha2::{Digest, Sha256};
 use crate::log_event;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
 use thiserror::Error;
 
-// Bitwig Theme Editor release URL for patching
-const PATCHER_JAR_URL: &str = "https://github.com/Berikai/bitwig-theme-editor/releases/download/2.2.0/bitwig-theme-editor-2.2.0.jar";
-const PATCHER_JAR_NAME: &str = "bitwig-theme-editor-2.2.0.jar";
-// SHA256 checksum of the patcher JAR for security verification
+// Bitwig Theme Editor release used for patching; a macro so the URL and JAR name below can
+// be built from it at compile time
+macro_rules! patcher_version {
+    () => {
+        "2.2.0"
+    };
+}
+const PATCHER_VERSION: &str = patcher_version!();
+const PATCHER_JAR_NAME: &str = concat!("bitwig-theme-editor-", patcher_version!(), ".jar");
+const PATCHER_JAR_URL: &str = concat!(
+    "https://github.com/Berikai/bitwig-theme-editor/releases/download/",
+    patcher_version!(),
+    "/bitwig-theme-editor-",
+    patcher_version!(),
+    ".jar"
+);
+// SHA256 checksum of the patcher JAR for security verification; update with the version
 const PATCHER_JAR_SHA256: &str = "a3d90aed113cc92cc9f2c8ebb086a54f82f6e7edf70afac34d3fe378e9732e2d";
+// Number of file hashes kept by the checksum cache
+const CHECKSUM_CACHE_SIZE: usize = 8;
+
+/// A previously computed hash, valid while the file's mtime and size are unchanged
+struct ChecksumEntry {
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+    checksum: String,
+}
+
+/// Recently computed JAR hashes, most recent last
+static CHECKSUM_CACHE: Mutex<Vec<ChecksumEntry>> = Mutex::new(Vec::new());
 
 #[derive(Error, Debug)]
 pub enum PatchError {
@@ -58,9 +88,83 @@ pub enum PatchError {
 
     #[error("Shell argument contains invalid characters")]
     InvalidShellArgument,
+
+    #[error("Safe mode is on: {0}")]
+    SafeModeBlocked(String),
+}
+
+/// Refuse to elevate while safe mode is on
+fn ensure_elevation_allowed() -> Result<(), PatchError> {
+    elevation_allowed(crate::settings::is_safe_mode())
+}
+
+fn elevation_allowed(safe_mode: bool) -> Result<(), PatchError> {
+    if safe_mode {
+        log_event("patcher: elevation blocked by safe mode");
+        return Err(PatchError::SafeModeBlocked(
+            "elevation is disabled, only user-writable JARs can be patched".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn patcher_download_allowed(safe_mode: bool) -> Result<(), PatchError> {
+    if safe_mode {
+        log_event("patcher: patcher download blocked by safe mode");
+        return Err(PatchError::SafeModeBlocked(
+            "the patcher download is disabled and no cached patcher was found".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Look up a cached hash for the file, if its mtime and size still match
+fn cached_checksum(path: &Path, modified: SystemTime, size: u64) -> Option<String> {
+    let cache = CHECKSUM_CACHE.lock().ok()?;
+    cache
+        .iter()
+        .find(|e| e.path == path && e.modified == modified && e.size == size)
+        .map(|e| e.checksum.clone())
 }
 
-/// Calculate SHA256 hash of a file
+/// Remember a computed hash, replacing any older entry for the same path
+fn store_checksum(path: &Path, modified: SystemTime, size: u64, checksum: &str) {
+    let Ok(mut cache) = CHECKSUM_CACHE.lock() else {
+        return;
+    };
+    cache.retain(|e| e.path != path);
+    if cache.len() >= CHECKSUM_CACHE_SIZE {
+        cache.remove(0);
+    }
+    cache.push(ChecksumEntry {
+        path: path.to_path_buf(),
+        modified,
+        size,
+        checksum: checksum.to_string(),
+    });
+}
+
+/// Hash a file for display, cached by (path, mtime, size) so status polling doesn't
+/// re-read large JARs
+/// A file rewritten with the same mtime and size keeps its old hash, so integrity checks
+/// must use `calculate_checksum` instead
+fn display_checksum(path: &Path) -> Result<String, PatchError> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified().ok();
+    if let Some(modified) = modified {
+        if let Some(checksum) = cached_checksum(path, modified, metadata.len()) {
+            return Ok(checksum);
+        }
+    }
+
+    let checksum = calculate_checksum(path)?;
+    if let Some(modified) = modified {
+        store_checksum(path, modified, metadata.len(), &checksum);
+    }
+    Ok(checksum)
+}
+
+/// Calculate SHA256 hash of a file, always reading its current contents
 pub fn calculate_checksum(path: &Path) -> Result<String, PatchError> {
     let mut file = fs::File::open(path)?;
     let mut hasher = Sha256::new();
@@ -140,16 +244,18 @@ fn verify_patcher_jar(jar_path: &Path) -> Result<(), PatchError> {
     Ok(())
 }
 
+/// Get the directory holding the manager's JAR backups for all installations
+pub fn get_backups_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("bitwig-theme-manager").join("backups"))
+}
+
 fn manager_backup_dir(jar_path: &Path) -> Result<PathBuf, PatchError> {
-    let cache_dir = dirs::cache_dir()
+    let backups_root = get_backups_root()
         .ok_or_else(|| PatchError::DownloadFailed("Could not determine cache directory".to_string()))?;
     let mut hasher = Sha256::new();
     hasher.update(jar_path.to_string_lossy().as_bytes());
     let hash = hex::encode(hasher.finalize());
-    Ok(cache_dir
-        .join("bitwig-theme-manager")
-        .join("backups")
-        .join(hash))
+    Ok(backups_root.join(hash))
 }
 
 fn create_manager_backup(jar_path: &Path) -> Result<PathBuf, PatchError> {
@@ -355,17 +461,176 @@ pub fn has_backup(jar_path: &Path) -> bool {
     get_backup_path(jar_path).exists()
 }
 
+/// Fixed cost of a patch run: starting Java and the patcher
+const PATCH_BASE_SECONDS: u64 = 5;
+
+/// JAR bytes copied, hashed and rewritten per second, roughly
+const PATCH_BYTES_PER_SECOND: u64 = 20 * 1024 * 1024;
+
+/// Time to download the patcher when it isn't cached yet
+const PATCHER_DOWNLOAD_SECONDS: u64 = 5;
+
+/// What patching a JAR will take, for a pre-flight dialog
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PatchRequirements {
+    pub jar_size_bytes: u64,
+    /// Free space needed next to the JAR for the backup; 0 when one already exists
+    pub backup_space_needed: u64,
+    pub needs_elevation: bool,
+    pub java_available: bool,
+    pub patcher_downloaded: bool,
+    /// Rough wall-clock estimate, including the patcher download if needed
+    pub estimated_seconds: u64,
+}
+
+/// Gather what patching `jar_path` will need, without changing anything
+pub fn patch_requirements(jar_path: &Path) -> Result<PatchRequirements, PatchError> {
+    patch_requirements_with(jar_path, has_java(), get_patcher_status().installed)
+}
+
+fn patch_requirements_with(
+    jar_path: &Path,
+    java_available: bool,
+    patcher_downloaded: bool,
+) -> Result<PatchRequirements, PatchError> {
+    if !jar_path.is_file() {
+        return Err(PatchError::JarNotFound(jar_path.to_path_buf()));
+    }
+
+    let jar_size_bytes = fs::metadata(jar_path)?.len();
+    let backup_space_needed = if has_backup(jar_path) { 0 } else { jar_size_bytes };
+
+    // The JAR is read for the backup and checksum, then rewritten by the patcher
+    let estimated_seconds = PATCH_BASE_SECONDS
+        + (jar_size_bytes * 3).div_ceil(PATCH_BYTES_PER_SECOND)
+        + if patcher_downloaded { 0 } else { PATCHER_DOWNLOAD_SECONDS };
+
+    Ok(PatchRequirements {
+        jar_size_bytes,
+        backup_space_needed,
+        needs_elevation: !can_write(jar_path),
+        java_available,
+        patcher_downloaded,
+        estimated_seconds,
+    })
+}
+
+/// Detailed patch state combining the marker file with the JAR contents and backups
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchStatusDetails {
+    pub marker_present: bool,
+    pub jar_actually_patched: bool,
+    pub backup_present: bool,
+    pub backup_valid: bool,
+}
+
+/// Check whether the JAR no longer matches the unpatched original recorded at backup time
+///
+/// The checksum written next to the backup is the only record of the vanilla JAR, so the
+/// manager backup's is preferred and the legacy one used as a fallback. Without either,
+/// `BackupNotFound` is returned since the state can't be told.
+pub fn jar_differs_from_original(jar_path: &Path) -> Result<bool, PatchError> {
+    let manager_checksum = find_latest_manager_backup(jar_path)
+        .ok()
+        .map(|backup| backup.with_extension("jar.sha256"))
+        .filter(|checksum| checksum.exists());
+    let checksum_path = match manager_checksum {
+        Some(checksum) => checksum,
+        None => {
+            let legacy = get_checksum_path(jar_path);
+            if !legacy.exists() {
+                return Err(PatchError::BackupNotFound(get_backup_path(jar_path)));
+            }
+            legacy
+        }
+    };
+
+    let original = fs::read_to_string(&checksum_path)?;
+    Ok(display_checksum(jar_path)? != original.trim())
+}
+
+/// Check whether a backup's recorded checksum matches its content
+fn backup_checksum_matches(backup_path: &Path, checksum_path: &Path) -> bool {
+    let expected = match fs::read_to_string(checksum_path) {
+        Ok(expected) => expected,
+        Err(_) => return false,
+    };
+    calculate_checksum(backup_path)
+        .map(|actual| expected.trim() == actual)
+        .unwrap_or(false)
+}
+
+/// Inspect the marker, the JAR itself, and both backup locations
+///
+/// The JAR counts as patched when its hash differs from the recorded original, which
+/// also holds after a Bitwig update replaced it; the hash is cached by mtime and size,
+/// so repeated scans don't re-read it.
+pub fn patch_status_detailed(jar_path: &Path) -> PatchStatusDetails {
+    let marker_present = get_marker_path(jar_path).exists();
+
+    let manager_backup = find_latest_manager_backup(jar_path).ok();
+    let manager_checksum = manager_backup
+        .as_ref()
+        .map(|backup| backup.with_extension("jar.sha256"));
+    let manager_valid = match (&manager_backup, &manager_checksum) {
+        (Some(backup), Some(checksum)) => backup_checksum_matches(backup, checksum),
+        _ => false,
+    };
+
+    let legacy_backup = get_backup_path(jar_path);
+    let legacy_present = legacy_backup.exists();
+    let legacy_valid =
+        legacy_present && backup_checksum_matches(&legacy_backup, &get_checksum_path(jar_path));
+
+    PatchStatusDetails {
+        marker_present,
+        jar_actually_patched: jar_differs_from_original(jar_path).unwrap_or(false),
+        backup_present: manager_backup.is_some() || legacy_present,
+        backup_valid: manager_valid || legacy_valid,
+    }
+}
+
 /// Get the directory where we cache the patcher JAR
 fn get_patcher_cache_dir() -> Option<PathBuf> {
     dirs::cache_dir().map(|d| d.join("bitwig-theme-manager").join("patcher"))
 }
 
 /// Get the path to the cached patcher JAR
-#[allow(dead_code)]
-fn get_patcher_jar_path() -> Option<PathBuf> {
+pub fn get_patcher_jar_path() -> Option<PathBuf> {
     get_patcher_cache_dir().map(|d| d.join(PATCHER_JAR_NAME))
 }
 
+/// State of the cached patcher JAR
+#[derive(Debug, Clone, Serialize)]
+pub struct PatcherStatus {
+    pub installed: bool,
+    /// Patcher version the manager expects
+    pub version: String,
+    pub checksum_ok: bool,
+    pub path: Option<String>,
+}
+
+/// Check the cached patcher JAR without downloading or deleting it
+pub fn get_patcher_status() -> PatcherStatus {
+    patcher_status_at(get_patcher_jar_path())
+}
+
+fn patcher_status_at(jar_path: Option<PathBuf>) -> PatcherStatus {
+    let installed = jar_path.as_deref().is_some_and(Path::is_file);
+    let checksum_ok = installed
+        && jar_path
+            .as_deref()
+            .and_then(|p| display_checksum(p).ok())
+            .is_some_and(|c| c == PATCHER_JAR_SHA256);
+
+    PatcherStatus {
+        installed,
+        version: PATCHER_VERSION.to_string(),
+        checksum_ok,
+        path: jar_path.map(|p| p.to_string_lossy().to_string()),
+    }
+}
+
 /// Find Java executable path
 /// Searches: Bitwig's bundled JRE, PATH, common installation directories, JAVA_HOME
 pub fn find_java() -> Option<PathBuf> {
@@ -509,7 +774,7 @@ fn find_bitwig_bundled_java() -> Option<PathBuf> {
 
         // Also check user home
         let mut all_paths = search_paths.to_vec();
-        if let Some(home) = dirs::home_dir() {
+        if let Some(home) = crate::user::home_dir() {
             all_paths.push(home.join(".local/share/bitwig-studio"));
         }
 
@@ -571,7 +836,7 @@ fn find_bitwig_bundled_java() -> Option<PathBuf> {
             PathBuf::from("/Applications/Bitwig Studio.app"),
         ];
 
-        if let Some(home) = dirs::home_dir() {
+        if let Some(home) = crate::user::home_dir() {
             let user_app = home.join("Applications/Bitwig Studio.app");
             if user_app.exists() {
                 let java_path = user_app.join("Contents/PlugIns/jre/Contents/Home/bin/java");
@@ -651,6 +916,8 @@ pub fn ensure_patcher_available() -> Result<PathBuf, PatchError> {
         }
     }
 
+    patcher_download_allowed(crate::settings::is_safe_mode())?;
+
     // Create cache directory
     fs::create_dir_all(&cache_dir)?;
 
@@ -700,9 +967,142 @@ pub fn ensure_patcher_available() -> Result<PathBuf, PatchError> {
     }
 }
 
+/// Number of hard links to a file (always 1 where this can't be determined)
+pub fn hardlink_count(path: &Path) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        path.metadata().map(|m| m.nlink()).unwrap_or(1)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        1
+    }
+}
+
+/// Sibling path a hardlinked file is copied to before it replaces the link
+fn unlink_temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.unlink", file_name))
+}
+
+/// Give a hardlinked file its own copy of the data so writing to it
+/// doesn't change the other linked paths
+/// Returns true if a link was broken
+fn break_hardlink(path: &Path) -> Result<bool, PatchError> {
+    let links = hardlink_count(path);
+    if links <= 1 {
+        return Ok(false);
+    }
+
+    log_event(&format!(
+        "patcher: {} has {} hard links, copying before patching",
+        path.to_string_lossy(),
+        links
+    ));
+
+    let temp_path = unlink_temp_path(path);
+
+    // Copy within the same directory so the rename replaces the link atomically
+    fs::copy(path, &temp_path)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    Ok(true)
+}
+
+/// Result of a patcher run, read from the bitwig-theme-editor CLI output
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PatcherOutcome {
+    /// The patcher modified the JAR on this run
+    pub patched: bool,
+    /// The patcher found the JAR already patched and left it alone
+    pub already_patched: bool,
+    /// Bitwig version the patcher reported, if any
+    pub version_detected: Option<String>,
+    /// The patcher's final output line
+    pub message: String,
+}
+
+/// Bitwig version as printed by the patcher, e.g. "Detected Bitwig Studio version: 6.0 Beta 6"
+static PATCHER_VERSION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)bitwig(?:\s+studio)?(?:\s+version)?\s*[:=]?\s*v?(\d+\.\d+(?:\.\d+)?(?:[\s-]+(?:beta|rc)\s*\d+)?)",
+    )
+    .unwrap()
+});
+
+/// The patcher's messages for a JAR it has already patched
+static PATCHER_ALREADY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\balready\s+patched\b").unwrap());
+
+/// The patcher's messages for a completed patch
+static PATCHER_SUCCESS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:successfully\s+patched|patched|patch\s+applied)\b").unwrap()
+});
+
+/// Error lines, matched as whole words so "unsuccessful" is read as a failure, not a success
+static PATCHER_FAILURE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^error\b|\b(?:exception|failed|failure|unsuccessful(?:ly)?)\b").unwrap()
+});
+
+/// Parse the bitwig-theme-editor CLI output into a structured outcome
+/// The exit status is authoritative: a failed run is never reported as patched. On a
+/// successful exit, an explicit success line outweighs warnings about failed side steps
+pub fn parse_patcher_output(stdout: &str, stderr: &str, exit_success: bool) -> PatcherOutcome {
+    let mut already_patched = false;
+    let mut success_line = false;
+    let mut failure_line = false;
+    let mut version_detected = None;
+    let mut message = String::new();
+
+    for line in stdout.lines().chain(stderr.lines()) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if PATCHER_ALREADY_RE.is_match(line) {
+            already_patched = true;
+        } else if PATCHER_SUCCESS_RE.is_match(line) {
+            success_line = true;
+        } else if PATCHER_FAILURE_RE.is_match(line) {
+            failure_line = true;
+        }
+
+        if version_detected.is_none() {
+            version_detected = PATCHER_VERSION_RE
+                .captures(line)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().to_string());
+        }
+
+        message = line.to_string();
+    }
+
+    let patched = exit_success && !already_patched && (success_line || !failure_line);
+
+    PatcherOutcome {
+        patched,
+        already_patched,
+        version_detected,
+        message,
+    }
+}
+
 /// Run the bitwig-theme-editor patcher on a JAR file in CLI mode (no GUI)
 /// The patcher accepts the JAR path as argument and patches it directly
-fn run_patcher_process(bitwig_jar_path: &Path, home: &str, user: &str) -> Result<(String, String), PatchError> {
+fn run_patcher_process(
+    bitwig_jar_path: &Path,
+    home: &str,
+    user: &str,
+) -> Result<PatcherOutcome, PatchError> {
     let java_path = find_java().ok_or(PatchError::JavaNotFound)?;
     let patcher_jar = ensure_patcher_available()?;
     let patcher_jar_str = path_to_str(&patcher_jar)?;
@@ -721,9 +1121,14 @@ fn run_patcher_process(bitwig_jar_path: &Path, home: &str, user: &str) -> Result
 
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    log_event(&format!(
+        "patcher: process stdout='{}' stderr='{}'",
+        stdout, stderr
+    ));
 
-    if output.status.success() {
-        Ok((stdout, stderr))
+    let outcome = parse_patcher_output(&stdout, &stderr, output.status.success());
+    if outcome.patched || outcome.already_patched {
+        Ok(outcome)
     } else {
         Err(PatchError::PatcherFailed(format!(
             "stdout: {}\nstderr: {}",
@@ -732,7 +1137,7 @@ fn run_patcher_process(bitwig_jar_path: &Path, home: &str, user: &str) -> Result
     }
 }
 
-pub fn run_patcher_cli(bitwig_jar_path: &Path) -> Result<(), PatchError> {
+pub fn run_patcher_cli(bitwig_jar_path: &Path) -> Result<PatcherOutcome, PatchError> {
     if !has_java() {
         log_event("patcher: run_patcher_cli failed (no java)");
         return Err(PatchError::JavaNotFound);
@@ -762,17 +1167,16 @@ pub fn run_patcher_cli(bitwig_jar_path: &Path) -> Result<(), PatchError> {
         bitwig_jar_path.to_string_lossy()
     ));
 
-    let (stdout, stderr) = run_patcher_process(bitwig_jar_path, &home, &user)?;
-    if !stdout.contains("already patched") && !stderr.contains("already patched") {
+    break_hardlink(bitwig_jar_path)?;
+
+    let outcome = run_patcher_process(bitwig_jar_path, &home, &user)?;
+    if outcome.patched {
         // Create our marker file for tracking
         let marker_path = get_marker_path(bitwig_jar_path);
         fs::write(&marker_path, "patched")?;
     }
-    log_event(&format!(
-        "patcher: run_patcher_cli ok stdout='{}' stderr='{}'",
-        stdout, stderr
-    ));
-    Ok(())
+    log_event(&format!("patcher: run_patcher_cli ok {:?}", outcome));
+    Ok(outcome)
 }
 
 /// Create a secure temporary script file with unique name
@@ -806,227 +1210,6 @@ fn create_secure_temp_script(name_prefix: &str, content: &str) -> Result<PathBuf
     Ok(script_path)
 }
 
-/// Run patcher with elevated privileges using pkexec (Unix) or UAC (Windows)
-pub fn run_patcher_cli_elevated(bitwig_jar_path: &Path) -> Result<(), PatchError> {
-    let java_path = find_java().ok_or_else(|| {
-        log_event("patcher: run_patcher_cli_elevated failed (no java)");
-        PatchError::JavaNotFound
-    })?;
-
-    let patcher_jar = ensure_patcher_available()?;
-
-    log_event(&format!(
-        "patcher: run_patcher_cli_elevated start -> {}",
-        bitwig_jar_path.to_string_lossy()
-    ));
-
-    // Get user home and name (platform-specific)
-    #[cfg(target_os = "windows")]
-    #[allow(unused_variables)]
-    let (home, user, logname) = {
-        let home = std::env::var("USERPROFILE").unwrap_or_else(|_| {
-            std::env::var("HOME").unwrap_or_default()
-        });
-        let user = std::env::var("USERNAME").unwrap_or_default();
-        (home.clone(), user.clone(), user)
-    };
-    #[cfg(not(target_os = "windows"))]
-    let (home, user, logname) = {
-        let home = std::env::var("HOME").unwrap_or_default();
-        let user = std::env::var("USER").unwrap_or_default();
-        let logname = std::env::var("LOGNAME").unwrap_or_else(|_| user.clone());
-        (home, user, logname)
-    };
-
-    let backup_dir = manager_backup_dir(bitwig_jar_path)?;
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-    let backup_path = backup_dir.join(format!("{}.jar", timestamp));
-    let checksum_path = backup_dir.join(format!("{}.jar.sha256", timestamp));
-
-    log_event(&format!(
-        "patcher: elevating with HOME='{}' USER='{}'",
-        home, user
-    ));
-
-    #[cfg(target_os = "windows")]
-    let output = {
-        // On Windows, create a PowerShell script for elevation
-        let temp_dir = std::env::temp_dir().join("bitwig-theme-manager");
-        fs::create_dir_all(&temp_dir)?;
-        fs::create_dir_all(&backup_dir)?;
-
-        let id: u64 = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos() as u64)
-            .unwrap_or(0);
-
-        let script_path = temp_dir.join(format!("patch-elevated-{}.ps1", id));
-
-        // Escape paths for PowerShell
-        let java_path_escaped = java_path.to_string_lossy().replace("'", "''");
-        let patcher_jar_escaped = patcher_jar.to_string_lossy().replace("'", "''");
-        let bitwig_jar_escaped = bitwig_jar_path.to_string_lossy().replace("'", "''");
-        let backup_path_escaped = backup_path.to_string_lossy().replace("'", "''");
-        let checksum_path_escaped = checksum_path.to_string_lossy().replace("'", "''");
-        let home_escaped = home.replace("'", "''");
-        let user_escaped = user.replace("'", "''");
-
-        let script_content = format!(
-            r#"$ErrorActionPreference = 'Stop'
-Copy-Item -Path '{bitwig_jar}' -Destination '{backup_path}' -Force
-$hash = (Get-FileHash -Path '{bitwig_jar}' -Algorithm SHA256).Hash.ToLower()
-Set-Content -Path '{checksum_path}' -Value $hash -NoNewline
-& '{java_path}' '-Duser.home={home}' '-Duser.name={user}' '-Duser.dir={home}' '-jar' '{patcher_jar}' '{bitwig_jar}'
-"#,
-            java_path = java_path_escaped,
-            patcher_jar = patcher_jar_escaped,
-            bitwig_jar = bitwig_jar_escaped,
-            backup_path = backup_path_escaped,
-            checksum_path = checksum_path_escaped,
-            home = home_escaped,
-            user = user_escaped,
-        );
-
-        fs::write(&script_path, &script_content)?;
-
-        let script_path_str = script_path.to_string_lossy().replace("'", "''");
-
-        // Use PowerShell to run the script with elevation
-        let ps_command = format!(
-            "Start-Process -FilePath 'powershell' -ArgumentList '-NoProfile', '-ExecutionPolicy', 'Bypass', '-File', '{}' -Verb RunAs -Wait -WindowStyle Hidden",
-            script_path_str
-        );
-
-        let output = Command::new("powershell")
-            .args(["-NoProfile", "-NonInteractive", "-Command", &ps_command])
-            .output()?;
-
-        // Clean up script
-        let _ = fs::remove_file(&script_path);
-        output
-    };
-
-    #[cfg(not(target_os = "windows"))]
-    let output = {
-        // Sanitize all shell arguments
-        let home_safe = sanitize_shell_arg(&home)?;
-        let user_safe = sanitize_shell_arg(&user)?;
-        let logname_safe = sanitize_shell_arg(&logname)?;
-
-        // Sanitize path arguments
-        let backup_dir_safe = sanitize_shell_arg(&backup_dir.to_string_lossy())?;
-        let backup_path_safe = sanitize_shell_arg(&backup_path.to_string_lossy())?;
-        let checksum_path_safe = sanitize_shell_arg(&checksum_path.to_string_lossy())?;
-        let bitwig_jar_safe = sanitize_shell_arg(&bitwig_jar_path.to_string_lossy())?;
-        let patcher_jar_safe = sanitize_shell_arg(&patcher_jar.to_string_lossy())?;
-        let java_path_safe = sanitize_shell_arg(&java_path.to_string_lossy())?;
-
-        // Create a script that runs the patcher with java
-        let script_content = format!(
-            "#!/bin/bash\nset -e\nexport HOME='{}'\nexport USER='{}'\nexport LOGNAME='{}'\nmkdir -p '{}'\ncp '{}' '{}'\nsha256sum '{}' | cut -d' ' -f1 > '{}'\n'{}' -Duser.home='{}' -Duser.name='{}' -Duser.dir='{}' -jar '{}' '{}'\n",
-            home_safe,
-            user_safe,
-            logname_safe,
-            backup_dir_safe,
-            bitwig_jar_safe,
-            backup_path_safe,
-            bitwig_jar_safe,
-            checksum_path_safe,
-            java_path_safe,
-            home_safe,
-            user_safe,
-            home_safe,
-            patcher_jar_safe,
-            bitwig_jar_safe
-        );
-
-        let script_path = create_secure_temp_script("patch-cli", &script_content)?;
-
-        // Run with pkexec
-        let output = Command::new("pkexec")
-            .arg("bash")
-            .arg(&script_path)
-            .output()?;
-
-        // Clean up script
-        let _ = fs::remove_file(&script_path);
-        output
-    };
-
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    if output.status.success() {
-        // Create our marker file for tracking
-        let marker_path = get_marker_path(bitwig_jar_path);
-        // Need to write marker with elevation too if in system location
-        if !can_write(&marker_path) {
-            #[cfg(target_os = "windows")]
-            {
-                // On Windows, use PowerShell with elevation to write marker
-                let marker_path_escaped = marker_path.to_string_lossy().replace("'", "''");
-                let ps_command = format!(
-                    "Start-Process -FilePath 'powershell' -ArgumentList '-NoProfile', '-Command', \"Set-Content -Path '{}' -Value 'patched'\" -Verb RunAs -Wait -WindowStyle Hidden",
-                    marker_path_escaped
-                );
-                let marker_result = Command::new("powershell")
-                    .args(["-NoProfile", "-NonInteractive", "-Command", &ps_command])
-                    .output();
-                if let Err(e) = marker_result {
-                    log_event(&format!("patcher: warning - failed to write marker: {}", e));
-                }
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                let marker_path_safe = sanitize_shell_arg(&marker_path.to_string_lossy())?;
-                let marker_script = format!(
-                    "#!/bin/bash\necho 'patched' > '{}'\n",
-                    marker_path_safe
-                );
-                let marker_script_path = create_secure_temp_script("marker", &marker_script)?;
-                let marker_result = Command::new("pkexec")
-                    .arg("bash")
-                    .arg(&marker_script_path)
-                    .output();
-                let _ = fs::remove_file(&marker_script_path);
-                if let Err(e) = marker_result {
-                    log_event(&format!("patcher: warning - failed to write marker: {}", e));
-                }
-            }
-        } else if let Err(e) = fs::write(&marker_path, "patched") {
-            log_event(&format!("patcher: warning - failed to write marker: {}", e));
-        }
-        log_event(&format!(
-            "patcher: run_patcher_cli_elevated ok stdout='{}' stderr='{}'",
-            stdout, stderr
-        ));
-        Ok(())
-    } else {
-        log_event(&format!(
-            "patcher: run_patcher_cli_elevated failed stdout='{}' stderr='{}'",
-            stdout, stderr
-        ));
-
-        if stderr.contains("dismissed") || output.status.code() == Some(126) {
-            Err(PatchError::ElevationCancelled)
-        } else if stdout.contains("already patched") {
-            let marker_path = get_marker_path(bitwig_jar_path);
-            if let Err(e) = fs::write(&marker_path, "patched") {
-                log_event(&format!("patcher: warning - failed to write marker: {}", e));
-            }
-            Ok(())
-        } else {
-            Err(PatchError::PatcherFailed(format!(
-                "stdout: {}\nstderr: {}",
-                stdout, stderr
-            )))
-        }
-    }
-}
-
 /// Create a headless patching script that uses the patcher's classes
 /// Kept for potential future use
 #[allow(dead_code)]
@@ -1124,6 +1307,117 @@ pub fn has_pkexec() -> bool {
     }
 }
 
+/// Whether elevated operations can be expected to work, checked without prompting
+#[derive(Debug, Clone, Serialize)]
+pub struct ElevationStatus {
+    pub available: bool,
+    /// "pkexec" on Unix, "uac" on Windows
+    pub mechanism: String,
+    /// Why elevation is unavailable, or a caveat when it may not be
+    pub reason: Option<String>,
+}
+
+/// Desktop shells that run their own polkit authentication agent
+const POLKIT_AGENT_SHELLS: &[&str] = &["gnome-shell", "cinnamon", "budgie-panel"];
+
+/// Find a running polkit authentication agent among process names
+/// Agents are named after polkit (e.g. `polkit-gnome-authentication-agent-1`, `lxpolkit`)
+/// apart from the `polkitd` daemon itself; some desktop shells provide one built in
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn find_polkit_agent<'a>(process_names: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    process_names
+        .into_iter()
+        .map(str::trim)
+        .find(|name| {
+            (name.contains("polkit") && *name != "polkitd") || POLKIT_AGENT_SHELLS.contains(name)
+        })
+        .map(str::to_string)
+}
+
+/// Names of running processes, read from /proc
+#[cfg(target_os = "linux")]
+fn running_process_names() -> Vec<String> {
+    fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+                .filter_map(|e| fs::read_to_string(e.path().join("comm")).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check whether elevated patching can work before attempting it
+/// Nothing is run with elevation, so the user is never prompted
+pub fn check_elevation_available() -> ElevationStatus {
+    #[cfg(target_os = "windows")]
+    {
+        let status = |available: bool, reason: Option<&str>| ElevationStatus {
+            available,
+            mechanism: "uac".to_string(),
+            reason: reason.map(str::to_string),
+        };
+        if !has_pkexec() {
+            return status(false, Some("PowerShell is not available to request elevation"));
+        }
+        // EnableLUA = 0 means UAC is switched off and RunAs can't elevate
+        let uac_disabled = Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System",
+                "/v",
+                "EnableLUA",
+            ])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains("0x0"))
+            .unwrap_or(false);
+        if uac_disabled {
+            return status(false, Some("User Account Control is disabled"));
+        }
+        status(true, None)
+    }
+
+    #[cfg(unix)]
+    {
+        let status = |available: bool, reason: Option<String>| ElevationStatus {
+            available,
+            mechanism: "pkexec".to_string(),
+            reason,
+        };
+        if !has_pkexec() {
+            let reason = "pkexec is not installed; install polkit to patch system installs";
+            return status(false, Some(reason.to_string()));
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let names = running_process_names();
+            if find_polkit_agent(names.iter().map(String::as_str)).is_none() {
+                return status(
+                    false,
+                    Some(
+                        "No polkit authentication agent is running, so pkexec can't ask for \
+                         your password. Start your desktop's polkit agent and try again."
+                            .to_string(),
+                    ),
+                );
+            }
+        }
+
+        status(true, None)
+    }
+
+    #[cfg(not(any(unix, target_os = "windows")))]
+    {
+        ElevationStatus {
+            available: false,
+            mechanism: "none".to_string(),
+            reason: Some("Elevation is not supported on this platform".to_string()),
+        }
+    }
+}
+
 /// Execute a shell command with pkexec elevation
 #[cfg(unix)]
 pub fn run_with_pkexec(command: &str, args: &[&str]) -> Result<(), PatchError> {
@@ -1224,7 +1518,7 @@ fn get_patch_sources(jar_path: &Path) -> Vec<PathBuf> {
     sources
 }
 
-fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
+fn patch_via_user_temp(jar_path: &Path) -> Result<PatcherOutcome, PatchError> {
     let temp_dir = std::env::temp_dir().join("bitwig-theme-manager");
     fs::create_dir_all(&temp_dir)?;
 
@@ -1260,13 +1554,10 @@ fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
             source.to_string_lossy()
         ));
 
-        let (stdout, stderr) = run_patcher_process(&temp_jar, &home, &user)?;
-        log_event(&format!(
-            "patcher: run_patcher_cli temp stdout='{}' stderr='{}'",
-            stdout, stderr
-        ));
+        let outcome = run_patcher_process(&temp_jar, &home, &user)?;
+        log_event(&format!("patcher: run_patcher_cli temp {:?}", outcome));
 
-        if stdout.contains("already patched") || stderr.contains("already patched") {
+        if outcome.already_patched {
             continue;
         }
 
@@ -1294,7 +1585,7 @@ fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
                 .output()?;
 
             if output.status.success() {
-                return Ok(());
+                return Ok(outcome);
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
                 if stderr.contains("canceled") || stderr.contains("cancelled") {
@@ -1311,10 +1602,25 @@ fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
             let jar_path_safe = sanitize_shell_arg(&jar_path.to_string_lossy())?;
             let marker_path_safe = sanitize_shell_arg(&marker_path.to_string_lossy())?;
 
+            // A plain cp writes through a hard link; give the JAR its own inode instead by
+            // copying it (keeping owner and mode), filling the copy and renaming it over
+            let copy_back = if hardlink_count(jar_path) > 1 {
+                log_event("patcher: jar is hardlinked, replacing it instead of writing through");
+                let unlink_path = unlink_temp_path(jar_path);
+                let unlink_safe = sanitize_shell_arg(&unlink_path.to_string_lossy())?;
+                format!(
+                    "cp -p '{jar}' '{unlink}'\ncp '{temp}' '{unlink}'\nmv -f '{unlink}' '{jar}'\n",
+                    jar = jar_path_safe,
+                    unlink = unlink_safe,
+                    temp = temp_jar_safe
+                )
+            } else {
+                format!("cp '{}' '{}'\n", temp_jar_safe, jar_path_safe)
+            };
+
             let script_content = format!(
-                "#!/bin/bash\nset -e\ncp '{}' '{}'\necho 'patched' > '{}'\n",
-                temp_jar_safe,
-                jar_path_safe,
+                "#!/bin/bash\nset -e\n{}echo 'patched' > '{}'\n",
+                copy_back,
                 marker_path_safe
             );
 
@@ -1323,7 +1629,7 @@ fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
 
             let result = run_with_pkexec("bash", &[script_path_str]);
             let _ = fs::remove_file(&script_path);
-            return result;
+            return result.map(|()| outcome);
         }
     }
 
@@ -1332,7 +1638,8 @@ fn patch_via_user_temp(jar_path: &Path) -> Result<(), PatchError> {
 
 /// Patch the JAR file with elevation if needed
 /// Uses the bitwig-theme-editor patcher in CLI mode (no GUI)
-pub fn patch_jar_elevated(jar_path: &Path) -> Result<(), PatchError> {
+/// Returns what the patcher reported, including the Bitwig version it detected
+pub fn patch_jar_elevated(jar_path: &Path) -> Result<PatcherOutcome, PatchError> {
     if !jar_path.exists() {
         log_event(&format!(
             "patcher: patch_jar_elevated jar missing {}",
@@ -1341,6 +1648,11 @@ pub fn patch_jar_elevated(jar_path: &Path) -> Result<(), PatchError> {
         return Err(PatchError::JarNotFound(jar_path.to_path_buf()));
     }
 
+    // Bail out before backing up or downloading anything we couldn't use
+    if !can_write(jar_path) {
+        ensure_elevation_allowed()?;
+    }
+
     // Check if Java is available
     if !has_java() {
         log_event("patcher: patch_jar_elevated failed (no java)");
@@ -1440,17 +1752,35 @@ pub fn restore_jar_elevated(jar_path: &Path) -> Result<(), PatchError> {
         "patcher: restore_jar_elevated start -> {}",
         jar_path.to_string_lossy()
     ));
+
+    let source = select_restore_source(find_latest_manager_backup(jar_path).ok(), jar_path)?;
+
     // First try without elevation
-    match restore_from_manager_backup(jar_path) {
+    let result = match &source {
+        RestoreSource::Manager(_) => restore_from_manager_backup(jar_path),
+        RestoreSource::Sidecar(backup) => {
+            log_event(&format!(
+                "patcher: no manager backup, restoring from sidecar {}",
+                backup.to_string_lossy()
+            ));
+            restore_from_backup(jar_path)
+        }
+    };
+
+    match result {
         Ok(()) => {
             log_event("patcher: restore ok");
             Ok(())
         }
         Err(PatchError::Io(ref e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+            ensure_elevation_allowed()?;
             // Try with pkexec
             if has_pkexec() {
                 log_event("patcher: restore needs elevation");
-                let script = create_restore_manager_script(jar_path)?;
+                let script = match source {
+                    RestoreSource::Manager(_) => create_restore_manager_script(jar_path)?,
+                    RestoreSource::Sidecar(_) => create_restore_script(jar_path)?,
+                };
                 let script_str = path_to_str(&script)?;
                 let result = run_with_pkexec("bash", &[script_str]);
                 let _ = fs::remove_file(&script);
@@ -1464,6 +1794,34 @@ pub fn restore_jar_elevated(jar_path: &Path) -> Result<(), PatchError> {
     }
 }
 
+/// Backup an elevated restore reads from
+#[derive(Debug, Clone, PartialEq)]
+enum RestoreSource {
+    /// Backup in the manager's backup directory
+    Manager(PathBuf),
+    /// Legacy `.jar.backup` sidecar next to the JAR
+    Sidecar(PathBuf),
+}
+
+/// Prefer the manager backup, falling back to the sidecar when its checksum verifies
+fn select_restore_source(
+    manager_backup: Option<PathBuf>,
+    jar_path: &Path,
+) -> Result<RestoreSource, PatchError> {
+    if let Some(backup) = manager_backup {
+        return Ok(RestoreSource::Manager(backup));
+    }
+
+    let sidecar = get_backup_path(jar_path);
+    if !sidecar.exists() {
+        return Err(PatchError::BackupNotFound(sidecar));
+    }
+    if !backup_checksum_matches(&sidecar, &get_checksum_path(jar_path)) {
+        return Err(PatchError::ChecksumMismatch);
+    }
+    Ok(RestoreSource::Sidecar(sidecar))
+}
+
 fn create_restore_manager_script(jar_path: &Path) -> Result<PathBuf, PatchError> {
     let backup_path = find_latest_manager_backup(jar_path)?;
     let checksum_path = backup_path.with_extension("jar.sha256");
@@ -1509,8 +1867,7 @@ echo "Restored successfully"
     create_secure_temp_script("restore-manager", &script_content)
 }
 
-/// Create a temporary shell script for restoring with elevated privileges
-#[allow(dead_code)]
+/// Create a temporary shell script for restoring the sidecar backup with elevated privileges
 fn create_restore_script(jar_path: &Path) -> Result<PathBuf, PatchError> {
     let backup_path = get_backup_path(jar_path);
     let marker_path = get_marker_path(jar_path);
@@ -1589,6 +1946,12 @@ mod tests {
     use std::io::Write;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_patcher_release_names_follow_version() {
+        assert_eq!(PATCHER_JAR_NAME, format!("bitwig-theme-editor-{}.jar", PATCHER_VERSION));
+        assert!(PATCHER_JAR_URL.ends_with(&format!("/{}/{}", PATCHER_VERSION, PATCHER_JAR_NAME)));
+    }
+
     #[test]
     fn test_calculate_checksum() {
         let dir = tempdir().unwrap();
@@ -1601,6 +1964,83 @@ mod tests {
         assert_eq!(checksum.len(), 64); // SHA256 produces 64 hex characters
     }
 
+    #[test]
+    fn test_checksum_cache_invalidation() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("bitwig.jar");
+        fs::write(&file_path, b"original contents").unwrap();
+
+        let first = display_checksum(&file_path).unwrap();
+        let metadata = fs::metadata(&file_path).unwrap();
+        let modified = metadata.modified().unwrap();
+        assert_eq!(cached_checksum(&file_path, modified, metadata.len()), Some(first.clone()));
+        assert_eq!(display_checksum(&file_path).unwrap(), first);
+
+        fs::write(&file_path, b"modified, longer contents").unwrap();
+        let metadata = fs::metadata(&file_path).unwrap();
+        let modified = metadata.modified().unwrap();
+        assert_eq!(cached_checksum(&file_path, modified, metadata.len()), None);
+
+        let second = display_checksum(&file_path).unwrap();
+        assert_ne!(second, first);
+        assert_eq!(second, calculate_checksum(&file_path).unwrap());
+
+        // Integrity checks never see a cached hash, even when mtime and size are unchanged
+        store_checksum(&file_path, modified, metadata.len(), "stale");
+        assert_eq!(display_checksum(&file_path).unwrap(), "stale");
+        assert_eq!(calculate_checksum(&file_path).unwrap(), second);
+    }
+
+    #[test]
+    fn test_select_restore_source() {
+        let dir = tempdir().unwrap();
+        let jar_path = dir.path().join("bitwig.jar");
+        let manager = dir.path().join("manager").join("bitwig.jar");
+
+        assert_eq!(
+            select_restore_source(Some(manager.clone()), &jar_path).unwrap(),
+            RestoreSource::Manager(manager)
+        );
+        assert!(matches!(
+            select_restore_source(None, &jar_path),
+            Err(PatchError::BackupNotFound(_))
+        ));
+
+        let sidecar = get_backup_path(&jar_path);
+        fs::write(&sidecar, b"original jar").unwrap();
+        // A sidecar without a recorded checksum can't be verified
+        assert!(matches!(
+            select_restore_source(None, &jar_path),
+            Err(PatchError::ChecksumMismatch)
+        ));
+
+        let checksum = calculate_checksum(&sidecar).unwrap();
+        fs::write(get_checksum_path(&jar_path), checksum).unwrap();
+        assert_eq!(
+            select_restore_source(None, &jar_path).unwrap(),
+            RestoreSource::Sidecar(sidecar)
+        );
+    }
+
+    #[test]
+    fn test_patcher_status() {
+        let dir = tempdir().unwrap();
+        let jar_path = dir.path().join(PATCHER_JAR_NAME);
+
+        let missing = patcher_status_at(Some(jar_path.clone()));
+        assert!(!missing.installed);
+        assert!(!missing.checksum_ok);
+        assert_eq!(missing.version, PATCHER_VERSION);
+
+        fs::write(&jar_path, b"not the patcher").unwrap();
+        let tampered = patcher_status_at(Some(jar_path.clone()));
+        assert!(tampered.installed);
+        assert!(!tampered.checksum_ok);
+        assert!(jar_path.exists());
+
+        assert!(patcher_status_at(None).path.is_none());
+    }
+
     #[test]
     fn test_backup_paths() {
         let jar_path = Path::new("/opt/bitwig-studio/5.2/bin/bitwig.jar");
@@ -1617,4 +2057,168 @@ mod tests {
             Path::new("/opt/bitwig-studio/5.2/bin/bitwig.patched")
         );
     }
+
+    fn write_test_jar(path: &Path, entries: &[&str]) {
+        let file = fs::File::create(path).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        for entry in entries {
+            writer
+                .start_file(*entry, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"class").unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_jar_differs_from_original() {
+        let dir = tempdir().unwrap();
+        let jar = dir.path().join("bitwig.jar");
+        write_test_jar(&jar, &["com/bitwig/Main.class"]);
+
+        assert!(matches!(
+            jar_differs_from_original(&jar),
+            Err(PatchError::BackupNotFound(_))
+        ));
+
+        create_backup(&jar).unwrap();
+        assert!(!jar_differs_from_original(&jar).unwrap());
+
+        write_test_jar(&jar, &["com/bitwig/Main.class", "com/bitwig/Other.class"]);
+        assert!(jar_differs_from_original(&jar).unwrap());
+    }
+
+    #[test]
+    fn test_patch_status_detailed_marker_mismatch() {
+        let dir = tempdir().unwrap();
+        let jar = dir.path().join("bitwig.jar");
+        write_test_jar(&jar, &["com/bitwig/Main.class"]);
+        fs::write(get_marker_path(&jar), "patched").unwrap();
+
+        let status = patch_status_detailed(&jar);
+        assert!(status.marker_present);
+        assert!(!status.jar_actually_patched);
+        assert!(!status.backup_present);
+        assert!(!status.backup_valid);
+    }
+
+    #[test]
+    fn test_parse_patcher_output_success() {
+        let stdout = "Bitwig Theme Editor 2.2.0\n\
+            Detected Bitwig Studio version: 6.0 Beta 6\n\
+            Patching /opt/bitwig-studio/bin/bitwig.jar...\n\
+            Successfully patched bitwig.jar\n";
+        let outcome = parse_patcher_output(stdout, "", true);
+
+        assert!(outcome.patched);
+        assert!(!outcome.already_patched);
+        assert_eq!(outcome.version_detected.as_deref(), Some("6.0 Beta 6"));
+        assert_eq!(outcome.message, "Successfully patched bitwig.jar");
+    }
+
+    #[test]
+    fn test_parse_patcher_output_already_patched() {
+        let outcome = parse_patcher_output("Bitwig 5.2.4\nJAR is already patched.\n", "", false);
+
+        assert!(!outcome.patched);
+        assert!(outcome.already_patched);
+        assert_eq!(outcome.version_detected.as_deref(), Some("5.2.4"));
+    }
+
+    #[test]
+    fn test_parse_patcher_output_failure() {
+        let stderr = "Exception in thread \"main\" java.util.zip.ZipException: \
+            error in opening zip file\n";
+        let outcome = parse_patcher_output("", stderr, true);
+
+        assert!(!outcome.patched);
+        assert!(!outcome.already_patched);
+        assert_eq!(outcome.version_detected, None);
+
+        let outcome = parse_patcher_output("", "", false);
+        assert!(!outcome.patched);
+        assert_eq!(outcome.message, "");
+    }
+
+    #[test]
+    fn test_parse_patcher_output_exit_status_is_authoritative() {
+        let outcome = parse_patcher_output("Patching was unsuccessful\n", "", true);
+        assert!(!outcome.patched);
+
+        let outcome = parse_patcher_output("Successfully patched bitwig.jar\n", "", false);
+        assert!(!outcome.patched);
+
+        let outcome = parse_patcher_output("Patching bitwig.jar...\n", "", true);
+        assert!(outcome.patched);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_break_hardlink_isolates_jar() {
+        let dir = tempdir().unwrap();
+        let jar = dir.path().join("bitwig.jar");
+        let linked = dir.path().join("linked.jar");
+        fs::write(&jar, b"original").unwrap();
+        fs::hard_link(&jar, &linked).unwrap();
+        assert_eq!(hardlink_count(&jar), 2);
+
+        assert!(break_hardlink(&jar).unwrap());
+        assert_eq!(hardlink_count(&jar), 1);
+        assert_eq!(hardlink_count(&linked), 1);
+
+        // Simulate the patcher rewriting the JAR in place
+        fs::write(&jar, b"patched").unwrap();
+        assert_eq!(fs::read(&linked).unwrap(), b"original");
+        assert_eq!(fs::read(&jar).unwrap(), b"patched");
+
+        assert!(!break_hardlink(&jar).unwrap());
+    }
+
+    #[test]
+    fn test_find_polkit_agent() {
+        let names = ["systemd", "polkitd", "bash", "polkit-gnome-au\n"];
+        assert_eq!(find_polkit_agent(names), Some("polkit-gnome-au".to_string()));
+        assert_eq!(find_polkit_agent(["gnome-shell"]), Some("gnome-shell".to_string()));
+        assert_eq!(find_polkit_agent(["lxpolkit"]), Some("lxpolkit".to_string()));
+        assert_eq!(find_polkit_agent(["systemd", "polkitd", "sway"]), None);
+    }
+
+    #[test]
+    fn test_patch_requirements() {
+        let dir = tempdir().unwrap();
+        let jar = dir.path().join("bitwig.jar");
+        fs::write(&jar, vec![0u8; 4096]).unwrap();
+
+        let requirements = patch_requirements_with(&jar, true, false).unwrap();
+        assert_eq!(requirements.jar_size_bytes, 4096);
+        assert_eq!(requirements.backup_space_needed, 4096);
+        assert!(!requirements.needs_elevation);
+        assert!(requirements.java_available);
+        assert!(!requirements.patcher_downloaded);
+        assert_eq!(
+            requirements.estimated_seconds,
+            PATCH_BASE_SECONDS + 1 + PATCHER_DOWNLOAD_SECONDS
+        );
+
+        // An existing backup means no extra space is needed
+        create_backup(&jar).unwrap();
+        let requirements = patch_requirements_with(&jar, false, true).unwrap();
+        assert_eq!(requirements.backup_space_needed, 0);
+        assert_eq!(requirements.estimated_seconds, PATCH_BASE_SECONDS + 1);
+
+        assert!(matches!(
+            patch_requirements_with(&dir.path().join("missing.jar"), true, true),
+            Err(PatchError::JarNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_safe_mode_blocks_elevation_and_download() {
+        assert!(elevation_allowed(false).is_ok());
+        assert!(patcher_download_allowed(false).is_ok());
+        let err = elevation_allowed(true).unwrap_err();
+        assert!(matches!(err, PatchError::SafeModeBlocked(_)));
+        assert!(err.to_string().contains("elevation is disabled"));
+        assert!(matches!(patcher_download_allowed(true), Err(PatchError::SafeModeBlocked(_))));
+    }
 }
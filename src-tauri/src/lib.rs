@@ -1,19 +1,25 @@
 pub mod bitwig;
+pub mod log_stream;
 pub mod repository;
 pub mod settings;
 pub mod theme;
+pub mod updates;
+pub mod user;
 
-use bitwig::{detector, patcher};
-use repository::{bundled, cache, fetcher};
+use bitwig::{detector, history, patcher};
+use repository::{bundled, cache, favorites, fetcher};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_updater::{Update, UpdaterExt};
-use theme::parser;
+use theme::{aliases, color, defaults, generator, parser};
 
 // Re-export types for frontend
 pub use bitwig::BitwigInstallation;
@@ -96,27 +102,38 @@ pub struct UpdateInfo {
     pub current_version: String,
     pub body: Option<String>,
     pub date: Option<String>,
+    /// Whether `version` is actually newer than `current_version`
+    pub is_newer: bool,
+    /// Whether the offered `version` is older than `current_version`
+    pub is_downgrade: bool,
 }
 
 // State to hold pending update
 pub struct PendingUpdate(pub Mutex<Option<Update>>);
 
+// State to hold the in-progress update download so it can be cancelled
+pub struct UpdateDownload(pub Mutex<Option<tokio::task::AbortHandle>>);
+
 // Tauri Commands - Updates
 
 /// Check for available updates
 #[tauri::command]
 async fn check_for_updates(app: tauri::AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    fetcher::ensure_network_allowed()?;
     let updater = app.updater().map_err(|e| AppError {
         message: format!("Failed to get updater: {}", e),
     })?;
 
     match updater.check().await {
         Ok(Some(update)) => {
+            let order = updates::compare_versions(&update.version, &update.current_version);
             let info = UpdateInfo {
                 version: update.version.clone(),
                 current_version: update.current_version.clone(),
                 body: update.body.clone(),
                 date: update.date.map(|d| d.to_string()),
+                is_newer: order == std::cmp::Ordering::Greater,
+                is_downgrade: order == std::cmp::Ordering::Less,
             };
 
             // Store the update for later installation
@@ -134,6 +151,21 @@ async fn check_for_updates(app: tauri::AppHandle) -> Result<Option<UpdateInfo>,
     }
 }
 
+/// Get the pending update's release notes without re-checking the network
+#[tauri::command]
+fn get_pending_update_notes(app: tauri::AppHandle) -> Option<updates::ReleaseNotes> {
+    let state = app.try_state::<PendingUpdate>()?;
+    let pending = state.0.lock().unwrap();
+    let update = pending.as_ref()?;
+    let raw = update.body.clone()?;
+
+    Some(updates::ReleaseNotes {
+        version: update.version.clone(),
+        formatted: updates::format_release_notes(&raw),
+        raw,
+    })
+}
+
 /// Get the current app version
 #[tauri::command]
 fn get_app_version() -> String {
@@ -148,51 +180,108 @@ struct DownloadProgress {
 }
 
 /// Download and install the pending update
+/// The download can be aborted with `cancel_update_download`; a cancelled or failed
+/// download puts the update back as pending so it can be retried. The updater plugin
+/// doesn't support ranged downloads, so a retry starts from the beginning.
 #[tauri::command]
 async fn install_update(app: tauri::AppHandle) -> Result<(), AppError> {
+    fetcher::ensure_network_allowed()?;
     let update = {
         let state = app.state::<PendingUpdate>();
         let mut pending = state.0.lock().unwrap();
         pending.take()
     };
 
-    match update {
-        Some(update) => {
-            // Download and install the update
-            let mut downloaded = 0;
-            let app_handle = app.clone();
-
-            update
-                .download_and_install(
-                    move |chunk_length, content_length| {
-                        downloaded += chunk_length;
-                        log_event(&format!(
-                            "Update download progress: {} / {:?}",
-                            downloaded, content_length
-                        ));
-                        // Emit progress event to frontend
-                        let _ = app_handle.emit("update-download-progress", DownloadProgress {
-                            downloaded,
-                            total: content_length,
-                        });
-                    },
-                    || {
-                        log_event("Update download completed, preparing to install");
-                    },
-                )
-                .await
-                .map_err(|e| AppError {
-                    message: format!("Failed to install update: {}", e),
-                })?;
-
-            // Emit completion event
-            let _ = app.emit("update-ready", ());
-            log_event("Update installed successfully, restart required");
-            Ok(())
-        }
-        None => Err(AppError {
+    let Some(update) = update else {
+        return Err(AppError {
             message: "No pending update available. Please check for updates first.".to_string(),
-        }),
+        });
+    };
+
+    // Kept so the update can be re-armed if the download doesn't finish
+    let retry = update.clone();
+    let app_handle = app.clone();
+
+    let download = tokio::spawn(async move {
+        let mut downloaded = 0;
+        update
+            .download(
+                move |chunk_length, content_length| {
+                    downloaded += chunk_length;
+                    log_event(&format!(
+                        "Update download progress: {} / {:?}",
+                        downloaded, content_length
+                    ));
+                    // Emit progress event to frontend
+                    let _ = app_handle.emit("update-download-progress", DownloadProgress {
+                        downloaded,
+                        total: content_length,
+                    });
+                },
+                || {
+                    log_event("Update download completed, preparing to install");
+                },
+            )
+            .await
+            .map(|bytes| (update, bytes))
+    });
+
+    *app.state::<UpdateDownload>().0.lock().unwrap() = Some(download.abort_handle());
+    let outcome = download.await;
+    app.state::<UpdateDownload>().0.lock().unwrap().take();
+
+    let rearm = |app: &tauri::AppHandle| {
+        *app.state::<PendingUpdate>().0.lock().unwrap() = Some(retry.clone());
+    };
+
+    let (update, bytes) = match outcome {
+        Ok(Ok(downloaded)) => downloaded,
+        Ok(Err(e)) => {
+            rearm(&app);
+            log_event(&format!("Update download failed: {}", e));
+            return Err(AppError {
+                message: format!("Failed to download update: {}", e),
+            });
+        }
+        Err(e) if e.is_cancelled() => {
+            rearm(&app);
+            log_event("Update download cancelled");
+            let _ = app.emit("update-download-cancelled", ());
+            return Err(AppError {
+                message: "Update download cancelled".to_string(),
+            });
+        }
+        Err(e) => {
+            rearm(&app);
+            return Err(AppError {
+                message: format!("Update download stopped unexpectedly: {}", e),
+            });
+        }
+    };
+
+    update.install(bytes).map_err(|e| {
+        rearm(&app);
+        AppError {
+            message: format!("Failed to install update: {}", e),
+        }
+    })?;
+
+    // Emit completion event
+    let _ = app.emit("update-ready", ());
+    log_event("Update installed successfully, restart required");
+    Ok(())
+}
+
+/// Abort the update download started by `install_update`
+/// Returns false if no download was in progress
+#[tauri::command]
+fn cancel_update_download(app: tauri::AppHandle) -> bool {
+    match app.state::<UpdateDownload>().0.lock().unwrap().take() {
+        Some(handle) => {
+            handle.abort();
+            true
+        }
+        None => false,
     }
 }
 
@@ -204,6 +293,38 @@ fn detect_bitwig_installations() -> Vec<BitwigInstallation> {
     detector::detect_installations()
 }
 
+/// Get the paths the detector searches and whether each exists
+#[tauri::command]
+fn get_search_paths() -> Vec<detector::SearchPathInfo> {
+    detector::get_search_paths()
+}
+
+/// Add a persistent custom search path for installation detection
+#[tauri::command]
+fn add_search_path(path: String) -> Result<Vec<String>, AppError> {
+    let path = path.trim().to_string();
+    if path.is_empty() {
+        return Err(AppError {
+            message: "Search path cannot be empty".to_string(),
+        });
+    }
+
+    let updated = settings::update_setting(|s| {
+        if !s.custom_search_paths.contains(&path) {
+            s.custom_search_paths.push(path);
+        }
+    })?;
+    Ok(updated.custom_search_paths)
+}
+
+/// Remove a custom search path
+#[tauri::command]
+fn remove_search_path(path: String) -> Result<Vec<String>, AppError> {
+    let path = path.trim().to_string();
+    let updated = settings::update_setting(|s| s.custom_search_paths.retain(|p| p != &path))?;
+    Ok(updated.custom_search_paths)
+}
+
 /// Validate a manually provided Bitwig installation path
 #[tauri::command]
 fn validate_bitwig_path(path: String) -> Option<BitwigInstallation> {
@@ -216,15 +337,57 @@ fn get_patch_status(jar_path: String) -> bool {
     patcher::is_patched(&PathBuf::from(jar_path))
 }
 
+/// Get a detailed patch report comparing the marker with the real JAR state
+#[tauri::command]
+fn patch_status_detailed(jar_path: String) -> patcher::PatchStatusDetails {
+    patcher::patch_status_detailed(&PathBuf::from(jar_path))
+}
+
 /// Get the latest Bitwig version
 #[tauri::command]
 fn get_latest_bitwig_version() -> String {
     detector::get_latest_version()
 }
 
+fn log_file_in(base: &Path) -> PathBuf {
+    base.join("bitwig-theme-manager").join("logs").join("app.log")
+}
+
+/// Logs live in the XDG state dir where there is one, since cache cleaners may wipe
+/// the cache dir; elsewhere they stay in the cache dir
 fn get_log_path_buf() -> Option<PathBuf> {
-    dirs::cache_dir()
-        .map(|dir| dir.join("bitwig-theme-manager").join("logs").join("app.log"))
+    static LOG_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+    LOG_PATH
+        .get_or_init(|| resolve_log_path(dirs::state_dir(), dirs::cache_dir()))
+        .clone()
+}
+
+/// Pick the log location, moving a log left in the cache dir over to the state dir
+/// Keeps using the old location if the move fails so earlier entries aren't split off
+fn resolve_log_path(state_dir: Option<PathBuf>, cache_dir: Option<PathBuf>) -> Option<PathBuf> {
+    let legacy = cache_dir.map(|dir| log_file_in(&dir));
+    let Some(state_dir) = state_dir else {
+        return legacy;
+    };
+    let path = log_file_in(&state_dir);
+
+    if let Some(legacy) = legacy.filter(|l| l.is_file() && !path.exists()) {
+        let moved = path
+            .parent()
+            .map_or(Ok(()), cache::create_dir_all_checked)
+            .and_then(|_| {
+                std::fs::rename(&legacy, &path).or_else(|_| {
+                    // Cross-device moves need a copy
+                    std::fs::copy(&legacy, &path)?;
+                    std::fs::remove_file(&legacy)
+                })
+            });
+        if moved.is_err() {
+            return Some(legacy);
+        }
+    }
+
+    Some(path)
 }
 
 pub fn log_event(message: &str) {
@@ -234,7 +397,7 @@ pub fn log_event(message: &str) {
     };
 
     if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
+        let _ = cache::create_dir_all_checked(parent);
     }
 
     let timestamp = SystemTime::now()
@@ -254,16 +417,89 @@ fn get_log_path() -> Option<String> {
     get_log_path_buf().map(|p| p.to_string_lossy().to_string())
 }
 
+/// Start emitting `log-line` events for each line appended to the log
+#[tauri::command]
+fn start_log_stream(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, log_stream::LogStreamManager>,
+) -> Result<(), AppError> {
+    let log_path = get_log_path_buf().ok_or_else(|| AppError {
+        message: "Could not determine log file location".to_string(),
+    })?;
+    state.start(app_handle, log_path).map_err(|e| e.into())
+}
+
+/// Stop the live log stream
+#[tauri::command]
+fn stop_log_stream(state: tauri::State<'_, log_stream::LogStreamManager>) -> Result<(), AppError> {
+    state.stop().map_err(|e| e.into())
+}
+
+/// Structured patch history, kept next to the app log
+fn get_patch_history_path() -> Option<PathBuf> {
+    get_log_path_buf().and_then(|p| p.parent().map(|dir| dir.join("patch-history.jsonl")))
+}
+
+/// Run a patch or restore, recording it in the patch history when that setting is on
+fn record_patch_op<T>(
+    op: &str,
+    jar_path: &Path,
+    run: impl FnOnce(&Path) -> Result<T, patcher::PatchError>,
+) -> Result<T, patcher::PatchError> {
+    let started = std::time::Instant::now();
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let result = run(jar_path);
+
+    let enabled = settings::load_settings()
+        .map(|s| s.patch_history_log)
+        .unwrap_or(false);
+    if let (true, Some(path)) = (enabled, get_patch_history_path()) {
+        let entry = history::PatchLogEntry {
+            ts,
+            op: op.to_string(),
+            jar_path: jar_path.to_path_buf(),
+            result: if result.is_ok() { "ok" } else { "error" }.to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        if let Err(e) = history::append_patch_log(&path, &entry, history::MAX_PATCH_HISTORY_BYTES) {
+            log_event(&format!("Could not write patch history: {}", e));
+        }
+    }
+
+    result
+}
+
 /// Patch a Bitwig installation (with automatic elevation if needed)
 #[tauri::command]
 fn patch_bitwig(jar_path: String) -> Result<(), AppError> {
-    patcher::patch_jar_elevated(&PathBuf::from(jar_path)).map_err(|e| e.into())
+    record_patch_op("patch", &PathBuf::from(jar_path), patcher::patch_jar_elevated)
+        .map(|_| ())
+        .map_err(|e| e.into())
 }
 
 /// Restore a Bitwig installation from backup (with automatic elevation if needed)
 #[tauri::command]
 fn restore_bitwig(jar_path: String) -> Result<(), AppError> {
-    patcher::restore_jar_elevated(&PathBuf::from(jar_path)).map_err(|e| e.into())
+    record_patch_op("restore", &PathBuf::from(jar_path), patcher::restore_jar_elevated)
+        .map_err(|e| e.into())
+}
+
+/// Check whether elevated patching can work, without prompting the user
+#[tauri::command]
+fn check_elevation_available() -> patcher::ElevationStatus {
+    patcher::check_elevation_available()
+}
+
+/// Read the structured patch history, oldest first
+#[tauri::command]
+fn read_patch_history() -> Vec<history::PatchLogEntry> {
+    get_patch_history_path()
+        .map(|path| history::read_patch_log(&path))
+        .unwrap_or_default()
 }
 
 /// Check if a backup exists for a JAR file
@@ -278,6 +514,18 @@ fn has_java() -> bool {
     patcher::has_java()
 }
 
+/// Pre-flight report of what patching a JAR will take
+#[tauri::command]
+fn patch_requirements(jar_path: String) -> Result<patcher::PatchRequirements, AppError> {
+    patcher::patch_requirements(Path::new(&jar_path)).map_err(|e| e.into())
+}
+
+/// Report whether the patcher JAR is cached and intact, without downloading it
+#[tauri::command]
+fn get_patcher_status() -> patcher::PatcherStatus {
+    patcher::get_patcher_status()
+}
+
 /// Download and cache the patcher JAR, return its path
 #[tauri::command]
 fn ensure_patcher_available() -> Result<String, AppError> {
@@ -288,6 +536,103 @@ fn ensure_patcher_available() -> Result<String, AppError> {
 
 // Tauri Commands - Theme Files
 
+const THEME_DIR_WRITE_ADVICE: &str =
+    "Set a custom theme directory in Settings to store themes in a writable location.";
+const EXPORT_WRITE_ADVICE: &str = "Choose a different destination folder.";
+
+/// Turn write failures on read-only or protected locations into actionable messages
+fn write_error(e: std::io::Error, path: &Path, advice: &str) -> AppError {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => AppError {
+            message: format!(
+                "Cannot write to {}: the location is read-only or not writable ({}). {}",
+                path.display(),
+                e,
+                advice
+            ),
+        },
+        _ => e.into(),
+    }
+}
+
+/// Copy a theme file, blaming the source or the destination for whichever side failed
+/// `fs::copy` reports both with one error, which made an unreadable source look like a
+/// read-only destination
+fn copy_theme_file(source: &Path, dest: &Path, advice: &str) -> Result<(), AppError> {
+    let mut reader = std::fs::File::open(source).map_err(|e| AppError {
+        message: format!("Cannot read {}: {}", source.display(), e),
+    })?;
+    let mut writer = std::fs::File::create(dest).map_err(|e| write_error(e, dest, advice))?;
+    std::io::copy(&mut reader, &mut writer).map_err(|e| write_error(e, dest, advice))?;
+    Ok(())
+}
+
+/// Like `write_error`, for failures surfaced through `ThemeError`
+fn theme_write_error(e: theme::ThemeError, path: &Path, advice: &str) -> AppError {
+    match e {
+        theme::ThemeError::Io(io) => write_error(io, path, advice),
+        other => other.into(),
+    }
+}
+
+/// Pick a free theme file path, logging when the name had to be shortened
+fn theme_file_destination(theme_dir: &Path, safe_name: &str) -> PathBuf {
+    let dest = parser::unique_theme_file_path(theme_dir, safe_name);
+    if dest.truncated {
+        log_event(&format!(
+            "Theme name '{}' shortened to fit the path length limit: {}",
+            safe_name,
+            dest.path.to_string_lossy()
+        ));
+    }
+    dest.path
+}
+
+/// What to do when a theme file is written over an existing file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ConflictPolicy {
+    /// Fail without touching the existing file
+    Error,
+    /// Replace the existing file
+    Overwrite,
+    /// Write alongside it with a numeric suffix (`name_1.bte`, `name_2.bte`, ...)
+    Rename,
+}
+
+/// Decide the final destination for a write according to the conflict policy
+fn resolve_conflict(dest: PathBuf, policy: ConflictPolicy) -> Result<PathBuf, AppError> {
+    if !dest.exists() {
+        return Ok(dest);
+    }
+
+    match policy {
+        ConflictPolicy::Overwrite => Ok(dest),
+        ConflictPolicy::Error => Err(AppError {
+            message: format!("A file already exists at {}", dest.to_string_lossy()),
+        }),
+        ConflictPolicy::Rename => {
+            let dir = dest.parent().map(Path::to_path_buf).unwrap_or_default();
+            let stem = dest
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let ext = dest
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .unwrap_or_default();
+
+            let mut counter = 1;
+            loop {
+                let candidate = dir.join(format!("{}_{}{}", stem, counter, ext));
+                if !candidate.exists() {
+                    return Ok(candidate);
+                }
+                counter += 1;
+            }
+        }
+    }
+}
+
 /// Get the theme directory for a Bitwig version
 #[tauri::command]
 fn get_theme_directory(bitwig_version: String) -> Option<String> {
@@ -304,16 +649,75 @@ fn list_themes(bitwig_version: String) -> Result<Vec<String>, AppError> {
         .collect())
 }
 
+/// Check every library theme for invalid colors
+/// Keys that differ from the version's reference palette are listed but don't fail a theme
+#[tauri::command]
+fn validate_all_themes(
+    bitwig_version: String,
+) -> Result<Vec<theme::ThemeValidationReport>, AppError> {
+    let themes = parser::list_themes(&bitwig_version)?;
+    theme::validate_theme_files(&themes, &bitwig_version).ok_or_else(|| AppError {
+        message: format!("No reference palette is bundled for Bitwig {}", bitwig_version),
+    })
+}
+
+/// Rewrite every library theme for a Bitwig version in canonical form
+/// Files that are already canonical are not written
+#[tauri::command]
+fn normalize_library(
+    app_handle: tauri::AppHandle,
+    watcher: tauri::State<'_, theme::WatcherManager>,
+    bitwig_version: String,
+) -> Result<Vec<theme::NormalizeResult>, AppError> {
+    let themes = parser::list_themes(&bitwig_version)?;
+    let _paused = watcher.pause_guard(&app_handle);
+    Ok(parser::normalize_theme_files(&themes))
+}
+
+/// List library themes with how well each matches a Bitwig version's keys
+#[tauri::command]
+fn list_compatible_themes(
+    bitwig_version: String,
+) -> Result<Vec<theme::CompatibleTheme>, AppError> {
+    let themes = parser::list_themes(&bitwig_version)?;
+    theme::list_compatible_themes(&themes, &bitwig_version).ok_or_else(|| AppError {
+        message: format!("No reference palette is bundled for Bitwig {}", bitwig_version),
+    })
+}
+
 /// Load a theme from a file
 #[tauri::command]
 fn load_theme(path: String) -> Result<Theme, AppError> {
     parser::parse_theme_file(&PathBuf::from(path)).map_err(|e| e.into())
 }
 
+/// Group an unsaved theme's colors for the editor, e.g. after randomizing
+#[tauri::command]
+fn preview_theme(theme: Theme) -> theme::GroupedColors {
+    theme.grouped_colors_view()
+}
+
 /// Save a theme to a file
 #[tauri::command]
 fn save_theme(theme: Theme, path: String) -> Result<(), AppError> {
-    parser::save_theme(&theme, &PathBuf::from(path)).map_err(|e| e.into())
+    let path = PathBuf::from(path);
+    save_library_theme(&theme, &path)
+        .map_err(|e| theme_write_error(e, &path, THEME_DIR_WRITE_ADVICE))
+}
+
+/// Save a theme, embedding a color checksum when the setting asks for it
+fn save_library_theme(theme: &Theme, path: &Path) -> Result<(), theme::ThemeError> {
+    let embed_checksum = settings::load_settings()
+        .map(|s| s.embed_theme_checksum)
+        .unwrap_or(false);
+    parser::save_theme_with(theme, path, embed_checksum)
+}
+
+/// Check whether a theme's colors still match the checksum embedded when it was saved
+/// Themes saved without a checksum report false
+#[tauri::command]
+fn verify_theme_integrity(path: String) -> Result<bool, AppError> {
+    parser::verify_theme_integrity(&PathBuf::from(path)).map_err(|e| e.into())
 }
 
 /// Get the active theme path for a Bitwig version
@@ -322,20 +726,253 @@ fn get_active_theme_path(bitwig_version: String) -> Option<String> {
     parser::get_active_theme_path(&bitwig_version).map(|p| p.to_string_lossy().to_string())
 }
 
-/// Apply a theme by copying it to the active theme location
-/// Also patches Bitwig if not already patched
+/// Refuse to overwrite a locked active theme unless `force` is set
+fn ensure_active_unlocked(active: &Path, force: Option<bool>) -> Result<(), AppError> {
+    if parser::is_active_theme_locked(active) && !force.unwrap_or(false) {
+        let error = ApplyError::ThemeLocked {
+            path: active.to_path_buf(),
+        };
+        return Err(AppError {
+            message: error.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// State of the active theme for a Bitwig version
+#[derive(Debug, Clone, Serialize)]
+struct ActiveThemeInfo {
+    path: PathBuf,
+    exists: bool,
+    locked: bool,
+    /// Library file the theme was applied from, if recorded
+    source: Option<PathBuf>,
+    /// Installation the theme was applied for, if recorded
+    installation: Option<PathBuf>,
+}
+
+/// Describe the active theme, including whether it is locked
 #[tauri::command]
-fn apply_theme(theme_path: String, bitwig_version: String) -> Result<String, AppError> {
-    let source = PathBuf::from(theme_path);
-    let target = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
+fn get_active_theme_info(bitwig_version: String) -> Result<ActiveThemeInfo, AppError> {
+    let active = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
         message: "Could not determine active theme path".to_string(),
     })?;
+    Ok(ActiveThemeInfo {
+        exists: active.exists(),
+        locked: parser::is_active_theme_locked(&active),
+        source: parser::read_active_theme_source(&active).map(|s| s.path),
+        installation: parser::read_active_installation(&active),
+        path: active,
+    })
+}
 
-    let installations = detector::detect_installations();
+/// Lock the active theme so applies and restores refuse to overwrite it
+#[tauri::command]
+fn lock_active_theme(bitwig_version: String) -> Result<(), AppError> {
+    set_active_theme_lock(&bitwig_version, true)
+}
+
+/// Remove the lock from the active theme
+#[tauri::command]
+fn unlock_active_theme(bitwig_version: String) -> Result<(), AppError> {
+    set_active_theme_lock(&bitwig_version, false)
+}
+
+fn set_active_theme_lock(bitwig_version: &str, locked: bool) -> Result<(), AppError> {
+    let active = parser::get_active_theme_path(bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine active theme path".to_string(),
+    })?;
+    parser::set_active_theme_locked(&active, locked)
+        .map_err(|e| theme_write_error(e, &active, THEME_DIR_WRITE_ADVICE))?;
+    log_event(&format!(
+        "{} active theme {}",
+        if locked { "Locked" } else { "Unlocked" },
+        active.display()
+    ));
+    Ok(())
+}
+
+/// Get the library file the active theme was applied from, if it still exists
+#[tauri::command]
+fn get_active_theme_source(bitwig_version: String) -> Option<String> {
+    let active = parser::get_active_theme_path(&bitwig_version)?;
+    parser::read_active_theme_source(&active).map(|s| s.path.to_string_lossy().to_string())
+}
+
+/// Mark a theme as confirmed working with a Bitwig version
+/// The flag is dropped when any of the theme file's content changes, comments included
+#[tauri::command]
+fn mark_theme_verified(path: String, version: String) -> Result<(), AppError> {
+    let store = theme::get_verified_themes_path().ok_or_else(|| AppError {
+        message: "Could not determine config directory".to_string(),
+    })?;
+    theme::mark_theme_verified(&store, Path::new(&path), &version)?;
+    Ok(())
+}
+
+/// Check whether a theme, as it is now, was marked working with a Bitwig version
+#[tauri::command]
+fn is_theme_verified(path: String, version: String) -> bool {
+    theme::get_verified_themes_path()
+        .is_some_and(|store| theme::is_theme_verified(&store, Path::new(&path), &version))
+}
+
+/// Find the library theme the active theme was last applied from
+fn last_applied_theme(active: &Path) -> Result<PathBuf, ApplyError> {
+    let record = parser::read_recorded_theme_source(active).ok_or(ApplyError::NothingToReapply)?;
+    if !record.path.is_file() {
+        return Err(ApplyError::SourceMissing { path: record.path });
+    }
+    Ok(record.path)
+}
+
+/// Apply the last applied theme again, e.g. after a Bitwig update removed the patch
+/// Uses the selected Bitwig version when none is given
+#[tauri::command]
+fn reapply_last_theme(
+    bitwig_version: Option<String>,
+    installation_path: Option<String>,
+) -> Result<PatchResult, ApplyFailure> {
+    let bitwig_version = bitwig_version
+        .or_else(|| settings::load_settings().ok()?.selected_bitwig_version)
+        .ok_or_else(|| ApplyFailure::new(ApplyError::NoVersionSelected, &[]))?;
+    let details = [format!("Version: {}", bitwig_version)];
+    let active = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| {
+        let error = ApplyError::TargetUnresolvable {
+            bitwig_version: bitwig_version.clone(),
+        };
+        ApplyFailure::new(error, &details)
+    })?;
+    let source = last_applied_theme(&active).map_err(|e| ApplyFailure::new(e, &details))?;
+    let installation_path = installation_path.or_else(|| {
+        parser::read_active_installation(&active).map(|p| p.to_string_lossy().to_string())
+    });
+    log_event(&format!("reapply_last_theme {}", source.display()));
+
+    apply_theme_file(
+        source.to_string_lossy().to_string(),
+        bitwig_version,
+        installation_path,
+        None,
+        None,
+        None,
+    )
+}
+
+/// Why applying a theme failed
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[serde(tag = "kind")]
+pub enum ApplyError {
+    #[error("Theme file not found: {}", path.to_string_lossy())]
+    SourceMissing { path: PathBuf },
+
+    #[error("Could not determine active theme path for Bitwig {bitwig_version}")]
+    TargetUnresolvable { bitwig_version: String },
+
+    #[error("Failed to convert theme: {reason}")]
+    ConversionFailed { reason: String },
+
+    #[error("Failed to write theme to {}: {reason}", path.to_string_lossy())]
+    WriteFailed { path: PathBuf, reason: String },
+
+    #[error("Theme copied but patching failed: {reason}. Please patch Bitwig manually in the Patch Manager.")]
+    PatchFailed { jar_path: PathBuf, reason: String },
+
+    #[error("Patching failed: {reason}. The previous active theme was restored.")]
+    PatchFailedRolledBack { jar_path: PathBuf, reason: String },
+
+    #[error("No Bitwig installation was found to apply the theme to")]
+    NoInstallations,
+
+    #[error("Active theme {} is locked; unlock it or apply with force", path.to_string_lossy())]
+    ThemeLocked { path: PathBuf },
+
+    #[error("Select a Bitwig version before re-applying a theme")]
+    NoVersionSelected,
+
+    #[error("No theme has been applied yet, so there is nothing to re-apply")]
+    NothingToReapply,
+}
+
+/// Error returned by `apply_theme`
+/// `message` carries the same human-readable text the command returned before
+/// `ApplyError` existed, so callers that only read `message` keep working
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyFailure {
+    #[serde(flatten)]
+    pub error: ApplyError,
+    /// Diagnostic lines collected while applying
+    pub details: String,
+    pub message: String,
+}
+
+impl ApplyFailure {
+    fn new(error: ApplyError, details: &[String]) -> Self {
+        let details = details.join("\n");
+        log_event(&format!("apply_theme failed: {}", error));
+        ApplyFailure {
+            message: format!("{}\n\nDetails:\n{}", error, details),
+            error,
+            details,
+        }
+    }
+}
+
+/// Outcome of a successful apply
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchResult {
+    /// Library theme that was applied
+    pub theme_path: PathBuf,
+    /// Whether any installation was patched during this apply
+    pub patched: bool,
+    /// Human-readable summary with the collected details, as `apply_theme` returns it
+    pub message: String,
+}
+
+/// Apply a theme by copying it to the active theme location
+/// Also patches Bitwig if not already patched
+#[tauri::command]
+fn apply_theme(
+    theme_path: String,
+    bitwig_version: String,
+    installation_path: Option<String>,
+    remap_from_version: Option<String>,
+    force: Option<bool>,
+    normalize_keys: Option<bool>,
+) -> Result<String, ApplyFailure> {
+    apply_theme_file(
+        theme_path,
+        bitwig_version,
+        installation_path,
+        remap_from_version,
+        force,
+        normalize_keys,
+    )
+    .map(|result| result.message)
+}
+
+fn apply_theme_file(
+    theme_path: String,
+    bitwig_version: String,
+    installation_path: Option<String>,
+    remap_from_version: Option<String>,
+    force: Option<bool>,
+    normalize_keys: Option<bool>,
+) -> Result<PatchResult, ApplyFailure> {
+    let source = PathBuf::from(theme_path);
     let mut details = Vec::new();
     details.push(format!("Version: {}", bitwig_version));
     details.push(format!("Source: {}", source.to_string_lossy()));
     details.push(format!("Source exists: {}", source.exists()));
+
+    let Some(target) = parser::get_active_theme_path(&bitwig_version) else {
+        let error = ApplyError::TargetUnresolvable {
+            bitwig_version: bitwig_version.clone(),
+        };
+        return Err(ApplyFailure::new(error, &details));
+    };
+
+    let installations = detector::detect_installations();
     details.push(format!("Target: {}", target.to_string_lossy()));
     details.push(format!("Target exists (before): {}", target.exists()));
     if let Some(parent) = target.parent() {
@@ -352,109 +989,421 @@ fn apply_theme(theme_path: String, bitwig_version: String) -> Result<String, App
         ));
     }
 
+    // Applying to a version no installation reads succeeds but never shows up
+    let mismatch_warning = (!installations.is_empty()
+        && installation_path.is_none()
+        && !theme_directory_matches(&bitwig_version, &installations))
+    .then(|| {
+        format!(
+            "Warning: no detected Bitwig installation is version {}, so it will not load \
+             this theme. Select an installed version and apply again.\n\n",
+            bitwig_version
+        )
+    });
+    if mismatch_warning.is_some() {
+        details.push("Theme directory matches an installation: false".to_string());
+    }
+
     log_event(&format!("apply_theme start\n{}", details.join("\n")));
 
+    if !source.is_file() {
+        return Err(ApplyFailure::new(
+            ApplyError::SourceMissing { path: source },
+            &details,
+        ));
+    }
+    // Nothing could load or patch the theme, so leave the active theme untouched
+    if installations.is_empty() {
+        return Err(ApplyFailure::new(ApplyError::NoInstallations, &details));
+    }
+
+    let write_failed = |e: &dyn std::fmt::Display, details: &[String]| {
+        ApplyFailure::new(
+            ApplyError::WriteFailed {
+                path: target.clone(),
+                reason: e.to_string(),
+            },
+            details,
+        )
+    };
+    let conversion_failed = |e: &dyn std::fmt::Display, details: &[String]| {
+        ApplyFailure::new(
+            ApplyError::ConversionFailed {
+                reason: e.to_string(),
+            },
+            details,
+        )
+    };
+
+    if parser::is_active_theme_locked(&target) && !force.unwrap_or(false) {
+        return Err(ApplyFailure::new(
+            ApplyError::ThemeLocked {
+                path: target.clone(),
+            },
+            &details,
+        ));
+    }
+
     // Create theme directory if it doesn't exist
     if let Some(parent) = target.parent() {
-        std::fs::create_dir_all(parent)?;
+        cache::create_dir_all_checked(parent).map_err(|e| write_failed(&e, &details))?;
+    }
+
+    match snapshot_before_overwrite(&target) {
+        Ok(Some(snapshot)) => {
+            details.push(format!("Snapshot: {}", snapshot.to_string_lossy()));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            let error = ApplyError::WriteFailed {
+                path: parser::get_snapshots_dir(&target),
+                reason: e.to_string(),
+            };
+            return Err(ApplyFailure::new(error, &details));
+        }
     }
 
+    let rollback_enabled = settings::load_settings()
+        .map(|s| s.rollback_on_patch_failure)
+        .unwrap_or(false);
+    let backup = if rollback_enabled {
+        Some(parser::backup_active_theme(&target).map_err(|e| write_failed(&e, &details))?)
+    } else {
+        None
+    };
+
     // Copy or convert theme file
     let mut converted = false;
-    if let Ok(content) = std::fs::read_to_string(&source) {
+    let normalize_keys = normalize_keys.unwrap_or(false);
+    if remap_from_version.is_some() || normalize_keys {
+        let mut theme =
+            parser::parse_theme_file(&source).map_err(|e| conversion_failed(&e, &details))?;
+        if let Some(from_version) = &remap_from_version {
+            // Rename keys that changed between the theme's version and the target version
+            let result = aliases::remap_theme_keys(theme, from_version, &bitwig_version);
+            details.push(format!("Remapped keys: {}", result.remapped.len()));
+            if !result.unmapped.is_empty() {
+                details.push(format!("Keys without a mapping: {}", result.unmapped.join(", ")));
+            }
+            theme = result.theme;
+            log_event("apply_theme remapped keys");
+        }
+        if normalize_keys {
+            // Match miscased keys to the spelling Bitwig expects
+            let result = defaults::normalize_key_casing(theme, &bitwig_version);
+            details.push(format!("Corrected key casing: {}", result.corrected.len()));
+            for (original, canonical) in &result.corrected {
+                details.push(format!("- {} -> {}", original, canonical));
+            }
+            theme = result.theme;
+        }
+        std::fs::write(&target, parser::serialize_theme(&theme))
+            .map_err(|e| write_failed(&e, &details))?;
+        converted = true;
+    } else if let Ok(content) = std::fs::read_to_string(&source) {
         if parser::is_json_content(&content) {
             let theme_name = source
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .map(|s| s.to_string());
             let converted_content = parser::convert_json_to_bte(&content, theme_name.as_deref())
-                .map_err(|e| AppError {
-                    message: format!("Failed to convert JSON theme: {}", e),
-                })?;
-            std::fs::write(&target, converted_content).map_err(|e| {
-                log_event(&format!("apply_theme write failed: {}", e));
-                AppError {
-                    message: format!(
-                        "Failed to write theme: {}.\n\nDetails:\n{}",
-                        e,
-                        details.join("\n")
-                    ),
-                }
-            })?;
+                .map_err(|e| conversion_failed(&e, &details))?;
+            std::fs::write(&target, converted_content).map_err(|e| write_failed(&e, &details))?;
             converted = true;
             log_event("apply_theme converted json to bte");
         }
     }
 
     if !converted {
-        std::fs::copy(&source, &target).map_err(|e| {
-            log_event(&format!("apply_theme copy failed: {}", e));
-            AppError {
-                message: format!(
-                    "Failed to copy theme: {}.\n\nDetails:\n{}",
-                    e,
-                    details.join("\n")
-                ),
-            }
-        })?;
+        std::fs::copy(&source, &target).map_err(|e| write_failed(&e, &details))?;
         log_event("apply_theme copy ok");
     }
 
+    if let Err(e) = parser::record_active_theme_source(&target, &source) {
+        log_event(&format!("apply_theme could not record source: {}", e));
+    }
+    // Installations of one version share this directory; remember which one it was for
+    let installation = installation_path.as_deref().map(Path::new);
+    if let Some(previous) = parser::read_active_installation(&target) {
+        if Some(previous.as_path()) != installation {
+            details.push(format!("Replaced theme applied for: {}", previous.to_string_lossy()));
+        }
+    }
+    if let Err(e) = parser::record_active_installation(&target, installation) {
+        log_event(&format!("apply_theme could not record installation: {}", e));
+    }
+
+    // Under sudo, leave the theme, its sidecars and snapshots writable by the real user
+    if let Some(theme_dir) = target.parent() {
+        user::hand_back_tree(theme_dir);
+    }
+
     // Check if Bitwig needs patching
-    let mut patched_now = false;
+    let outcomes = patch_unpatched(&installations, |jar_path| {
+        record_patch_op("patch", jar_path, patcher::patch_jar_elevated)
+    })
+    .map_err(|(jar_path, reason)| {
+        let error = patch_failure(&target, backup.as_ref(), jar_path, reason, &mut details);
+        ApplyFailure::new(error, &details)
+    })?;
 
-    for install in &installations {
-        if !install.is_patched {
-            // Try to patch
-            match patcher::patch_jar_elevated(&install.jar_path) {
-                Ok(()) => {
-                    patched_now = true;
-                }
-                Err(e) => {
-                    // Return error but theme is already copied
-                    log_event(&format!("apply_theme patch failed: {}", e));
-                    return Err(AppError {
-                        message: format!(
-                            "Theme copied but patching failed: {}. Please patch Bitwig manually in the Patch Manager.\n\nDetails:\n{}",
-                            e,
-                            details.join("\n")
-                        ),
-                    });
-                }
-            }
+    let mut warning = mismatch_warning.unwrap_or_default();
+    let detected_version = outcomes.iter().find_map(|o| o.version_detected.clone());
+    if let Some(detected) = detected_version {
+        details.push(format!("Patcher detected version: {}", detected));
+        let reconciled = parser::get_theme_directory(&detected)
+            .map(|patcher_dir| reconcile_patcher_directory(&target, &patcher_dir, &detected));
+        match reconciled {
+            Some(Ok(Some(note))) => warning.push_str(&format!("{}\n\n", note)),
+            Some(Err(e)) => details.push(format!("Could not reconcile patcher directory: {}", e)),
+            _ => {}
         }
     }
-
-    if patched_now {
+    let patched = !outcomes.is_empty();
+    let message = if patched {
         log_event("apply_theme patched");
-        Ok(format!(
-            "Theme applied and Bitwig patched! Restart Bitwig to see changes.\n\nDetails:\n{}",
-            details.join("\n")
-        ))
-    } else if installations.iter().any(|i| i.is_patched) {
-        log_event("apply_theme done (already patched)");
-        Ok(format!(
-            "Theme applied! Restart Bitwig to see changes.\n\nDetails:\n{}",
+        format!(
+            "{}Theme applied and Bitwig patched! Restart Bitwig to see changes.\n\nDetails:\n{}",
+            warning,
             details.join("\n")
-        ))
+        )
     } else {
-        log_event("apply_theme done (no installations found)");
-        Ok(format!(
-            "Theme copied. No Bitwig installation found to patch.\n\nDetails:\n{}",
+        log_event("apply_theme done (already patched)");
+        format!(
+            "{}Theme applied! Restart Bitwig to see changes.\n\nDetails:\n{}",
+            warning,
             details.join("\n")
-        ))
-    }
+        )
+    };
+    Ok(PatchResult {
+        theme_path: source,
+        patched,
+        message,
+    })
 }
 
-/// Reset to default theme by removing the active theme file
+/// Make sure the theme reaches the version directory the patcher set Bitwig up to read
+/// When the patcher detected a different version than the theme was applied for, the
+/// theme is copied into that version's directory too (unless its theme is locked)
+/// Returns a warning describing the mismatch, or None when the directories agree
+fn reconcile_patcher_directory(
+    target: &Path,
+    patcher_dir: &Path,
+    detected_version: &str,
+) -> std::io::Result<Option<String>> {
+    if target.parent() == Some(patcher_dir) {
+        return Ok(None);
+    }
+
+    let mismatch = format!(
+        "Warning: the patcher detected Bitwig {}, which reads themes from {}",
+        detected_version,
+        patcher_dir.display()
+    );
+    let patcher_target = patcher_dir.join("theme.bte");
+    if parser::is_active_theme_locked(&patcher_target) {
+        return Ok(Some(format!(
+            "{}. Its active theme is locked, so this theme was not copied there.",
+            mismatch
+        )));
+    }
+
+    cache::create_dir_all_checked(patcher_dir)?;
+    std::fs::copy(target, &patcher_target)?;
+    log_event(&format!("apply_theme copied theme to patcher directory {}", patcher_dir.display()));
+    Ok(Some(format!(
+        "{}. The theme was copied there as well; select Bitwig {} to manage it.",
+        mismatch, detected_version
+    )))
+}
+
+/// Patch every installation that isn't patched yet, stopping at the first failure
+/// Returns the patcher's outcome for each patched JAR, or the failing JAR and the reason
+fn patch_unpatched(
+    installations: &[detector::BitwigInstallation],
+    mut patch: impl FnMut(&Path) -> Result<patcher::PatcherOutcome, patcher::PatchError>,
+) -> Result<Vec<patcher::PatcherOutcome>, (PathBuf, String)> {
+    let mut outcomes = Vec::new();
+    for install in installations.iter().filter(|install| !install.is_patched) {
+        let outcome =
+            patch(&install.jar_path).map_err(|e| (install.jar_path.clone(), e.to_string()))?;
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Build the error for a failed patch during apply
+/// With a backup the previous active theme is restored first, so Bitwig isn't left
+/// with a new theme it can't load
+fn patch_failure(
+    target: &Path,
+    backup: Option<&parser::ActiveThemeBackup>,
+    jar_path: PathBuf,
+    reason: String,
+    details: &mut Vec<String>,
+) -> ApplyError {
+    let Some(backup) = backup else {
+        return ApplyError::PatchFailed { jar_path, reason };
+    };
+    match parser::restore_active_backup(target, backup) {
+        Ok(()) => {
+            details.push("Rolled back: true".to_string());
+            log_event("apply_theme rolled back active theme");
+            ApplyError::PatchFailedRolledBack { jar_path, reason }
+        }
+        Err(e) => {
+            details.push(format!("Rollback failed: {}", e));
+            ApplyError::PatchFailed { jar_path, reason }
+        }
+    }
+}
+
+/// Check whether any installation reads themes from the version's theme directory
+fn theme_directory_matches(
+    bitwig_version: &str,
+    installations: &[detector::BitwigInstallation],
+) -> bool {
+    let Some(theme_dir) = parser::get_theme_directory(bitwig_version) else {
+        return false;
+    };
+    installations
+        .iter()
+        .any(|install| parser::get_theme_directory(&install.version).as_ref() == Some(&theme_dir))
+}
+
+/// Check whether a detected installation loads themes written for this version
+#[tauri::command]
+fn theme_directory_matches_installation(bitwig_version: String) -> bool {
+    theme_directory_matches(&bitwig_version, &detector::detect_installations())
+}
+
+/// List snapshots of the active theme taken before applies, newest first
+#[tauri::command]
+fn list_active_snapshots(bitwig_version: String) -> Result<Vec<String>, AppError> {
+    let Some(active) = parser::get_active_theme_path(&bitwig_version) else {
+        return Ok(Vec::new());
+    };
+    let snapshots = parser::list_active_snapshots(&active)?;
+    Ok(snapshots
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// How many active theme snapshots to keep, or None if the user turned snapshots off
+fn active_snapshot_limit() -> Option<usize> {
+    settings::load_settings()
+        .is_ok_and(|s| s.snapshot_before_apply)
+        .then_some(parser::MAX_ACTIVE_SNAPSHOTS)
+}
+
+/// Snapshot the active theme before something overwrites it, if the user asked for that
+/// Returns the new snapshot, or None when snapshots are off or there is no active theme
+fn snapshot_before_overwrite(active: &Path) -> Result<Option<PathBuf>, theme::ThemeError> {
+    match active_snapshot_limit() {
+        Some(max_keep) => parser::snapshot_active_theme(active, max_keep),
+        None => Ok(None),
+    }
+}
+
+/// Put a snapshot back as the active theme
+/// With snapshots on, the theme being replaced is snapshotted first so the restore can be undone
+#[tauri::command]
+fn restore_active_snapshot(
+    bitwig_version: String,
+    snapshot_path: String,
+    force: Option<bool>,
+) -> Result<(), AppError> {
+    let active = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine active theme path".to_string(),
+    })?;
+    ensure_active_unlocked(&active, force)?;
+    let snapshot = PathBuf::from(snapshot_path);
+    parser::restore_active_snapshot(&active, &snapshot, active_snapshot_limit())
+        .map(|_| ())
+        .map_err(|e| theme_write_error(e, &active, THEME_DIR_WRITE_ADVICE))
+}
+
+/// Apply only the colors of the given groups from a theme over the active theme
+#[tauri::command]
+fn apply_theme_partial(
+    source_path: String,
+    bitwig_version: String,
+    groups: Vec<String>,
+    force: Option<bool>,
+) -> Result<String, AppError> {
+    if groups.is_empty() {
+        return Err(AppError {
+            message: "No color groups selected".to_string(),
+        });
+    }
+    if let Some(unknown) = groups.iter().find(|g| !parser::is_known_color_group(g)) {
+        return Err(AppError {
+            message: format!(
+                "Unknown color group: {}. Valid groups: {}",
+                unknown,
+                parser::COLOR_GROUPS.join(", ")
+            ),
+        });
+    }
+
+    let source = PathBuf::from(&source_path);
+    let content = std::fs::read_to_string(&source)?;
+    let theme_name = source.file_stem().and_then(|s| s.to_str());
+    let source_theme = parser::parse_theme_auto(&content, Some(source.clone()), theme_name)?;
+
+    let target = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine active theme path".to_string(),
+    })?;
+    ensure_active_unlocked(&target, force)?;
+    let mut active = if target.exists() {
+        parser::parse_theme_file(&target)?
+    } else {
+        Theme::new()
+    };
+
+    let selected = parser::filter_colors_by_groups(&source_theme, &groups);
+    let applied = selected.len();
+    active.colors.extend(selected);
+
+    if let Some(parent) = target.parent() {
+        cache::create_dir_all_checked(parent)?;
+    }
+    snapshot_before_overwrite(&target)?;
+    save_library_theme(&active, &target)?;
+
+    log_event(&format!(
+        "apply_theme_partial: {} colors from {} ({})",
+        applied,
+        source.display(),
+        groups.join(", ")
+    ));
+
+    Ok(format!(
+        "Applied {} colors from {}. Restart Bitwig to see changes.",
+        applied,
+        groups.join(", ")
+    ))
+}
+
+/// Reset to default theme by removing the active theme file
+/// A locked active theme is left alone unless `force` is set
 #[tauri::command]
-fn reset_theme(bitwig_version: String) -> Result<String, AppError> {
+fn reset_theme(bitwig_version: String, force: Option<bool>) -> Result<String, AppError> {
     let theme_path = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
         message: "Could not determine active theme path".to_string(),
     })?;
+    reset_active_theme(&theme_path, force)
+}
+
+fn reset_active_theme(theme_path: &Path, force: Option<bool>) -> Result<String, AppError> {
+    ensure_active_unlocked(theme_path, force)?;
+
+    let _ = std::fs::remove_file(parser::get_active_source_path(theme_path));
+    let _ = parser::record_active_installation(theme_path, None);
 
     if theme_path.exists() {
-        std::fs::remove_file(&theme_path)?;
+        std::fs::remove_file(theme_path)?;
         log_event(&format!("reset_theme: removed {}", theme_path.display()));
         Ok("Theme reset to default. Restart Bitwig to see changes.".to_string())
     } else {
@@ -469,45 +1418,73 @@ fn create_theme(name: String, bitwig_version: String) -> Result<Theme, AppError>
         message: "Could not determine theme directory".to_string(),
     })?;
 
-    std::fs::create_dir_all(&theme_dir)?;
+    cache::create_dir_all_checked(&theme_dir)
+        .map_err(|e| write_error(e, &theme_dir, THEME_DIR_WRITE_ADVICE))?;
 
     let safe_name: String = name
         .chars()
         .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
         .collect();
-    let mut dest = theme_dir.join(format!("{}.bte", safe_name));
-    if dest.exists() {
-        let mut counter = 1;
-        loop {
-            let candidate = theme_dir.join(format!("{}_{}.bte", safe_name, counter));
-            if !candidate.exists() {
-                dest = candidate;
-                break;
-            }
-            counter += 1;
-        }
-    }
+    let dest = theme_file_destination(&theme_dir, &safe_name);
 
     let base_theme = parser::get_active_theme_path(&bitwig_version)
         .filter(|path| path.exists())
         .and_then(|path| parser::parse_theme_file(&path).ok())
         .unwrap_or_default();
 
-    let mut theme = Theme::with_name(&name);
-    theme.colors = base_theme.colors;
+    let mut theme = Theme::from_colors(&name, base_theme.colors);
     theme.metadata.author = base_theme.metadata.author;
     theme.metadata.description = base_theme.metadata.description;
     theme.metadata.version = base_theme.metadata.version;
     theme.path = Some(dest.clone());
 
-    parser::save_theme(&theme, &dest)?;
+    save_library_theme(&theme, &dest)
+        .map_err(|e| theme_write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
 
     Ok(theme)
 }
 
+/// Import theme text pasted from the clipboard into the themes directory
+/// JSON is converted to BTE; returns the path of the saved theme
+#[tauri::command]
+fn import_theme_from_text(
+    content: String,
+    name: String,
+    bitwig_version: String,
+) -> Result<String, AppError> {
+    let mut theme = parser::parse_pasted_theme(&content, &name)?;
+
+    let theme_dir = parser::get_theme_directory(&bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine theme directory".to_string(),
+    })?;
+    cache::create_dir_all_checked(&theme_dir)
+        .map_err(|e| write_error(e, &theme_dir, THEME_DIR_WRITE_ADVICE))?;
+
+    let safe_name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let safe_name = if safe_name.is_empty() { "Pasted_theme".to_string() } else { safe_name };
+    let dest = theme_file_destination(&theme_dir, &safe_name);
+
+    theme.path = Some(dest.clone());
+    save_library_theme(&theme, &dest)
+        .map_err(|e| theme_write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
+    log_event(&format!("import_theme_from_text: saved {}", dest.display()));
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
 /// Import a theme from an external path to the themes directory
 #[tauri::command]
-fn import_theme(source_path: String, bitwig_version: String) -> Result<String, AppError> {
+fn import_theme(
+    app_handle: tauri::AppHandle,
+    watcher: tauri::State<'_, theme::WatcherManager>,
+    source_path: String,
+    bitwig_version: String,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<String, AppError> {
     let source = PathBuf::from(&source_path);
 
     // Get filename from source
@@ -525,24 +1502,163 @@ fn import_theme(source_path: String, bitwig_version: String) -> Result<String, A
     })?;
 
     // Create theme directory if needed
-    std::fs::create_dir_all(&theme_dir)?;
+    cache::create_dir_all_checked(&theme_dir)
+        .map_err(|e| write_error(e, &theme_dir, THEME_DIR_WRITE_ADVICE))?;
+
+    // Theme packages carry the theme plus its preview
+    if theme::is_theme_package(&source) {
+        let package = theme::read_theme_package(&source)?;
+        let _paused = watcher.pause_guard(&app_handle);
+        let dest = import_theme_package(
+            &package,
+            &theme_dir,
+            on_conflict.unwrap_or(ConflictPolicy::Rename),
+        )?;
+
+        if let Some((ext, bytes)) = &package.preview {
+            if let Err(e) = cache::save_preview_image(&package.manifest.name, ext, bytes) {
+                log_event(&format!("import_theme could not store package preview: {}", e));
+            }
+        }
+        return Ok(dest.to_string_lossy().to_string());
+    }
+
+    // Plain zips hold a bare theme, possibly under a nonstandard name
+    if source.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+        let content = theme::extract_theme_from_zip(&source)?;
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dest = resolve_conflict(
+            theme_dir.join(format!("{}.bte", stem)),
+            on_conflict.unwrap_or(ConflictPolicy::Rename),
+        )?;
+        std::fs::write(&dest, content)
+            .map_err(|e| write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
+        return Ok(dest.to_string_lossy().to_string());
+    }
 
     // Copy file to themes directory
-    let dest = theme_dir.join(&filename);
-    std::fs::copy(&source, &dest)?;
+    let dest = resolve_conflict(
+        theme_dir.join(&filename),
+        on_conflict.unwrap_or(ConflictPolicy::Rename),
+    )?;
+    copy_theme_file(&source, &dest, THEME_DIR_WRITE_ADVICE)?;
 
     Ok(dest.to_string_lossy().to_string())
 }
 
+/// Write a package's theme into a theme directory, named after the package
+/// `read_theme_package` has already checked that the theme parses
+fn import_theme_package(
+    package: &theme::ThemePackage,
+    theme_dir: &Path,
+    policy: ConflictPolicy,
+) -> Result<PathBuf, AppError> {
+    let safe_name = cache::sanitize_theme_name(package.manifest.name.trim());
+    let safe_name = if safe_name.is_empty() { "Imported_theme".to_string() } else { safe_name };
+    let dest = resolve_conflict(theme_dir.join(format!("{}.bte", safe_name)), policy)?;
+    std::fs::write(&dest, &package.theme_content)
+        .map_err(|e| write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
+    Ok(dest)
+}
+
 /// Export a theme to an external path
+/// Refuses to overwrite an existing file unless told otherwise
 #[tauri::command]
-fn export_theme(theme_path: String, dest_path: String) -> Result<(), AppError> {
+fn export_theme(
+    theme_path: String,
+    dest_path: String,
+    on_conflict: Option<ConflictPolicy>,
+    include_preview: Option<bool>,
+) -> Result<String, AppError> {
     let source = PathBuf::from(&theme_path);
-    let dest = PathBuf::from(&dest_path);
+    let include_preview = include_preview.unwrap_or(false);
 
-    std::fs::copy(&source, &dest)?;
+    // A theme with its preview is exported as a zip package
+    let mut dest = PathBuf::from(&dest_path);
+    if include_preview {
+        dest.set_extension("zip");
+    }
+    let dest = resolve_conflict(dest, on_conflict.unwrap_or(ConflictPolicy::Error))?;
 
-    Ok(())
+    if include_preview {
+        let theme = parser::parse_theme_file(&source)?;
+        let name = theme.metadata.name.clone().unwrap_or_else(|| {
+            source
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Theme".to_string())
+        });
+        let preview = cache::get_cached_preview(&name);
+        theme::write_theme_package(
+            &dest,
+            &name,
+            &parser::serialize_theme(&theme),
+            preview.as_deref(),
+        )
+        .map_err(|e| theme_write_error(e, &dest, EXPORT_WRITE_ADVICE))?;
+    } else {
+        copy_theme_file(&source, &dest, EXPORT_WRITE_ADVICE)?;
+    }
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Export only the colors a modified theme adds or changes relative to its base,
+/// so tweaks can be shared without the full theme
+#[tauri::command]
+fn export_theme_overlay(
+    base_path: String,
+    modified_path: String,
+    dest: String,
+) -> Result<(), AppError> {
+    let base = parser::parse_theme_file(&PathBuf::from(base_path))?;
+    let modified = parser::parse_theme_file(&PathBuf::from(modified_path))?;
+    let overlay = theme::theme_overlay(&base, &modified);
+
+    let dest = PathBuf::from(dest);
+    save_library_theme(&overlay, &dest)
+        .map_err(|e| theme_write_error(e, &dest, EXPORT_WRITE_ADVICE))
+}
+
+/// Apply an overlay exported by `export_theme_overlay` to a base theme
+#[tauri::command]
+fn apply_theme_overlay(base_path: String, overlay_path: String) -> Result<Theme, AppError> {
+    let base = parser::parse_theme_file(&PathBuf::from(base_path))?;
+    let overlay = parser::parse_theme_file(&PathBuf::from(overlay_path))?;
+    Ok(theme::apply_theme_overlay(&base, &overlay))
+}
+
+/// Convert a theme file between BTE and JSON without importing or applying it
+/// Refuses to replace an existing destination unless `overwrite` is set
+#[tauri::command]
+fn convert_theme_file(
+    source: String,
+    dest: String,
+    target_format: parser::ThemeFormat,
+    overwrite: Option<bool>,
+) -> Result<(), AppError> {
+    let source = PathBuf::from(&source);
+    if !source.is_file() {
+        return Err(AppError {
+            message: format!("Theme file not found: {}", source.to_string_lossy()),
+        });
+    }
+
+    let policy = if overwrite.unwrap_or(false) {
+        ConflictPolicy::Overwrite
+    } else {
+        ConflictPolicy::Error
+    };
+    let dest = resolve_conflict(PathBuf::from(&dest), policy)?;
+
+    let content = std::fs::read_to_string(&source)?;
+    let theme_name = source.file_stem().and_then(|s| s.to_str());
+    let converted = parser::convert_theme_content(&content, target_format, theme_name)?;
+
+    std::fs::write(&dest, converted).map_err(|e| write_error(e, &dest, EXPORT_WRITE_ADVICE))
 }
 
 /// Delete a theme file
@@ -568,7 +1684,7 @@ fn save_downloaded_theme(
         message: "Could not determine theme directory".to_string(),
     })?;
 
-    std::fs::create_dir_all(&theme_dir)?;
+    cache::create_dir_all_checked(&theme_dir)?;
 
     // Sanitize the theme name for use as a filename
     let safe_name: String = theme_name
@@ -582,39 +1698,255 @@ fn save_downloaded_theme(
         })
         .collect();
 
-    let mut dest = theme_dir.join(format!("{}.bte", safe_name));
+    // Handle duplicate and over-long names
+    let dest = theme_file_destination(&theme_dir, &safe_name);
 
-    // Handle duplicate names
-    if dest.exists() {
-        let mut counter = 1;
-        loop {
-            let candidate = theme_dir.join(format!("{}_{}.bte", safe_name, counter));
-            if !candidate.exists() {
-                dest = candidate;
-                break;
-            }
-            counter += 1;
+    std::fs::write(&dest, &content)?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Copy an active theme into its library under a new name
+/// If a library theme already has the same colors, its path is returned instead
+fn save_active_into(active: &Path, theme_dir: &Path, name: &str) -> Result<PathBuf, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError {
+            message: "A name is required to save the active theme".to_string(),
+        });
+    }
+    if !active.is_file() {
+        return Err(AppError {
+            message: format!(
+                "There is no active theme at {}; apply a theme first",
+                active.to_string_lossy()
+            ),
+        });
+    }
+
+    let mut theme = parser::parse_theme_file(active)?;
+    theme.metadata.name = Some(name.to_string());
+    let checksum = parser::colors_checksum(&theme.colors);
+
+    for entry in std::fs::read_dir(theme_dir)?.flatten() {
+        let path = entry.path();
+        if path == active || !path.extension().is_some_and(|ext| ext == "bte") {
+            continue;
+        }
+        let same_colors = parser::parse_theme_file(&path)
+            .is_ok_and(|existing| parser::colors_checksum(&existing.colors) == checksum);
+        if same_colors {
+            log_event(&format!(
+                "Active theme already saved as {}",
+                path.to_string_lossy()
+            ));
+            return Ok(path);
         }
     }
 
-    std::fs::write(&dest, &content)?;
+    let dest = theme_file_destination(theme_dir, &cache::sanitize_theme_name(name));
+    save_library_theme(&theme, &dest)
+        .map_err(|e| theme_write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
+    Ok(dest)
+}
 
+/// Save the live `theme.bte`, e.g. after editing it externally, as a named library theme
+#[tauri::command]
+fn save_active_as(name: String, bitwig_version: String) -> Result<String, AppError> {
+    let active = parser::get_active_theme_path(&bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine active theme path".to_string(),
+    })?;
+    let theme_dir = active.parent().map(Path::to_path_buf).unwrap_or_default();
+    let dest = save_active_into(&active, &theme_dir, &name)?;
     Ok(dest.to_string_lossy().to_string())
 }
 
+/// Simulate how a theme's colors appear with a color vision deficiency
+#[tauri::command]
+fn simulate_colorblindness(theme: Theme, kind: color::CvdType) -> HashMap<String, String> {
+    color::simulate_colorblindness(&theme, kind).colors
+}
+
+/// List a theme's colors in a perceptual order for the palette view
+#[tauri::command]
+fn sort_colors_by(theme: Theme, order: color::ColorSort) -> Vec<theme::ThemeColor> {
+    color::sort_colors_by(&theme, order)
+}
+
+/// Color to tint a theme's card with in the browser
+#[tauri::command]
+fn theme_dominant_color(theme: Theme) -> Option<String> {
+    color::theme_dominant_color(&theme)
+}
+
+/// Percentage of the community reference colors that a theme changes
+#[tauri::command]
+fn customization_percentage(theme: Theme, version: String) -> Result<f64, AppError> {
+    defaults::customization_percentage(&theme, &version).ok_or_else(|| AppError {
+        message: format!("No reference palette is bundled for Bitwig {}", version),
+    })
+}
+
+/// Compare a theme with the community reference colors, so the editor can single out
+/// customized keys
+#[tauri::command]
+fn diff_against_default(theme: Theme, version: String) -> Result<theme::ThemeDiff, AppError> {
+    defaults::diff_against_default(&theme, &version).ok_or_else(|| AppError {
+        message: format!("No reference palette is bundled for Bitwig {}", version),
+    })
+}
+
+/// List the theme keys community themes set for a Bitwig version, for key pickers and
+/// autocomplete
+#[tauri::command]
+fn get_theme_key_schema(version: String) -> Result<Vec<defaults::KeySchema>, AppError> {
+    defaults::theme_key_schema(&version).ok_or_else(|| AppError {
+        message: format!("No reference palette is bundled for Bitwig {}", version),
+    })
+}
+
+/// Rename a theme's keys that changed between two Bitwig versions
+#[tauri::command]
+fn remap_theme_keys(
+    theme: Theme,
+    from_version: String,
+    to_version: String,
+) -> aliases::KeyRemapResult {
+    aliases::remap_theme_keys(theme, &from_version, &to_version)
+}
+
+/// Convert a theme to CSS custom properties for a live in-app preview
+#[tauri::command]
+fn theme_to_css_variables(theme: Theme) -> String {
+    theme::theme_to_css_variables(&theme)
+}
+
+/// Render a theme over a mock arranger and return the path of the cached PNG
+#[tauri::command]
+fn render_theme_preview(theme: Theme) -> Result<String, AppError> {
+    let rendered_dir = cache::get_previews_cache_dir()
+        .ok_or_else(|| AppError {
+            message: "Could not determine cache directory".to_string(),
+        })?
+        .join("rendered");
+    let hash = parser::content_hash(&parser::serialize_theme(&theme));
+    let png_path = rendered_dir.join(format!("{}.png", &hash[..16]));
+
+    if !png_path.exists() {
+        let png = theme::render_theme_preview(&theme)?;
+        cache::create_dir_all_checked(&rendered_dir)?;
+        std::fs::write(&png_path, png)?;
+    }
+
+    Ok(png_path.to_string_lossy().to_string())
+}
+
+/// Generate a random but coherent palette over the base theme's keys
+#[tauri::command]
+fn generate_random_theme(seed: Option<u64>, base: Theme) -> Theme {
+    generator::generate_random_theme(seed, &base)
+}
+
 // Tauri Commands - Repository
 
 /// Fetch themes from bundled resources (no network required)
 #[tauri::command]
 fn fetch_repository_themes(
     app: tauri::AppHandle,
-    _force_refresh: bool,
+    force_refresh: bool,
 ) -> Result<Vec<RepositoryTheme>, AppError> {
     // Load themes from bundled resources
-    let themes = bundled::load_bundled_themes(&app)?;
+    let mut themes = bundled::load_bundled_themes(&app)?;
+    for theme in &mut themes {
+        if let Some(readme) = cache::load_cached_readme(&theme.repo_url) {
+            fetcher::apply_readme_previews(theme, &readme);
+        }
+    }
+
+    // Keep the community README around for reading offline
+    let stale = cache::readme_needs_refresh(fetcher::AWESOME_REPO_URL, REPOSITORY_README_TTL);
+    if (force_refresh || stale) && !REPOSITORY_README_REFRESHING.swap(true, Ordering::SeqCst) {
+        tauri::async_runtime::spawn(async {
+            refresh_repository_readme().await;
+            REPOSITORY_README_REFRESHING.store(false, Ordering::SeqCst);
+        });
+    }
     Ok(themes)
 }
 
+/// How long the cached community README is used before it is downloaded again
+const REPOSITORY_README_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Set while a README download runs, so repeated listings don't start more
+static REPOSITORY_README_REFRESHING: AtomicBool = AtomicBool::new(false);
+
+/// Download the community repository README into the cache
+async fn refresh_repository_readme() {
+    match fetcher::fetch_readme(fetcher::AWESOME_REPO_URL).await {
+        Ok(Some(content)) => {
+            if let Err(e) = cache::save_cached_readme(fetcher::AWESOME_REPO_URL, &content) {
+                log_event(&format!("Could not cache repository README: {}", e));
+            }
+        }
+        Ok(None) => log_event("Repository README not found"),
+        Err(e) => log_event(&format!("Could not fetch repository README: {}", e)),
+    }
+}
+
+/// Get the cached community repository README as markdown, for offline browsing
+#[tauri::command]
+fn get_repository_readme() -> Option<String> {
+    cache::load_cached_readme(fetcher::AWESOME_REPO_URL)
+}
+
+/// Repository themes carrying a tag (see `fetcher::THEME_TAGS`), matched case-insensitively
+#[tauri::command]
+fn filter_themes_by_tag(
+    app: tauri::AppHandle,
+    tag: String,
+) -> Result<Vec<RepositoryTheme>, AppError> {
+    let tag = tag.trim().to_lowercase();
+    let themes = bundled::load_bundled_themes(&app)?;
+    Ok(themes.into_iter().filter(|theme| theme.tags.contains(&tag)).collect())
+}
+
+fn favorites_store() -> Result<PathBuf, AppError> {
+    favorites::get_favorites_path().ok_or_else(|| AppError {
+        message: "Could not determine config directory".to_string(),
+    })
+}
+
+/// The user's favorite repository themes
+#[tauri::command]
+fn list_favorites() -> Result<Vec<favorites::FavoriteTheme>, AppError> {
+    Ok(favorites::load_favorites(&favorites_store()?))
+}
+
+/// Add a repository theme to the favorites
+#[tauri::command]
+fn add_favorite(theme: RepositoryTheme) -> Result<(), AppError> {
+    favorites::merge_favorites(&favorites_store()?, vec![(&theme).into()])?;
+    Ok(())
+}
+
+/// Remove a theme from the favorites
+#[tauri::command]
+fn remove_favorite(theme: favorites::FavoriteTheme) -> Result<(), AppError> {
+    favorites::remove_favorite(&favorites_store()?, &theme).map_err(|e| e.into())
+}
+
+/// Write the favorites to a JSON file for sharing, returning how many were written
+#[tauri::command]
+fn export_favorites(dest: String) -> Result<usize, AppError> {
+    favorites::export_favorites(&favorites_store()?, Path::new(&dest)).map_err(|e| e.into())
+}
+
+/// Merge a shared favorites file into the favorites, returning how many were new
+#[tauri::command]
+fn import_favorites(src: String) -> Result<usize, AppError> {
+    favorites::import_favorites(&favorites_store()?, Path::new(&src)).map_err(|e| e.into())
+}
+
 /// Get cached repository themes (no network request)
 #[tauri::command]
 fn get_cached_repository_themes() -> Result<Vec<RepositoryTheme>, AppError> {
@@ -624,29 +1956,37 @@ fn get_cached_repository_themes() -> Result<Vec<RepositoryTheme>, AppError> {
     }
 }
 
-/// Get theme content from bundled resources
-#[tauri::command]
-fn download_repository_theme(
-    app: tauri::AppHandle,
-    theme_name: String,
-    _repo_url: String,
-    download_url: Option<String>,
+/// Read a repository theme's content as BTE, converting JSON themes
+fn read_repository_theme(
+    app: &tauri::AppHandle,
+    theme_name: &str,
+    download_url: Option<&str>,
 ) -> Result<String, AppError> {
     // Extract filename from the bundled:// URL
     let filename = download_url
-        .as_ref()
         .and_then(|url| url.strip_prefix("bundled://"))
         .ok_or_else(|| AppError {
             message: format!("Invalid bundled theme URL for: {}", theme_name),
         })?;
 
     // Read theme content from bundled resources
-    let raw_content = bundled::get_bundled_theme_content(&app, filename)?;
+    let raw_content = bundled::get_bundled_theme_content(app, filename)?;
 
     // Convert JSON themes to BTE format if needed
     let is_json = parser::is_json_content(&raw_content);
     let content = if is_json {
-        parser::convert_json_to_bte(&raw_content, Some(&theme_name))?
+        parser::convert_json_to_bte(&raw_content, Some(theme_name)).inspect_err(|e| {
+            // Keep the source so the failed conversion can be reproduced
+            match cache::write_debug_dump(&format!("convert {}", theme_name), &raw_content) {
+                Ok(path) => log_event(&format!(
+                    "Could not convert '{}' ({}), saved {}",
+                    theme_name,
+                    e,
+                    path.display()
+                )),
+                Err(dump_error) => log_event(&format!("Could not save debug dump: {}", dump_error)),
+            }
+        })?
     } else {
         raw_content
     };
@@ -654,6 +1994,182 @@ fn download_repository_theme(
     Ok(content)
 }
 
+/// Maintainer diagnostics: tally the formats repository themes are published in
+/// Bundled files are inspected on disk and remote ones judged by URL; anything else is
+/// counted as skipped rather than downloaded
+#[tauri::command]
+fn repository_format_stats(app: tauri::AppHandle) -> Result<fetcher::FormatStats, AppError> {
+    let themes = bundled::load_bundled_themes(&app)?;
+
+    let formats = themes.iter().map(|theme| {
+        let url = theme.download_url.as_deref()?;
+        match url.strip_prefix("bundled://") {
+            Some(filename) => bundled::get_bundled_theme_content(&app, filename)
+                .ok()
+                .map(|content| fetcher::format_from_content(&content)),
+            None => fetcher::format_from_url(url),
+        }
+    });
+
+    Ok(fetcher::FormatStats::from_formats(formats))
+}
+
+/// List repository authors with how many themes each published
+/// Uses the cached repository index, falling back to the bundled themes
+#[tauri::command]
+fn list_theme_authors(app: tauri::AppHandle) -> Result<Vec<fetcher::AuthorSummary>, AppError> {
+    let themes = match cache::load_cached_themes()? {
+        Some(cached) if !cached.themes.is_empty() => cached.themes,
+        _ => bundled::load_bundled_themes(&app)?,
+    };
+    Ok(fetcher::summarize_authors(&themes))
+}
+
+/// How long a theme converted by `warm_cache` is served from the cache
+const WARM_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Get a repository theme from the warmed cache, if it is still current
+fn warm_cached_theme(
+    app: &tauri::AppHandle,
+    theme_name: &str,
+    download_url: Option<&str>,
+) -> Option<String> {
+    // Entries older than the bundled file (e.g. after an app update) are ignored
+    let source_modified = download_url
+        .and_then(|url| url.strip_prefix("bundled://"))
+        .and_then(|filename| bundled::get_bundled_theme_path(app, filename).ok())
+        .and_then(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok());
+
+    cache::load_fresh_theme_file(theme_name, download_url, WARM_CACHE_TTL, source_modified)
+}
+
+/// Get theme content from bundled resources
+/// A current, intact cache entry is served first; otherwise the theme is read from the
+/// source and cached, replacing any empty or truncated entry
+#[tauri::command]
+fn download_repository_theme(
+    app: tauri::AppHandle,
+    theme_name: String,
+    _repo_url: String,
+    download_url: Option<String>,
+) -> Result<String, AppError> {
+    if let Some(content) = warm_cached_theme(&app, &theme_name, download_url.as_deref()) {
+        return Ok(content);
+    }
+    let content = read_repository_theme(&app, &theme_name, download_url.as_deref())?;
+    if let Err(e) = cache::save_theme_file(&theme_name, download_url.as_deref(), &content) {
+        log_event(&format!("Could not cache '{}': {}", theme_name, e));
+    }
+    Ok(content)
+}
+
+/// Progress event payload for `warm_cache`
+#[derive(Clone, serde::Serialize)]
+struct CacheWarmProgress {
+    done: usize,
+    total: usize,
+    theme_name: String,
+}
+
+/// Resolve and convert every repository theme into the cache in the background,
+/// so later downloads are served without re-reading and converting the source
+/// Emits `cache-warm-progress` per theme and `cache-warm-finished` at the end;
+/// returns the number of themes that will be processed
+#[tauri::command]
+fn warm_cache(app: tauri::AppHandle) -> Result<usize, AppError> {
+    let themes = bundled::load_bundled_themes(&app)?;
+    let total = themes.len();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        for (index, theme) in themes.iter().enumerate() {
+            let download_url = theme.download_url.as_deref();
+            if warm_cached_theme(&app, &theme.name, download_url).is_none() {
+                let saved = read_repository_theme(&app, &theme.name, download_url)
+                    .and_then(|content| {
+                        cache::save_theme_file(&theme.name, download_url, &content)
+                            .map_err(AppError::from)
+                    });
+                if let Err(e) = saved {
+                    log_event(&format!("warm_cache skipped '{}': {}", theme.name, e.message));
+                }
+            }
+
+            let _ = app.emit(
+                "cache-warm-progress",
+                CacheWarmProgress {
+                    done: index + 1,
+                    total,
+                    theme_name: theme.name.clone(),
+                },
+            );
+        }
+        let _ = app.emit("cache-warm-finished", ());
+    });
+
+    Ok(total)
+}
+
+/// Compare a repository theme with a copy in the library, e.g. before re-importing over
+/// local edits; `old` values come from the library copy and `new` ones from the repository
+#[tauri::command]
+fn diff_downloaded_vs_library(
+    app: tauri::AppHandle,
+    theme_name: String,
+    _repo_url: String,
+    download_url: Option<String>,
+    library_path: String,
+) -> Result<theme::ThemeDiff, AppError> {
+    let upstream_content = read_repository_theme(&app, &theme_name, download_url.as_deref())?;
+    let upstream = parser::parse_theme_content(&upstream_content, None)?;
+
+    let library_path = PathBuf::from(library_path);
+    let library_content = std::fs::read_to_string(&library_path)?;
+    let library = parser::parse_theme_auto(&library_content, Some(library_path), None)?;
+
+    Ok(theme::diff_themes(&library, &upstream))
+}
+
+/// Estimate how much data downloading the given repository themes will take
+/// Uses the cached repository index, falling back to the bundled themes. Bundled files
+/// are sized from disk, remote ones from the server's reported length
+#[tauri::command]
+async fn estimate_download_size(
+    app: tauri::AppHandle,
+    theme_names: Vec<String>,
+) -> Result<fetcher::DownloadSizeEstimate, AppError> {
+    let themes = match cache::load_cached_themes()? {
+        Some(cached) if !cached.themes.is_empty() => cached.themes,
+        _ => bundled::load_bundled_themes(&app)?,
+    };
+
+    let mut sizes = Vec::new();
+    let mut remote = Vec::new();
+    for name in theme_names {
+        let url = themes
+            .iter()
+            .find(|t| t.name == name)
+            .and_then(|t| t.download_url.clone());
+
+        match url {
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                remote.push((name, url));
+            }
+            Some(url) => {
+                let bytes = url
+                    .strip_prefix("bundled://")
+                    .and_then(|file| bundled::get_bundled_theme_path(&app, file).ok())
+                    .and_then(|path| std::fs::metadata(path).ok())
+                    .map(|m| m.len());
+                sizes.push((name, bytes));
+            }
+            None => sizes.push((name, None)),
+        }
+    }
+
+    sizes.extend(fetcher::fetch_download_sizes(remote).await);
+    Ok(fetcher::DownloadSizeEstimate::from_sizes(sizes))
+}
+
 /// Cache a preview image for a theme
 #[tauri::command]
 async fn cache_theme_preview(theme_name: String, preview_url: String) -> Result<String, AppError> {
@@ -661,12 +2177,99 @@ async fn cache_theme_preview(theme_name: String, preview_url: String) -> Result<
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Cache preview images for several themes in one call
+/// Returns each theme's local preview path, or `None` if it couldn't be downloaded
+#[tauri::command]
+async fn cache_previews(entries: Vec<(String, String)>) -> Vec<(String, Option<String>)> {
+    cache::cache_previews(entries)
+        .await
+        .into_iter()
+        .map(|(name, path)| (name, path.map(|p| p.to_string_lossy().to_string())))
+        .collect()
+}
+
+/// Fetch a theme repository's README as raw markdown
+/// Served from the cache when available, otherwise downloaded and cached
+#[tauri::command]
+async fn fetch_theme_readme(repo_url: String) -> Result<Option<String>, AppError> {
+    if let Some(cached) = cache::load_cached_readme(&repo_url) {
+        return Ok(Some(cached));
+    }
+
+    let readme = fetcher::fetch_readme(&repo_url).await?;
+    if let Some(content) = &readme {
+        if let Err(e) = cache::save_cached_readme(&repo_url, content) {
+            log_event(&format!("fetch_theme_readme could not cache README: {}", e));
+        }
+    }
+
+    Ok(readme)
+}
+
+/// Find every screenshot in a theme repository's README and cache them
+/// The first image is cached under the theme name so it doubles as the primary preview;
+/// the rest go into the theme's own previews directory
+/// Returns the local paths of the images that could be cached, in README order
+#[tauri::command]
+async fn fetch_theme_previews(
+    theme_name: String,
+    repo_url: String,
+) -> Result<Vec<String>, AppError> {
+    let Some(readme) = fetch_theme_readme(repo_url.clone()).await? else {
+        return Ok(Vec::new());
+    };
+
+    let base = fetcher::readme_asset_base(&repo_url);
+    let mut urls = fetcher::extract_preview_urls(&readme, base.as_deref());
+    if urls.is_empty() {
+        return Ok(Vec::new());
+    }
+    let extra = urls.split_off(1);
+
+    let primary = cache::cache_previews(vec![(theme_name.clone(), urls.remove(0))])
+        .await
+        .into_iter()
+        .map(|(_, path)| path);
+    let extra = cache::cache_extra_previews(&theme_name, extra).await;
+
+    Ok(primary
+        .chain(extra)
+        .flatten()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Number of network requests currently in flight, for diagnostics
+#[tauri::command]
+fn network_requests_in_flight() -> usize {
+    fetcher::network_requests_in_flight()
+}
+
+/// Last known GitHub API budget, or None before any GitHub request reported one
+#[tauri::command]
+fn github_rate_limit_status() -> Option<fetcher::RateLimitStatus> {
+    fetcher::github_rate_limit_status()
+}
+
+/// Probe GitHub and Codeberg to diagnose why a refresh fails
+#[tauri::command]
+async fn check_connectivity() -> Result<fetcher::Connectivity, AppError> {
+    fetcher::check_connectivity().await.map_err(|e| e.into())
+}
+
 /// Get the cached preview path for a theme
 #[tauri::command]
 fn get_cached_preview_path(theme_name: String) -> Option<String> {
     cache::get_cached_preview(&theme_name).map(|p| p.to_string_lossy().to_string())
 }
 
+/// Get the cached preview for a theme as a `data:` URL
+/// Works where the asset protocol can't load the cached file path
+#[tauri::command]
+fn get_theme_preview_base64(theme_name: String) -> Option<String> {
+    cache::get_cached_preview_data_url(&theme_name)
+}
+
 /// List all cached theme files
 #[tauri::command]
 fn list_cached_themes() -> Result<Vec<String>, AppError> {
@@ -677,6 +2280,127 @@ fn list_cached_themes() -> Result<Vec<String>, AppError> {
         .collect())
 }
 
+/// Remove cached files for themes that are no longer in the repository
+#[tauri::command]
+fn compact_cache() -> Result<Vec<String>, AppError> {
+    cache::compact_cache().map_err(|e| e.into())
+}
+
+/// Check the cache for corrupt index, theme and preview files
+#[tauri::command]
+fn validate_cache() -> Result<cache::CacheHealth, AppError> {
+    cache::validate_cache().map_err(|e| e.into())
+}
+
+/// Delete corrupt cache entries so they are fetched again
+#[tauri::command]
+fn repair_cache() -> Result<Vec<String>, AppError> {
+    cache::repair_cache().map_err(|e| e.into())
+}
+
+/// List diagnostic dumps saved when repository themes failed to convert
+#[tauri::command]
+fn list_debug_dumps() -> Result<Vec<cache::DebugDump>, AppError> {
+    cache::list_debug_dumps().map_err(|e| e.into())
+}
+
+/// Delete all diagnostic dumps, returning how many were removed
+#[tauri::command]
+fn clear_debug_dumps() -> Result<usize, AppError> {
+    cache::clear_debug_dumps().map_err(|e| e.into())
+}
+
+/// Zip a Bitwig version's library themes into the snapshot cache
+/// Returns the snapshot's path
+#[tauri::command]
+fn snapshot_theme_library(bitwig_version: String) -> Result<String, AppError> {
+    let theme_dir = parser::get_theme_directory(&bitwig_version)
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| AppError {
+            message: format!("No theme directory exists for Bitwig {}", bitwig_version),
+        })?;
+    let snapshots_dir = cache::get_snapshots_dir().ok_or_else(|| AppError {
+        message: "Could not determine cache directory".to_string(),
+    })?;
+    cache::create_dir_all_checked(&snapshots_dir)?;
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let dest = snapshots_dir.join(cache::snapshot_file_name(&bitwig_version, created_at));
+    let count = theme::write_library_snapshot(&theme_dir, &dest)?;
+    log_event(&format!("Snapshot of {} themes written to {}", count, dest.display()));
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Write a snapshot's files into a theme directory, returning the paths written
+/// With `ConflictPolicy::Error` nothing is written if any file already exists
+fn restore_library_snapshot(
+    snapshot: &Path,
+    theme_dir: &Path,
+    policy: ConflictPolicy,
+) -> Result<Vec<PathBuf>, AppError> {
+    let files = theme::read_library_snapshot(snapshot)?;
+
+    if policy == ConflictPolicy::Error {
+        if let Some((relative, _)) = files.iter().find(|(rel, _)| theme_dir.join(rel).exists()) {
+            return Err(AppError {
+                message: format!(
+                    "A file already exists at {}",
+                    theme_dir.join(relative).to_string_lossy()
+                ),
+            });
+        }
+    }
+
+    let mut restored = Vec::new();
+    for (relative, bytes) in files {
+        let dest = resolve_conflict(theme_dir.join(relative), policy)?;
+        if let Some(parent) = dest.parent() {
+            cache::create_dir_all_checked(parent)
+                .map_err(|e| write_error(e, parent, THEME_DIR_WRITE_ADVICE))?;
+        }
+        std::fs::write(&dest, bytes).map_err(|e| write_error(e, &dest, THEME_DIR_WRITE_ADVICE))?;
+        restored.push(dest);
+    }
+    Ok(restored)
+}
+
+/// Restore a library snapshot into a Bitwig version's theme directory
+/// Existing files are kept and restored copies renamed unless told otherwise
+#[tauri::command]
+fn restore_theme_library(
+    app_handle: tauri::AppHandle,
+    watcher: tauri::State<'_, theme::WatcherManager>,
+    snapshot_path: String,
+    bitwig_version: String,
+    conflict_policy: Option<ConflictPolicy>,
+) -> Result<Vec<String>, AppError> {
+    let theme_dir = parser::get_theme_directory(&bitwig_version).ok_or_else(|| AppError {
+        message: "Could not determine theme directory".to_string(),
+    })?;
+    let _paused = watcher.pause_guard(&app_handle);
+    let restored = restore_library_snapshot(
+        Path::new(&snapshot_path),
+        &theme_dir,
+        conflict_policy.unwrap_or(ConflictPolicy::Rename),
+    )?;
+    Ok(restored.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+/// List library snapshots, newest first
+#[tauri::command]
+fn list_theme_library_snapshots() -> Result<Vec<cache::LibrarySnapshot>, AppError> {
+    cache::list_library_snapshots().map_err(|e| e.into())
+}
+
+/// Summarize what clearing the cache would delete, so the UI can confirm first
+#[tauri::command]
+fn cache_summary() -> Result<cache::CacheSummary, AppError> {
+    cache::cache_summary().map_err(|e| e.into())
+}
+
 /// Clear all cached data
 #[tauri::command]
 fn clear_cache() -> Result<(), AppError> {
@@ -694,7 +2418,137 @@ fn load_settings() -> Result<settings::Settings, AppError> {
 /// Save application settings
 #[tauri::command]
 fn save_settings(new_settings: settings::Settings) -> Result<(), AppError> {
-    settings::save_settings(&new_settings).map_err(|e| e.into())
+    if let Some(proxy_url) = new_settings.proxy_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        fetcher::validate_proxy_url(proxy_url)?;
+    }
+    let custom_dir = new_settings.custom_theme_directory.as_deref();
+    if let Some(dir) = custom_dir.filter(|d| !d.trim().is_empty()) {
+        parser::validate_theme_directory_override(Path::new(dir))?;
+    }
+    settings::save_settings(&new_settings)?;
+    apply_runtime_settings(&new_settings);
+    Ok(())
+}
+
+/// Push settings that are read from process-wide state rather than the settings file
+fn apply_runtime_settings(settings: &settings::Settings) {
+    parser::set_max_theme_keys(settings.max_theme_keys);
+    // A directory that fails validation (e.g. edited by hand) is ignored, not trusted
+    let custom_dir = settings
+        .custom_theme_directory
+        .as_deref()
+        .filter(|dir| !dir.trim().is_empty())
+        .and_then(|dir| match parser::validate_theme_directory_override(Path::new(dir)) {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                log_event(&format!("Ignoring custom_theme_directory: {}", e));
+                None
+            }
+        });
+    parser::set_custom_theme_directory(custom_dir);
+    settings::set_safe_mode(settings.safe_mode);
+    fetcher::set_proxy_setting(settings.proxy_url.as_deref());
+}
+
+/// Change one setting by name, returning the settings as saved
+/// Updates are applied atomically, so concurrent changes to different settings all stick
+#[tauri::command]
+fn set_setting(key: String, value: serde_json::Value) -> Result<settings::Settings, AppError> {
+    if key == "proxy_url" {
+        if let Some(proxy_url) = value.as_str().filter(|u| !u.trim().is_empty()) {
+            fetcher::validate_proxy_url(proxy_url)?;
+        }
+    }
+    if key == "custom_theme_directory" {
+        if let Some(dir) = value.as_str().filter(|d| !d.trim().is_empty()) {
+            parser::validate_theme_directory_override(Path::new(dir))?;
+        }
+    }
+    let updated = settings::set_setting(&key, value)?;
+    apply_runtime_settings(&updated);
+    Ok(updated)
+}
+
+/// Where the app stores its files; unresolvable locations are `None`
+#[derive(Debug, Clone, Serialize)]
+pub struct AppPaths {
+    pub config_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub log_path: Option<String>,
+    pub patcher_jar: Option<String>,
+    pub backups_root: Option<String>,
+    pub previews_dir: Option<String>,
+    pub themes_cache_dir: Option<String>,
+}
+
+/// Get every directory and file location the app uses
+#[tauri::command]
+fn get_app_paths() -> AppPaths {
+    let display = |p: PathBuf| p.to_string_lossy().to_string();
+
+    AppPaths {
+        config_dir: settings::settings_path()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .map(display),
+        cache_dir: cache::get_cache_dir().map(display),
+        log_path: get_log_path_buf().map(display),
+        patcher_jar: patcher::get_patcher_jar_path().map(display),
+        backups_root: patcher::get_backups_root().map(display),
+        previews_dir: cache::get_previews_cache_dir().map(display),
+        themes_cache_dir: cache::get_themes_cache_dir().map(display),
+    }
+}
+
+/// Directories whose contents `open_path` may hand to external apps
+fn openable_roots() -> Vec<PathBuf> {
+    [
+        parser::get_theme_root(),
+        parser::custom_theme_directory(),
+        std::env::var_os(parser::THEME_DIR_ENV)
+            .and_then(|dir| parser::validate_theme_directory_override(Path::new(&dir)).ok()),
+        cache::get_cache_dir(),
+        settings::settings_path()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf)),
+        get_log_path_buf().and_then(|p| p.parent().map(Path::to_path_buf)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Check that a path resolves to somewhere inside one of the roots
+/// Both sides are canonicalized so `..` segments and symlinks can't escape a root
+fn is_within_roots(path: &Path, roots: &[PathBuf]) -> bool {
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| path.starts_with(root))
+}
+
+/// Open a file or directory in the default external app
+/// Limited to the app's own theme, cache, config and log locations
+#[tauri::command]
+fn open_path(app: tauri::AppHandle, path: String) -> Result<(), AppError> {
+    let target = PathBuf::from(&path);
+    if !is_within_roots(&target, &openable_roots()) {
+        return Err(AppError {
+            message: format!(
+                "Refusing to open {}: only theme, cache, config and log locations can be opened",
+                path
+            ),
+        });
+    }
+
+    app.opener()
+        .open_path(target.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError {
+            message: format!("Failed to open {}: {}", path, e),
+        })
 }
 
 /// Get the settings file path
@@ -708,13 +2562,17 @@ fn get_settings_path() -> Result<String, AppError> {
 // Tauri Commands - File Watcher
 
 /// Start watching a directory for theme file changes
+/// Subdirectories are only watched when `recursive` is set
 #[tauri::command]
 fn start_watching(
     path: String,
+    recursive: Option<bool>,
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, theme::WatcherManager>,
 ) -> Result<(), AppError> {
-    state.start(app_handle, PathBuf::from(path)).map_err(|e| e.into())
+    state
+        .start(app_handle, PathBuf::from(path), recursive.unwrap_or(false))
+        .map_err(|e| e.into())
 }
 
 /// Stop watching for theme file changes
@@ -725,6 +2583,18 @@ fn stop_watching(
     state.stop().map_err(|e| e.into())
 }
 
+/// Hold back theme change events during a bulk operation
+#[tauri::command]
+fn pause_watching(state: tauri::State<'_, theme::WatcherManager>) {
+    state.pause();
+}
+
+/// Release held-back theme change events as a single event
+#[tauri::command]
+fn resume_watching(app_handle: tauri::AppHandle, state: tauri::State<'_, theme::WatcherManager>) {
+    state.resume(&app_handle);
+}
+
 /// Get the current watcher status
 #[tauri::command]
 fn get_watcher_status(
@@ -733,11 +2603,20 @@ fn get_watcher_status(
     theme::WatcherStatus {
         is_running: state.is_running(),
         watched_path: state.watched_path().map(|p| p.to_string_lossy().to_string()),
+        is_paused: state.is_paused(),
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    settings::set_safe_mode_launch_flag(settings::safe_mode_flag_passed(std::env::args_os()));
+    if let Ok(settings) = settings::load_settings() {
+        apply_runtime_settings(&settings);
+    }
+    if settings::is_safe_mode() {
+        log_event("safe mode: elevation, network access and patcher download disabled");
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
@@ -745,53 +2624,463 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .manage(theme::WatcherManager::new())
+        .manage(log_stream::LogStreamManager::default())
         .manage(PendingUpdate(Mutex::new(None)))
+        .manage(UpdateDownload(Mutex::new(None)))
         .invoke_handler(tauri::generate_handler![
             // Bitwig detection
             detect_bitwig_installations,
+            get_search_paths,
+            add_search_path,
+            remove_search_path,
             validate_bitwig_path,
             get_patch_status,
+            patch_status_detailed,
             get_latest_bitwig_version,
             patch_bitwig,
+            read_patch_history,
+            check_elevation_available,
             restore_bitwig,
             has_backup,
             has_java,
             ensure_patcher_available,
+            get_patcher_status,
+            patch_requirements,
             // Theme files
             get_theme_directory,
             list_themes,
+            validate_all_themes,
+            normalize_library,
+            list_compatible_themes,
             load_theme,
+            preview_theme,
             save_theme,
+            verify_theme_integrity,
             get_active_theme_path,
+            get_active_theme_info,
+            theme_directory_matches_installation,
+            lock_active_theme,
+            unlock_active_theme,
+            get_active_theme_source,
+            reapply_last_theme,
+            mark_theme_verified,
+            is_theme_verified,
             apply_theme,
+            apply_theme_partial,
+            list_active_snapshots,
+            restore_active_snapshot,
             reset_theme,
             create_theme,
             import_theme,
+            import_theme_from_text,
             export_theme,
+            export_theme_overlay,
+            apply_theme_overlay,
+            convert_theme_file,
             delete_theme,
             save_downloaded_theme,
+            save_active_as,
+            simulate_colorblindness,
+            sort_colors_by,
+            theme_dominant_color,
+            generate_random_theme,
+            remap_theme_keys,
+            customization_percentage,
+            diff_against_default,
+            get_theme_key_schema,
+            render_theme_preview,
+            theme_to_css_variables,
             // Repository
             fetch_repository_themes,
             get_cached_repository_themes,
+            get_repository_readme,
+            filter_themes_by_tag,
+            list_favorites,
+            add_favorite,
+            remove_favorite,
+            export_favorites,
+            import_favorites,
             download_repository_theme,
+            warm_cache,
+            diff_downloaded_vs_library,
+            estimate_download_size,
+            repository_format_stats,
+            list_theme_authors,
+            github_rate_limit_status,
+            check_connectivity,
+            network_requests_in_flight,
             cache_theme_preview,
+            cache_previews,
+            fetch_theme_readme,
+            fetch_theme_previews,
             get_cached_preview_path,
+            get_theme_preview_base64,
             list_cached_themes,
+            compact_cache,
+            validate_cache,
+            repair_cache,
+            list_debug_dumps,
+            clear_debug_dumps,
+            snapshot_theme_library,
+            restore_theme_library,
+            list_theme_library_snapshots,
+            cache_summary,
             clear_cache,
             get_log_path,
+            start_log_stream,
+            stop_log_stream,
             // Settings
             load_settings,
             save_settings,
+            set_setting,
             get_settings_path,
+            get_app_paths,
+            open_path,
             // File watcher
             start_watching,
             stop_watching,
+            pause_watching,
+            resume_watching,
             get_watcher_status,
             // Updates
             check_for_updates,
             get_app_version,
+            get_pending_update_notes,
             install_update,
+            cancel_update_download,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let _ = app.state::<log_stream::LogStreamManager>().stop();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_theme_file_blames_the_failing_side() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source.bte");
+        let dest = temp.path().join("dest.bte");
+
+        let error = copy_theme_file(&source, &dest, EXPORT_WRITE_ADVICE).unwrap_err();
+        assert!(error.message.starts_with("Cannot read"), "{}", error.message);
+        assert!(error.message.contains("source.bte"));
+        assert!(!dest.exists());
+
+        std::fs::write(&source, "Window background: #101010\n").unwrap();
+        let error = copy_theme_file(&source, temp.path(), EXPORT_WRITE_ADVICE).unwrap_err();
+        assert!(!error.message.starts_with("Cannot read"), "{}", error.message);
+
+        copy_theme_file(&source, &dest, EXPORT_WRITE_ADVICE).unwrap();
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "Window background: #101010\n");
+    }
+
+    #[test]
+    fn test_log_migrates_from_cache_to_state_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_dir = temp.path().join("cache");
+        let state_dir = temp.path().join("state");
+
+        let legacy = log_file_in(&cache_dir);
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, "[1] earlier entry\n").unwrap();
+
+        let path = resolve_log_path(Some(state_dir.clone()), Some(cache_dir.clone())).unwrap();
+
+        assert_eq!(path, log_file_in(&state_dir));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1] earlier entry\n");
+        assert!(!legacy.exists());
+
+        // Without a state dir (macOS, Windows) the cache location is kept
+        assert_eq!(resolve_log_path(None, Some(cache_dir)), Some(legacy));
+    }
+
+    #[test]
+    fn test_is_within_roots() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("themes");
+        let outside = temp.path().join("elsewhere");
+        std::fs::create_dir_all(root.join("5.2")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(root.join("5.2").join("theme.bte"), "").unwrap();
+
+        let roots = vec![root.clone()];
+        assert!(is_within_roots(&root.join("5.2").join("theme.bte"), &roots));
+        assert!(is_within_roots(&root, &roots));
+        assert!(!is_within_roots(&outside, &roots));
+        let escaped = root.join("5.2").join("..").join("..").join("elsewhere");
+        assert!(!is_within_roots(&escaped, &roots));
+        assert!(!is_within_roots(&root.join("missing.bte"), &roots));
+    }
+
+    #[test]
+    fn test_theme_directory_matches_installation() {
+        let install = |version: &str| detector::BitwigInstallation {
+            path: PathBuf::from("/opt/bitwig-studio"),
+            version: version.to_string(),
+            jar_path: PathBuf::from("/opt/bitwig-studio/bin/bitwig.jar"),
+            is_patched: true,
+            installation_type: detector::InstallationType::System,
+            needs_sudo: false,
+        };
+
+        let installations = vec![install("5.2"), install("6.0 Beta 6")];
+        assert!(theme_directory_matches("5.2", &installations));
+        assert!(theme_directory_matches("6.0 Beta 6", &installations));
+        assert!(!theme_directory_matches("5.1", &installations));
+        assert!(!theme_directory_matches("5.2", &[]));
+    }
+
+    #[test]
+    fn test_patch_failure_rolls_back_active_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("theme.bte");
+        std::fs::write(&target, "Background: #111111\n").unwrap();
+
+        let backup = parser::backup_active_theme(&target).unwrap();
+        std::fs::write(&target, "Background: #222222\n").unwrap();
+
+        let installations = vec![detector::BitwigInstallation {
+            path: PathBuf::from("/opt/bitwig-studio"),
+            version: "5.2".to_string(),
+            jar_path: PathBuf::from("/opt/bitwig-studio/bin/bitwig.jar"),
+            is_patched: false,
+            installation_type: detector::InstallationType::System,
+            needs_sudo: false,
+        }];
+        let (jar_path, reason) = patch_unpatched(&installations, |jar_path| {
+            Err(patcher::PatchError::JarNotFound(jar_path.to_path_buf()))
+        })
+        .unwrap_err();
+
+        let mut details = Vec::new();
+        let error = patch_failure(&target, Some(&backup), jar_path, reason, &mut details);
+        assert!(matches!(error, ApplyError::PatchFailedRolledBack { .. }));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "Background: #111111\n");
+
+        // Without a backup the new theme stays in place
+        std::fs::write(&target, "Background: #222222\n").unwrap();
+        let error = patch_failure(&target, None, PathBuf::new(), String::new(), &mut details);
+        assert!(matches!(error, ApplyError::PatchFailed { .. }));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "Background: #222222\n");
+    }
+
+    #[test]
+    fn test_patch_unpatched_returns_outcomes() {
+        let install = |jar: &str, is_patched: bool| detector::BitwigInstallation {
+            path: PathBuf::from("/opt/bitwig-studio"),
+            version: "5.2".to_string(),
+            jar_path: PathBuf::from(jar),
+            is_patched,
+            installation_type: detector::InstallationType::System,
+            needs_sudo: false,
+        };
+        let installations = vec![
+            install("/opt/a/bitwig.jar", true),
+            install("/opt/b/bitwig.jar", false),
+        ];
+
+        let mut seen = Vec::new();
+        let outcomes = patch_unpatched(&installations, |jar_path| {
+            seen.push(jar_path.to_path_buf());
+            Ok(patcher::parse_patcher_output("Bitwig 5.2.4\nSuccessfully patched\n", "", true))
+        })
+        .unwrap();
+
+        assert_eq!(seen, vec![PathBuf::from("/opt/b/bitwig.jar")]);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].version_detected.as_deref(), Some("5.2.4"));
+    }
+
+    #[test]
+    fn test_last_applied_theme_picks_most_recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("theme.bte");
+        assert!(matches!(last_applied_theme(&active), Err(ApplyError::NothingToReapply)));
+
+        let first = dir.path().join("First.bte");
+        let second = dir.path().join("Second.bte");
+        std::fs::write(&first, "Background: #111111\n").unwrap();
+        std::fs::write(&second, "Background: #222222\n").unwrap();
+        parser::record_active_theme_source(&active, &first).unwrap();
+        parser::record_active_theme_source(&active, &second).unwrap();
+        assert_eq!(last_applied_theme(&active).unwrap(), second);
+
+        std::fs::remove_file(&second).unwrap();
+        let error = last_applied_theme(&active).unwrap_err();
+        assert!(matches!(error, ApplyError::SourceMissing { path } if path == second));
+    }
+
+    #[test]
+    fn test_reconcile_patcher_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("versions").join("5.2");
+        let patcher_dir = dir.path().join("versions").join("5.2.4");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        let target = app_dir.join("theme.bte");
+        std::fs::write(&target, "Background: #111111\n").unwrap();
+
+        assert_eq!(reconcile_patcher_directory(&target, &app_dir, "5.2").unwrap(), None);
+
+        let warning = reconcile_patcher_directory(&target, &patcher_dir, "5.2.4")
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("5.2.4"));
+        assert_eq!(
+            std::fs::read_to_string(patcher_dir.join("theme.bte")).unwrap(),
+            "Background: #111111\n"
+        );
+
+        // A locked theme in the patcher's directory is left alone
+        std::fs::write(&target, "Background: #222222\n").unwrap();
+        parser::set_active_theme_locked(&patcher_dir.join("theme.bte"), true).unwrap();
+        let warning = reconcile_patcher_directory(&target, &patcher_dir, "5.2.4")
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("locked"));
+        assert_eq!(
+            std::fs::read_to_string(patcher_dir.join("theme.bte")).unwrap(),
+            "Background: #111111\n"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_library() {
+        let temp = tempfile::tempdir().unwrap();
+        let library = temp.path().join("themes");
+        std::fs::create_dir_all(library.join("snapshots")).unwrap();
+        std::fs::write(library.join("Light.bte"), "Background: #eeeeee\n").unwrap();
+        std::fs::write(library.join("Night.bte"), "Background: #111111\n").unwrap();
+        std::fs::write(library.join("snapshots/theme-1.bte"), "Background: #222222\n").unwrap();
+        let active = library.join("theme.bte");
+        std::fs::write(&active, "Background: #333333\n").unwrap();
+        parser::set_active_theme_locked(&active, true).unwrap();
+
+        // Only library themes are stored, not the active theme, its sidecars or snapshots
+        let snapshot = temp.path().join("snapshot.zip");
+        assert_eq!(theme::write_library_snapshot(&library, &snapshot).unwrap(), 2);
+
+        // Restoring into an empty directory recreates the library as it was
+        let restored_dir = temp.path().join("restored");
+        let restored =
+            restore_library_snapshot(&snapshot, &restored_dir, ConflictPolicy::Error).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(restored_dir.join("Night.bte")).unwrap(),
+            "Background: #111111\n"
+        );
+        assert!(!restored_dir.join("theme.bte").exists());
+
+        // Existing files block an all-or-nothing restore, or are kept alongside renames
+        std::fs::write(library.join("Light.bte"), "Background: #ffffff\n").unwrap();
+        assert!(restore_library_snapshot(&snapshot, &library, ConflictPolicy::Error).is_err());
+        restore_library_snapshot(&snapshot, &library, ConflictPolicy::Rename).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(library.join("Light.bte")).unwrap(),
+            "Background: #ffffff\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(library.join("Light_1.bte")).unwrap(),
+            "Background: #eeeeee\n"
+        );
+
+        // Overwriting never reaches the locked active theme
+        restore_library_snapshot(&snapshot, &library, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(std::fs::read_to_string(&active).unwrap(), "Background: #333333\n");
+        assert!(!library.join("theme_1.bte").exists());
+    }
+
+    #[test]
+    fn test_save_active_as() {
+        let temp = tempfile::tempdir().unwrap();
+        let active = temp.path().join("theme.bte");
+
+        assert!(save_active_into(&active, temp.path(), "Tweaked").is_err());
+
+        std::fs::write(&active, "// Theme: Dark\n\nBackground: #111111\n").unwrap();
+        let saved = save_active_into(&active, temp.path(), "Tweaked").unwrap();
+        assert_eq!(saved, temp.path().join("Tweaked.bte"));
+
+        let theme = parser::parse_theme_file(&saved).unwrap();
+        assert_eq!(theme.metadata.name.as_deref(), Some("Tweaked"));
+        assert_eq!(theme.colors["Background"], "#111111");
+
+        // The same colors aren't saved twice, whatever the name
+        assert_eq!(save_active_into(&active, temp.path(), "Again").unwrap(), saved);
+        assert!(!temp.path().join("Again.bte").exists());
+
+        std::fs::write(&active, "Background: #222222\n").unwrap();
+        assert_eq!(
+            save_active_into(&active, temp.path(), "Tweaked").unwrap(),
+            temp.path().join("Tweaked_1.bte")
+        );
+    }
+
+    #[test]
+    fn test_reset_refuses_locked_active_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("theme.bte");
+        std::fs::write(&active, "Background: #111111\n").unwrap();
+        parser::set_active_theme_locked(&active, true).unwrap();
+
+        assert!(reset_active_theme(&active, None).is_err());
+        assert!(active.exists());
+
+        reset_active_theme(&active, Some(true)).unwrap();
+        assert!(!active.exists());
+    }
+
+    #[test]
+    fn test_theme_package_export_import_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let library = temp.path().join("themes");
+        std::fs::create_dir_all(&library).unwrap();
+        let source = temp.path().join("Dark Night.bte");
+        std::fs::write(&source, "// Theme: Dark Night\n\nBackground: #111111\nAccent: #e94560\n")
+            .unwrap();
+        let preview = temp.path().join("preview.webp");
+        std::fs::write(&preview, b"RIFF....WEBP").unwrap();
+
+        // Export the way export_theme does with include_preview
+        let theme = parser::parse_theme_file(&source).unwrap();
+        let package = temp.path().join("Dark Night.zip");
+        theme::write_theme_package(
+            &package,
+            "Dark Night",
+            &parser::serialize_theme(&theme),
+            Some(&preview),
+        )
+        .unwrap();
+
+        let read = theme::read_theme_package(&package).unwrap();
+        let imported = import_theme_package(&read, &library, ConflictPolicy::Rename).unwrap();
+        assert_eq!(imported, library.join("Dark_Night.bte"));
+        assert_eq!(parser::parse_theme_file(&imported).unwrap().colors, theme.colors);
+        assert_eq!(read.preview, Some(("webp".to_string(), b"RIFF....WEBP".to_vec())));
+
+        let again = import_theme_package(&read, &library, ConflictPolicy::Rename).unwrap();
+        assert_eq!(again, library.join("Dark_Night_1.bte"));
+    }
+
+    #[test]
+    fn test_safe_mode_blocks_network_and_elevation() {
+        let temp = tempfile::tempdir().unwrap();
+        // Not writable even for root, so patching it would need elevation
+        let jar = temp.path().join("bitwig.jar");
+        std::fs::create_dir(&jar).unwrap();
+
+        settings::set_safe_mode_launch_flag(true);
+        let client = fetcher::http_client();
+        let patch = patcher::patch_jar_elevated(&jar);
+        settings::set_safe_mode_launch_flag(false);
+
+        assert!(matches!(client, Err(fetcher::FetchError::SafeModeBlocked(_))));
+        assert!(matches!(patch, Err(patcher::PatchError::SafeModeBlocked(_))));
+    }
 }
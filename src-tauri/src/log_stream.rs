@@ -0,0 +1,204 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::theme::WatcherError;
+
+/// Quiet period after a write before the new lines are read
+const LOG_READ_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Reads lines appended to a log file since the last read
+#[derive(Debug)]
+pub struct LogTail {
+    path: PathBuf,
+    /// Byte offset just past the last complete line that was read
+    offset: u64,
+}
+
+impl LogTail {
+    /// Start tailing from the current end of the file
+    pub fn from_end(path: &Path) -> Self {
+        let offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        Self {
+            path: path.to_path_buf(),
+            offset,
+        }
+    }
+
+    /// Read complete lines appended since the last call
+    /// A trailing partial line is left for the next read; a truncated file is read from the start
+    pub fn read_new_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)?;
+
+        let Some(last_newline) = appended.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        self.offset += last_newline as u64 + 1;
+
+        Ok(String::from_utf8_lossy(&appended[..last_newline])
+            .lines()
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+/// Follow the log until a stop is requested, calling `on_line` for each appended line
+fn run_log_stream(log_path: &Path, stop_rx: &Receiver<()>, mut on_line: impl FnMut(String)) {
+    let Some(log_dir) = log_path.parent() else {
+        return;
+    };
+    if std::fs::create_dir_all(log_dir).is_err() {
+        return;
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher = match RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default().with_poll_interval(Duration::from_millis(500)),
+    ) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("Failed to create log watcher: {}", e);
+            return;
+        }
+    };
+
+    // Watch the directory so the stream survives the log being created or replaced
+    if let Err(e) = watcher.watch(log_dir, RecursiveMode::NonRecursive) {
+        eprintln!("Failed to watch log directory: {}", e);
+        return;
+    }
+
+    let mut tail = LogTail::from_end(log_path);
+    let mut pending_since: Option<Instant> = None;
+
+    loop {
+        if stop_rx.try_recv().is_ok() {
+            return;
+        }
+
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(Ok(event)) => {
+                if event.paths.iter().any(|p| p == log_path) {
+                    pending_since = Some(Instant::now());
+                }
+            }
+            Ok(Err(e)) => eprintln!("Log watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if pending_since.is_some_and(|since| since.elapsed() >= LOG_READ_DEBOUNCE) {
+            pending_since = None;
+            match tail.read_new_lines() {
+                Ok(lines) => lines.into_iter().for_each(&mut on_line),
+                Err(e) => eprintln!("Failed to read log: {}", e),
+            }
+        }
+    }
+}
+
+/// Internal state for the log stream thread
+struct LogStreamThread {
+    stop_signal: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Streams appended log lines to the frontend as `log-line` events
+#[derive(Default)]
+pub struct LogStreamManager {
+    state: Mutex<Option<LogStreamThread>>,
+}
+
+impl LogStreamManager {
+    /// Start following the log file
+    pub fn start<R: tauri::Runtime>(
+        &self,
+        app_handle: AppHandle<R>,
+        log_path: PathBuf,
+    ) -> Result<(), WatcherError> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.as_ref().is_some_and(|t| !t.handle.is_finished()) {
+            return Err(WatcherError::AlreadyRunning);
+        }
+        if let Some(finished) = state.take() {
+            let _ = finished.handle.join();
+        }
+
+        let (stop_tx, stop_rx) = channel::<()>();
+        let handle = thread::spawn(move || {
+            run_log_stream(&log_path, &stop_rx, |line| {
+                if let Err(e) = app_handle.emit("log-line", &line) {
+                    eprintln!("Failed to emit log-line event: {}", e);
+                }
+            });
+        });
+
+        *state = Some(LogStreamThread {
+            stop_signal: stop_tx,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Stop following the log file
+    pub fn stop(&self) -> Result<(), WatcherError> {
+        let thread = self.state.lock().unwrap().take();
+        match thread {
+            Some(thread) => {
+                let _ = thread.stop_signal.send(());
+                let _ = thread.handle.join();
+                Ok(())
+            }
+            None => Err(WatcherError::NotRunning),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_log_tail_reads_only_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("bitwig-theme-manager.log");
+        std::fs::write(&log, "[1] before stream\n").unwrap();
+
+        let mut tail = LogTail::from_end(&log);
+        assert!(tail.read_new_lines().unwrap().is_empty());
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(b"[2] patching\n[3] partial").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["[2] patching".to_string()]);
+
+        file.write_all(b" line\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["[3] partial line".to_string()]);
+
+        // A truncated log is followed from the start again
+        std::fs::write(&log, "[4] fresh\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["[4] fresh".to_string()]);
+    }
+}
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use thiserror::Error;
 
-use super::RepositoryTheme;
+use super::{extract_theme_tags, validate_theme_index, FetchError, RepositoryTheme};
 
 #[derive(Error, Debug)]
 pub enum BundledError {
@@ -15,6 +15,9 @@ pub enum BundledError {
 
     #[error("Failed to parse index: {0}")]
     ParseIndex(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    InvalidIndex(#[from] FetchError),
 }
 
 /// A theme entry from the bundled index.json
@@ -45,7 +48,9 @@ pub fn load_bundled_themes(app: &AppHandle) -> Result<Vec<RepositoryTheme>, Bund
         .map_err(|e| BundledError::ResourcePath(e.to_string()))?;
 
     let content = std::fs::read_to_string(&index_path)?;
-    let index: BundledThemesIndex = serde_json::from_str(&content)?;
+    let raw: serde_json::Value = serde_json::from_str(&content)?;
+    validate_theme_index(&raw, "bundled")?;
+    let index: BundledThemesIndex = serde_json::from_value(raw)?;
 
     let themes = index
         .themes
@@ -71,14 +76,18 @@ pub fn load_bundled_themes(app: &AppHandle) -> Result<Vec<RepositoryTheme>, Bund
                     .map(|path| path.to_string_lossy().to_string())
             });
 
+            let tags = extract_theme_tags(&entry.name, entry.description.as_deref());
+
             RepositoryTheme {
                 name: entry.name,
                 author: entry.author,
                 author_url: None,
                 repo_url: format!("bundled://{}", entry.id),
+                preview_urls: preview_url.iter().cloned().collect(),
                 preview_url,
                 description: entry.description,
                 download_url: Some(format!("bundled://{}", file_name)),
+                tags,
             }
         })
         .collect();
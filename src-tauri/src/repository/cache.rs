@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::{Duration, SystemTime};
 use thiserror::Error;
+use tokio::task::JoinSet;
 
-use super::RepositoryTheme;
+use super::{download_bytes, http_client, RepositoryTheme};
+use crate::theme::{content_hash, parse_theme_content};
 
 #[derive(Error, Debug)]
 pub enum CacheError {
@@ -19,13 +23,40 @@ pub enum CacheError {
     CacheDirNotFound,
 }
 
+/// Version of the `repository.json` layout; bump when `CacheMetadata` or
+/// `RepositoryTheme` change incompatibly
+pub const CACHE_SCHEMA_VERSION: u32 = 1;
+
 /// Metadata for cached themes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheMetadata {
+    /// Caches written before versioning was added read as 0
+    #[serde(default)]
+    pub cache_schema_version: u32,
     pub last_updated: u64,
     pub themes: Vec<RepositoryTheme>,
 }
 
+/// Create a directory and its parents, explaining when a file is in the way
+/// `fs::create_dir_all` only reports "File exists" or "Not a directory" without saying where
+pub fn create_dir_all_checked(path: &Path) -> io::Result<()> {
+    let blocking = path.ancestors().find(|p| p.exists()).filter(|p| !p.is_dir());
+    if let Some(blocking) = blocking {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "Cannot create folder {}: {} is a file, not a folder. \
+                 Move or delete that file and try again.",
+                path.display(),
+                blocking.display()
+            ),
+        ));
+    }
+    fs::create_dir_all(path)?;
+    crate::user::hand_back(path);
+    Ok(())
+}
+
 /// Get the cache directory for the theme manager
 pub fn get_cache_dir() -> Option<PathBuf> {
     dirs::cache_dir().map(|d| d.join("bitwig-theme-manager"))
@@ -47,27 +78,46 @@ pub fn get_previews_cache_dir() -> Option<PathBuf> {
 }
 
 /// Load cached repository themes
+/// A cache written with a different schema version is discarded so it gets rebuilt
 pub fn load_cached_themes() -> Result<Option<CacheMetadata>, CacheError> {
     let cache_file = get_cache_file().ok_or(CacheError::CacheDirNotFound)?;
+    load_cached_themes_from(&cache_file)
+}
 
+fn load_cached_themes_from(cache_file: &Path) -> Result<Option<CacheMetadata>, CacheError> {
     if !cache_file.exists() {
         return Ok(None);
     }
 
-    let content = fs::read_to_string(&cache_file)?;
-    let metadata: CacheMetadata = serde_json::from_str(&content)?;
+    let content = fs::read_to_string(cache_file)?;
+    let raw: serde_json::Value = serde_json::from_str(&content)?;
+    let schema_version = raw
+        .get("cache_schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
 
+    if schema_version != CACHE_SCHEMA_VERSION as u64 {
+        crate::log_event(&format!(
+            "cache: discarding repository cache with schema version {} (expected {})",
+            schema_version, CACHE_SCHEMA_VERSION
+        ));
+        fs::remove_file(cache_file)?;
+        return Ok(None);
+    }
+
+    let metadata: CacheMetadata = serde_json::from_value(raw)?;
     Ok(Some(metadata))
 }
 
 /// Save themes to cache
 pub fn save_cached_themes(themes: &[RepositoryTheme]) -> Result<(), CacheError> {
     let cache_dir = get_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
-    fs::create_dir_all(&cache_dir)?;
+    create_dir_all_checked(&cache_dir)?;
 
     let cache_file = cache_dir.join("repository.json");
 
     let metadata = CacheMetadata {
+        cache_schema_version: CACHE_SCHEMA_VERSION,
         last_updated: SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -97,50 +147,170 @@ pub fn is_cache_stale(max_age: Duration) -> bool {
 }
 
 /// Save a downloaded theme file to the cache
-pub fn save_theme_file(theme_name: &str, content: &str) -> Result<PathBuf, CacheError> {
+pub fn save_theme_file(
+    theme_name: &str,
+    source_url: Option<&str>,
+    content: &str,
+) -> Result<PathBuf, CacheError> {
     let themes_dir = get_themes_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
-    fs::create_dir_all(&themes_dir)?;
+    create_dir_all_checked(&themes_dir)?;
 
-    // Sanitize theme name for filename
-    let safe_name: String = theme_name
-        .chars()
-        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
-        .collect();
-
-    let file_path = themes_dir.join(format!("{}.bte", safe_name));
-    fs::write(&file_path, content)?;
+    let file_path = themes_dir.join(format!("{}.bte", theme_cache_stem(theme_name, source_url)));
+    write_if_changed(&file_path, content)?;
 
     Ok(file_path)
 }
 
+/// Write `content` unless the file already holds exactly that, so re-downloading an
+/// unchanged theme doesn't rewrite it
+/// A skipped write still bumps the mtime, which `load_fresh_theme_file` reads as the time
+/// the entry was last confirmed against the source
+/// Returns whether the file was written
+fn write_if_changed(path: &Path, content: &str) -> io::Result<bool> {
+    let unchanged = fs::read_to_string(path).is_ok_and(|existing| existing == content);
+    if unchanged {
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now())?;
+        return Ok(false);
+    }
+    fs::write(path, content)?;
+    Ok(true)
+}
+
 /// Load a cached theme file
-pub fn load_cached_theme_file(theme_name: &str) -> Result<Option<String>, CacheError> {
+pub fn load_cached_theme_file(
+    theme_name: &str,
+    source_url: Option<&str>,
+) -> Result<Option<String>, CacheError> {
     let themes_dir = get_themes_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+    let file_path = themes_dir.join(format!("{}.bte", theme_cache_stem(theme_name, source_url)));
+    load_cached_theme_file_from(&file_path)
+}
 
-    let safe_name: String = theme_name
+/// Read a cached theme, treating empty, truncated or unparseable files as a cache miss
+/// Bad files are removed so the next download replaces them
+fn load_cached_theme_file_from(file_path: &Path) -> Result<Option<String>, CacheError> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let problem = match fs::read_to_string(file_path) {
+        Ok(content) => match cached_theme_problem(&content) {
+            None => return Ok(Some(content)),
+            Some(problem) => problem,
+        },
+        // Invalid UTF-8 from a download cut off mid-character
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => format!("Unreadable theme: {}", e),
+        Err(e) => return Err(e.into()),
+    };
+
+    crate::log_event(&format!(
+        "cache: ignoring corrupt cached theme {}: {}",
+        file_path.to_string_lossy(),
+        problem
+    ));
+    fs::remove_file(file_path)?;
+    Ok(None)
+}
+
+/// Describe why cached theme content can't be used, or None if it is usable
+fn cached_theme_problem(content: &str) -> Option<String> {
+    if content.trim().is_empty() {
+        return Some("Theme file is empty".to_string());
+    }
+    match parse_theme_content(content, None) {
+        Err(e) => Some(format!("Unparseable theme: {}", e)),
+        Ok(theme) if theme.colors.is_empty() => Some("Theme contains no colors".to_string()),
+        Ok(_) => None,
+    }
+}
+
+/// Load a cached theme file only if it is younger than `max_age` and newer than `not_before`
+/// Stale or corrupt entries count as a miss
+pub fn load_fresh_theme_file(
+    theme_name: &str,
+    source_url: Option<&str>,
+    max_age: Duration,
+    not_before: Option<SystemTime>,
+) -> Option<String> {
+    let file_path = get_themes_cache_dir()?
+        .join(format!("{}.bte", theme_cache_stem(theme_name, source_url)));
+    let modified = fs::metadata(&file_path).and_then(|m| m.modified()).ok()?;
+
+    let expired = modified.elapsed().map_or(true, |age| age > max_age);
+    let outdated = not_before.is_some_and(|source| modified < source);
+    if expired || outdated {
+        return None;
+    }
+
+    load_cached_theme_file(theme_name, source_url).ok().flatten()
+}
+
+/// Get the path of the cached README for a repository
+fn get_readme_cache_file(repo_url: &str) -> Option<PathBuf> {
+    let safe_name: String = repo_url
+        .trim_end_matches('/')
         .chars()
         .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
         .collect();
 
-    let file_path = themes_dir.join(format!("{}.bte", safe_name));
+    get_themes_cache_dir().map(|d| d.join(format!("{}.README.md", safe_name)))
+}
 
-    if !file_path.exists() {
-        return Ok(None);
+/// Load a cached repository README
+pub fn load_cached_readme(repo_url: &str) -> Option<String> {
+    let file_path = get_readme_cache_file(repo_url)?;
+    fs::read_to_string(file_path).ok()
+}
+
+/// Save a repository README to the cache
+pub fn save_cached_readme(repo_url: &str, content: &str) -> Result<PathBuf, CacheError> {
+    let file_path = get_readme_cache_file(repo_url).ok_or(CacheError::CacheDirNotFound)?;
+    if let Some(parent) = file_path.parent() {
+        create_dir_all_checked(parent)?;
     }
+    write_if_changed(&file_path, content)?;
+    Ok(file_path)
+}
+
+/// Whether a repository's cached README is missing or older than `max_age`
+pub fn readme_needs_refresh(repo_url: &str, max_age: Duration) -> bool {
+    get_readme_cache_file(repo_url).is_none_or(|path| is_older_than(&path, max_age))
+}
 
-    let content = fs::read_to_string(file_path)?;
-    Ok(Some(content))
+/// Whether a file is missing, or was last written more than `max_age` ago
+fn is_older_than(path: &Path, max_age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_or(true, |modified| modified.elapsed().map_or(true, |age| age > max_age))
 }
 
 /// Download and cache a preview image
 pub async fn cache_preview_image(theme_name: &str, url: &str) -> Result<PathBuf, CacheError> {
+    let client = http_client().map_err(|e| CacheError::Io(io::Error::other(e.to_string())))?;
+    cache_preview_image_with(&client, theme_name, url).await
+}
+
+/// Download and cache a preview image using an existing HTTP client
+async fn cache_preview_image_with(
+    client: &reqwest::Client,
+    theme_name: &str,
+    url: &str,
+) -> Result<PathBuf, CacheError> {
     let previews_dir = get_previews_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
-    fs::create_dir_all(&previews_dir)?;
+    cache_image_in(client, &previews_dir, &sanitize_theme_name(theme_name), url).await
+}
 
-    let safe_name: String = theme_name
-        .chars()
-        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
-        .collect();
+/// Download an image into `dir` as `<stem>.<ext>`, unless it is already there
+async fn cache_image_in(
+    client: &reqwest::Client,
+    dir: &Path,
+    stem: &str,
+    url: &str,
+) -> Result<PathBuf, CacheError> {
+    create_dir_all_checked(dir)?;
 
     // Determine file extension from URL
     let ext = url
@@ -149,28 +319,110 @@ pub async fn cache_preview_image(theme_name: &str, url: &str) -> Result<PathBuf,
         .filter(|e| ["png", "jpg", "jpeg", "gif", "webp"].contains(&e.to_lowercase().as_str()))
         .unwrap_or("png");
 
-    let file_path = previews_dir.join(format!("{}.{}", safe_name, ext));
+    let file_path = dir.join(format!("{}.{}", stem, ext));
 
     // Skip if already cached
     if file_path.exists() {
         return Ok(file_path);
     }
 
-    // Download the image
-    let client = reqwest::Client::new();
-    let response = client.get(url).send().await.map_err(|e| {
-        CacheError::Io(io::Error::other(e.to_string()))
-    })?;
-
-    let bytes = response.bytes().await.map_err(|e| {
-        CacheError::Io(io::Error::other(e.to_string()))
-    })?;
+    // Download the image, resolving Git LFS pointers to the real file
+    let bytes = download_bytes(client, url)
+        .await
+        .map_err(|e| CacheError::Io(io::Error::other(e.to_string())))?;
 
     fs::write(&file_path, bytes)?;
 
     Ok(file_path)
 }
 
+/// Cache previews for many themes at once, given `(theme_name, url)` pairs
+/// Already cached previews are not downloaded again and repeated themes are fetched once
+/// Downloads share the global network request cap
+/// Returns each theme's local preview path in input order, or `None` if it failed
+pub async fn cache_previews(entries: Vec<(String, String)>) -> Vec<(String, Option<PathBuf>)> {
+    let client = match http_client() {
+        Ok(client) => client,
+        Err(e) => {
+            crate::log_event(&format!("Could not download previews: {}", e));
+            return entries.into_iter().map(|(name, _)| (name, None)).collect();
+        }
+    };
+    let mut resolved: HashMap<String, Option<PathBuf>> = HashMap::new();
+    let mut downloads = JoinSet::new();
+
+    for (theme_name, url) in &entries {
+        if resolved.contains_key(theme_name) {
+            continue;
+        }
+
+        if let Some(path) = get_cached_preview(theme_name) {
+            resolved.insert(theme_name.clone(), Some(path));
+            continue;
+        }
+
+        resolved.insert(theme_name.clone(), None);
+
+        let client = client.clone();
+        let theme_name = theme_name.clone();
+        let url = url.clone();
+        downloads.spawn(async move {
+            let result = cache_preview_image_with(&client, &theme_name, &url).await;
+            (theme_name, result.ok())
+        });
+    }
+
+    while let Some(joined) = downloads.join_next().await {
+        if let Ok((theme_name, path)) = joined {
+            resolved.insert(theme_name, path);
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(theme_name, _)| {
+            let path = resolved.get(&theme_name).cloned().flatten();
+            (theme_name, path)
+        })
+        .collect()
+}
+
+/// Directory holding a theme's screenshots beyond the primary preview
+/// Named after the theme like its primary preview, so compaction treats them together
+pub fn get_extra_previews_dir(theme_name: &str) -> Option<PathBuf> {
+    get_previews_cache_dir().map(|d| d.join(sanitize_theme_name(theme_name)))
+}
+
+/// Cache a theme's additional screenshots, numbered from 2 after the primary preview
+/// Returns each image's local path in input order, or `None` if it failed
+pub async fn cache_extra_previews(theme_name: &str, urls: Vec<String>) -> Vec<Option<PathBuf>> {
+    let (client, dir) = match (http_client(), get_extra_previews_dir(theme_name)) {
+        (Ok(client), Some(dir)) => (client, dir),
+        (Err(e), _) => {
+            crate::log_event(&format!("Could not download previews: {}", e));
+            return vec![None; urls.len()];
+        }
+        (_, None) => return vec![None; urls.len()],
+    };
+
+    let mut downloads = JoinSet::new();
+    for (i, url) in urls.iter().enumerate() {
+        let (client, dir, url) = (client.clone(), dir.clone(), url.clone());
+        downloads.spawn(async move {
+            let path = cache_image_in(&client, &dir, &(i + 2).to_string(), &url).await.ok()?;
+            Some((i, path))
+        });
+    }
+
+    let mut paths = vec![None; urls.len()];
+    while let Some(joined) = downloads.join_next().await {
+        if let Ok(Some((i, path))) = joined {
+            paths[i] = Some(path);
+        }
+    }
+    paths
+}
+
 /// Get the cached preview image path if it exists
 pub fn get_cached_preview(theme_name: &str) -> Option<PathBuf> {
     let previews_dir = get_previews_cache_dir()?;
@@ -190,6 +442,76 @@ pub fn get_cached_preview(theme_name: &str) -> Option<PathBuf> {
     None
 }
 
+/// Largest preview inlined as a data URL; bigger images stay on disk only
+const MAX_PREVIEW_DATA_URL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Detect an image's MIME type from its leading bytes
+pub fn image_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Standard base64 with padding
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Read an image into a `data:` URL
+/// Returns `None` for missing, oversized or unrecognized files
+pub fn image_data_url(path: &Path) -> Option<String> {
+    if fs::metadata(path).ok()?.len() > MAX_PREVIEW_DATA_URL_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let mime = image_mime_type(&bytes)?;
+    Some(format!("data:{};base64,{}", mime, encode_base64(&bytes)))
+}
+
+/// Get a theme's cached preview as a `data:` URL, for webviews that can't load file paths
+pub fn get_cached_preview_data_url(theme_name: &str) -> Option<String> {
+    image_data_url(&get_cached_preview(theme_name)?)
+}
+
+/// Store preview image bytes for a theme, e.g. from an imported theme package
+pub fn save_preview_image(
+    theme_name: &str,
+    ext: &str,
+    bytes: &[u8],
+) -> Result<PathBuf, CacheError> {
+    let previews_dir = get_previews_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+    create_dir_all_checked(&previews_dir)?;
+
+    let file_path = previews_dir.join(format!("{}.{}", sanitize_theme_name(theme_name), ext));
+    fs::write(&file_path, bytes)?;
+    Ok(file_path)
+}
+
 /// Clear all cached data
 pub fn clear_cache() -> Result<(), CacheError> {
     let cache_dir = get_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
@@ -201,6 +523,59 @@ pub fn clear_cache() -> Result<(), CacheError> {
     Ok(())
 }
 
+/// What `clear_cache` would delete
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct CacheSummary {
+    /// Themes listed in the cached repository index
+    pub repository_entries: usize,
+    pub cached_theme_files: usize,
+    pub preview_images: usize,
+    /// Size of everything under the cache directory, backups included
+    pub total_bytes: u64,
+    /// Size of the Bitwig JAR backups kept in the cache directory
+    pub backups_bytes: u64,
+}
+
+/// Count the files under a directory and their total size; missing directories are empty
+fn directory_usage(dir: &Path) -> (usize, u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    entries.flatten().fold((0, 0), |(files, bytes), entry| {
+        let Ok(file_type) = entry.file_type() else {
+            return (files, bytes);
+        };
+        if file_type.is_dir() {
+            let (sub_files, sub_bytes) = directory_usage(&entry.path());
+            (files + sub_files, bytes + sub_bytes)
+        } else {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            (files + 1, bytes + size)
+        }
+    })
+}
+
+/// Summarize what `clear_cache` would delete, without changing anything
+pub fn cache_summary() -> Result<CacheSummary, CacheError> {
+    let cache_dir = get_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+    Ok(cache_summary_in(&cache_dir))
+}
+
+fn cache_summary_in(cache_dir: &Path) -> CacheSummary {
+    let repository_entries = fs::read_to_string(cache_dir.join("repository.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<CacheMetadata>(&content).ok())
+        .map_or(0, |metadata| metadata.themes.len());
+
+    CacheSummary {
+        repository_entries,
+        cached_theme_files: directory_usage(&cache_dir.join("themes")).0,
+        preview_images: directory_usage(&cache_dir.join("previews")).0,
+        total_bytes: directory_usage(cache_dir).1,
+        backups_bytes: directory_usage(&cache_dir.join("backups")).1,
+    }
+}
+
 /// Get list of all cached theme files
 pub fn list_cached_themes() -> Result<Vec<PathBuf>, CacheError> {
     let themes_dir = get_themes_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
@@ -224,6 +599,380 @@ pub fn list_cached_themes() -> Result<Vec<PathBuf>, CacheError> {
     Ok(themes)
 }
 
+/// Get the directory holding whole-library theme snapshots
+pub fn get_snapshots_dir() -> Option<PathBuf> {
+    get_cache_dir().map(|d| d.join("snapshots"))
+}
+
+/// A zipped copy of a Bitwig version's theme directory
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LibrarySnapshot {
+    pub path: PathBuf,
+    pub bitwig_version: String,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// File name for a new snapshot: `<version>_<timestamp>.zip`
+/// Spaces in the version (`5.3 Beta 1`) are stored as underscores
+pub fn snapshot_file_name(bitwig_version: &str, created_at: u64) -> String {
+    let version: String = bitwig_version
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}_{}.zip", version, created_at)
+}
+
+/// List library snapshots, newest first
+pub fn list_library_snapshots() -> Result<Vec<LibrarySnapshot>, CacheError> {
+    let snapshots_dir = get_snapshots_dir().ok_or(CacheError::CacheDirNotFound)?;
+    list_library_snapshots_in(&snapshots_dir)
+}
+
+fn list_library_snapshots_in(snapshots_dir: &Path) -> Result<Vec<LibrarySnapshot>, CacheError> {
+    if !snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(snapshots_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Some(stem) = path
+            .extension()
+            .filter(|ext| *ext == "zip")
+            .and_then(|_| path.file_stem())
+            .map(|s| s.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        let Some((version, created_at)) = stem
+            .rsplit_once('_')
+            .and_then(|(version, ts)| Some((version.replace('_', " "), ts.parse().ok()?)))
+        else {
+            continue;
+        };
+
+        snapshots.push(LibrarySnapshot {
+            size_bytes: entry.metadata()?.len(),
+            path,
+            bitwig_version: version,
+            created_at,
+        });
+    }
+
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(snapshots)
+}
+
+/// Get the directory holding diagnostic dumps, one subdirectory per run
+pub fn get_debug_dumps_dir() -> Option<PathBuf> {
+    get_cache_dir().map(|d| d.join("debug"))
+}
+
+/// Name of this run's dump directory: the Unix time of the first dump
+fn debug_run_name() -> &'static str {
+    static RUN: OnceLock<String> = OnceLock::new();
+    RUN.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            .to_string()
+    })
+}
+
+/// Save content that failed to process for later inspection
+/// Returns the dump's path under this run's debug directory
+pub fn write_debug_dump(name: &str, content: &str) -> Result<PathBuf, CacheError> {
+    let debug_dir = get_debug_dumps_dir().ok_or(CacheError::CacheDirNotFound)?;
+    write_debug_dump_in(&debug_dir.join(debug_run_name()), name, content)
+}
+
+fn write_debug_dump_in(run_dir: &Path, name: &str, content: &str) -> Result<PathBuf, CacheError> {
+    create_dir_all_checked(run_dir)?;
+    let path = run_dir.join(format!("{}.txt", sanitize_theme_name(name)));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// A saved diagnostic dump
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DebugDump {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// List saved diagnostic dumps from all runs, oldest run first
+pub fn list_debug_dumps() -> Result<Vec<DebugDump>, CacheError> {
+    let debug_dir = get_debug_dumps_dir().ok_or(CacheError::CacheDirNotFound)?;
+    list_debug_dumps_in(&debug_dir)
+}
+
+fn list_debug_dumps_in(debug_dir: &Path) -> Result<Vec<DebugDump>, CacheError> {
+    if !debug_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut dumps = Vec::new();
+    for run in fs::read_dir(debug_dir)? {
+        let run = run?.path();
+        if !run.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&run)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                dumps.push(DebugDump {
+                    path: entry.path(),
+                    size_bytes: metadata.len(),
+                });
+            }
+        }
+    }
+
+    dumps.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(dumps)
+}
+
+/// Delete all diagnostic dumps, returning how many were removed
+pub fn clear_debug_dumps() -> Result<usize, CacheError> {
+    let debug_dir = get_debug_dumps_dir().ok_or(CacheError::CacheDirNotFound)?;
+    clear_debug_dumps_in(&debug_dir)
+}
+
+fn clear_debug_dumps_in(debug_dir: &Path) -> Result<usize, CacheError> {
+    let count = list_debug_dumps_in(debug_dir)?.len();
+    if debug_dir.exists() {
+        fs::remove_dir_all(debug_dir)?;
+    }
+    Ok(count)
+}
+
+/// Sanitize a theme name the same way cached files are named
+pub fn sanitize_theme_name(theme_name: &str) -> String {
+    theme_name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// File stem of a cached theme: the sanitized name, readable when browsing the cache, plus
+/// a hash of the source URL (or of the exact name, without one), since names like
+/// "Foo Bar" and "Foo_Bar" sanitize to the same string
+pub fn theme_cache_stem(theme_name: &str, source_url: Option<&str>) -> String {
+    let hash = content_hash(source_url.unwrap_or(theme_name));
+    format!("{}-{}", sanitize_theme_name(theme_name), &hash[..12])
+}
+
+/// Remove cached theme files and previews for themes no longer in the repository
+/// Returns the file names that were removed
+pub fn compact_cache() -> Result<Vec<String>, CacheError> {
+    let themes = match load_cached_themes()? {
+        Some(cache) => cache.themes,
+        None => return Ok(Vec::new()),
+    };
+    let themes_dir = get_themes_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+    let previews_dir = get_previews_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+
+    compact_cache_dirs(&themes_dir, &previews_dir, &themes)
+}
+
+fn compact_cache_dirs(
+    themes_dir: &Path,
+    previews_dir: &Path,
+    themes: &[RepositoryTheme],
+) -> Result<Vec<String>, CacheError> {
+    let valid_themes: HashSet<String> = themes
+        .iter()
+        .map(|t| theme_cache_stem(&t.name, t.download_url.as_deref()))
+        .collect();
+    let valid_previews: HashSet<String> =
+        themes.iter().map(|t| sanitize_theme_name(&t.name)).collect();
+    let mut removed = Vec::new();
+
+    let dirs: [(&Path, &[&str], &HashSet<String>); 2] = [
+        (themes_dir, &["bte"], &valid_themes),
+        (previews_dir, &["png", "jpg", "jpeg", "gif", "webp"], &valid_previews),
+    ];
+
+    for (dir, extensions, valid) in dirs {
+        if !dir.exists() {
+            continue;
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            // Extra screenshots sit in a directory named after their theme
+            if dir == previews_dir && fs::symlink_metadata(&path)?.is_dir() {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if !valid.contains(&name) {
+                    fs::remove_dir_all(&path)?;
+                    removed.push(format!("{}/", name));
+                }
+                continue;
+            }
+            if !path.is_file() {
+                continue;
+            }
+
+            let ext = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !extensions.contains(&ext.as_str()) {
+                continue;
+            }
+
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if valid.contains(&stem) {
+                continue;
+            }
+
+            fs::remove_file(&path)?;
+            removed.push(
+                path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            );
+        }
+    }
+
+    removed.sort();
+    Ok(removed)
+}
+
+/// A cached file that failed validation
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CorruptCacheEntry {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Result of checking the cache for corruption
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct CacheHealth {
+    /// Whether `repository.json` exists and parses
+    pub repository_valid: bool,
+    pub themes_checked: usize,
+    pub previews_checked: usize,
+    pub corrupt: Vec<CorruptCacheEntry>,
+}
+
+/// Check whether bytes start with the signature of a supported preview image format
+fn has_image_signature(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x89PNG\r\n\x1a\n")
+        || bytes.starts_with(&[0xff, 0xd8, 0xff])
+        || bytes.starts_with(b"GIF87a")
+        || bytes.starts_with(b"GIF89a")
+        || (bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP")
+}
+
+/// Check the repository index, cached themes and previews for corruption
+pub fn validate_cache() -> Result<CacheHealth, CacheError> {
+    let cache_file = get_cache_file().ok_or(CacheError::CacheDirNotFound)?;
+    let themes_dir = get_themes_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+    let previews_dir = get_previews_cache_dir().ok_or(CacheError::CacheDirNotFound)?;
+
+    validate_cache_files(&cache_file, &themes_dir, &previews_dir)
+}
+
+/// Delete every corrupt cache entry, returning the removed paths
+pub fn repair_cache() -> Result<Vec<String>, CacheError> {
+    let health = validate_cache()?;
+    remove_corrupt_entries(&health)
+}
+
+fn remove_corrupt_entries(health: &CacheHealth) -> Result<Vec<String>, CacheError> {
+    let mut removed = Vec::new();
+    for entry in &health.corrupt {
+        let path = Path::new(&entry.path);
+        if path.exists() {
+            fs::remove_file(path)?;
+            removed.push(entry.path.clone());
+        }
+    }
+    Ok(removed)
+}
+
+fn validate_cache_files(
+    cache_file: &Path,
+    themes_dir: &Path,
+    previews_dir: &Path,
+) -> Result<CacheHealth, CacheError> {
+    let mut health = CacheHealth::default();
+    let mut corrupt = Vec::new();
+
+    if cache_file.exists() {
+        let parsed = fs::read_to_string(cache_file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<CacheMetadata>(&content).map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok(_) => health.repository_valid = true,
+            Err(e) => corrupt.push((cache_file.to_path_buf(), format!("Invalid index: {}", e))),
+        }
+    }
+
+    if themes_dir.exists() {
+        for entry in fs::read_dir(themes_dir)? {
+            let path = entry?.path();
+            if !path.is_file() || !path.extension().is_some_and(|ext| ext == "bte") {
+                continue;
+            }
+            health.themes_checked += 1;
+
+            let reason = match fs::read_to_string(&path) {
+                Err(e) => Some(format!("Unreadable theme: {}", e)),
+                Ok(content) => cached_theme_problem(&content),
+            };
+            if let Some(reason) = reason {
+                corrupt.push((path, reason));
+            }
+        }
+    }
+
+    if previews_dir.exists() {
+        // Includes the extra screenshots in each theme's directory
+        let entries = walkdir::WalkDir::new(previews_dir).min_depth(1).max_depth(2);
+        for entry in entries {
+            let path = entry.map_err(io::Error::from)?.into_path();
+            if !path.is_file() {
+                continue;
+            }
+            health.previews_checked += 1;
+
+            let bytes = fs::read(&path)?;
+            if bytes.is_empty() {
+                corrupt.push((path, "Preview is empty".to_string()));
+            } else if !has_image_signature(&bytes) {
+                corrupt.push((path, "Preview is not a valid image".to_string()));
+            }
+        }
+    }
+
+    health.corrupt = corrupt
+        .into_iter()
+        .map(|(path, reason)| CorruptCacheEntry {
+            path: path.to_string_lossy().to_string(),
+            reason,
+        })
+        .collect();
+    health.corrupt.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(health)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,11 +985,310 @@ mod tests {
 
     #[test]
     fn test_sanitize_theme_name() {
-        let name = "Theme/With:Special*Chars";
-        let safe: String = name
-            .chars()
-            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        assert_eq!(sanitize_theme_name("Theme/With:Special*Chars"), "Theme_With_Special_Chars");
+    }
+
+    #[test]
+    fn test_theme_cache_stem() {
+        let url = Some("https://codeberg.org/someone/themes/raw/foo.bte");
+        assert!(theme_cache_stem("Foo Bar", url).starts_with("Foo_Bar-"));
+        assert_ne!(theme_cache_stem("Foo Bar", None), theme_cache_stem("Foo_Bar", None));
+        assert_ne!(theme_cache_stem("Foo", url), theme_cache_stem("Foo", None));
+        assert_eq!(theme_cache_stem("Foo", url), theme_cache_stem("Foo", url));
+    }
+
+    #[test]
+    fn test_compact_cache_removes_orphans() {
+        let temp = tempfile::tempdir().unwrap();
+        let themes_dir = temp.path().join("themes");
+        let previews_dir = temp.path().join("previews");
+        fs::create_dir_all(&themes_dir).unwrap();
+        fs::create_dir_all(&previews_dir).unwrap();
+
+        let kept = themes_dir.join(format!("{}.bte", theme_cache_stem("Still Here", None)));
+        fs::write(&kept, "Window.Background: #000000").unwrap();
+        fs::write(themes_dir.join("Removed.bte"), "Window.Background: #ffffff").unwrap();
+        fs::write(themes_dir.join("notes.txt"), "not a theme").unwrap();
+        fs::write(previews_dir.join("Still_Here.png"), b"png").unwrap();
+        fs::write(previews_dir.join("Removed.jpg"), b"jpg").unwrap();
+        fs::create_dir_all(previews_dir.join("Still_Here")).unwrap();
+        fs::write(previews_dir.join("Still_Here").join("2.png"), b"png").unwrap();
+        fs::create_dir_all(previews_dir.join("Removed")).unwrap();
+        fs::write(previews_dir.join("Removed").join("2.png"), b"png").unwrap();
+
+        let themes = vec![RepositoryTheme {
+            name: "Still Here".to_string(),
+            author: "someone".to_string(),
+            author_url: None,
+            repo_url: "bundled://still-here".to_string(),
+            preview_url: None,
+            preview_urls: Vec::new(),
+            description: None,
+            download_url: None,
+            tags: Vec::new(),
+        }];
+
+        let removed = compact_cache_dirs(&themes_dir, &previews_dir, &themes).unwrap();
+
+        assert_eq!(removed, vec!["Removed.bte", "Removed.jpg", "Removed/"]);
+        assert!(kept.exists());
+        assert!(themes_dir.join("notes.txt").exists());
+        assert!(previews_dir.join("Still_Here.png").exists());
+        assert!(previews_dir.join("Still_Here").join("2.png").exists());
+        assert!(!previews_dir.join("Removed").exists());
+        assert!(!themes_dir.join("Removed.bte").exists());
+        assert!(!previews_dir.join("Removed.jpg").exists());
+    }
+
+    #[test]
+    fn test_validate_and_repair_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_file = temp.path().join("repository.json");
+        let themes_dir = temp.path().join("themes");
+        let previews_dir = temp.path().join("previews");
+        fs::create_dir_all(&themes_dir).unwrap();
+        fs::create_dir_all(&previews_dir).unwrap();
+
+        fs::write(&cache_file, "{ \"last_updated\": 1, \"themes\": [").unwrap();
+        fs::write(themes_dir.join("Good.bte"), "Window.Background: #000000").unwrap();
+        fs::write(themes_dir.join("Empty.bte"), "not a theme").unwrap();
+        fs::write(themes_dir.join("Binary.bte"), [0xff, 0xfe, 0x00]).unwrap();
+        fs::write(previews_dir.join("Good.png"), b"\x89PNG\r\n\x1a\n....").unwrap();
+        fs::write(previews_dir.join("Blank.png"), b"").unwrap();
+        fs::write(previews_dir.join("Html.jpg"), b"<html>404</html>").unwrap();
+
+        let health = validate_cache_files(&cache_file, &themes_dir, &previews_dir).unwrap();
+
+        assert!(!health.repository_valid);
+        assert_eq!(health.themes_checked, 3);
+        assert_eq!(health.previews_checked, 3);
+        let names: Vec<String> = health
+            .corrupt
+            .iter()
+            .map(|e| Path::new(&e.path).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["Blank.png", "Html.jpg", "repository.json", "Binary.bte", "Empty.bte"]
+        );
+
+        let removed = remove_corrupt_entries(&health).unwrap();
+        assert_eq!(removed.len(), 5);
+        assert!(!cache_file.exists());
+        assert!(themes_dir.join("Good.bte").exists());
+        assert!(previews_dir.join("Good.png").exists());
+
+        let health = validate_cache_files(&cache_file, &themes_dir, &previews_dir).unwrap();
+        assert!(health.corrupt.is_empty());
+    }
+
+    #[test]
+    fn test_load_cache_discards_old_schema() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_file = temp.path().join("repository.json");
+
+        // Written before schema versioning, with a since-removed field
+        fs::write(
+            &cache_file,
+            r#"{ "last_updated": 1, "themes": [{ "name": "Old", "stars": 3 }] }"#,
+        )
+        .unwrap();
+
+        assert!(load_cached_themes_from(&cache_file).unwrap().is_none());
+        assert!(!cache_file.exists());
+    }
+
+    #[test]
+    fn test_load_cache_current_schema() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_file = temp.path().join("repository.json");
+
+        let content = serde_json::json!({
+            "cache_schema_version": CACHE_SCHEMA_VERSION,
+            "last_updated": 1,
+            "themes": [{ "name": "New" }],
+        });
+        fs::write(&cache_file, content.to_string()).unwrap();
+
+        let cache = load_cached_themes_from(&cache_file).unwrap().unwrap();
+        assert_eq!(cache.themes[0].name, "New");
+        assert_eq!(cache.themes[0].repo_url, "");
+    }
+
+    #[test]
+    fn test_corrupt_cached_theme_is_a_miss() {
+        let temp = tempfile::tempdir().unwrap();
+        let empty = temp.path().join("Empty.bte");
+        let truncated = temp.path().join("Truncated.bte");
+        let good = temp.path().join("Good.bte");
+
+        fs::write(&empty, "").unwrap();
+        fs::write(&truncated, "{ \"window\": { \"Background\": \"#1").unwrap();
+        fs::write(&good, "Background: #111111\n").unwrap();
+
+        assert!(load_cached_theme_file_from(&empty).unwrap().is_none());
+        assert!(!empty.exists());
+        assert!(load_cached_theme_file_from(&truncated).unwrap().is_none());
+        assert_eq!(
+            load_cached_theme_file_from(&good).unwrap().as_deref(),
+            Some("Background: #111111\n")
+        );
+    }
+
+    #[test]
+    fn test_create_dir_all_checked_reports_file_in_the_way() {
+        let temp = tempfile::tempdir().unwrap();
+        let blocking = temp.path().join("Themes");
+        fs::write(&blocking, "not a folder").unwrap();
+
+        let err = create_dir_all_checked(&blocking.join("5.2")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(err.to_string().contains(&blocking.display().to_string()));
+        assert!(err.to_string().contains("is a file, not a folder"));
+
+        let nested = temp.path().join("a").join("b");
+        create_dir_all_checked(&nested).unwrap();
+        assert!(nested.is_dir());
+        create_dir_all_checked(&nested).unwrap();
+    }
+
+    #[test]
+    fn test_write_if_changed_skips_identical_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("Dark.bte");
+        assert!(write_if_changed(&path, "Background: #111111\n").unwrap());
+
+        // Backdate the file so the mtime bump on a skipped write is visible
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+
+        assert!(!write_if_changed(&path, "Background: #111111\n").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Background: #111111\n");
+        // The skipped write still marks the entry as fresh
+        assert!(fs::metadata(&path).unwrap().modified().unwrap() > old);
+
+        fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+        assert!(write_if_changed(&path, "Background: #222222\n").unwrap());
+        assert_ne!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+    }
+
+    #[test]
+    fn test_cache_summary() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_dir = temp.path();
+        assert_eq!(cache_summary_in(&cache_dir.join("missing")), CacheSummary::default());
+
+        let metadata = CacheMetadata {
+            cache_schema_version: CACHE_SCHEMA_VERSION,
+            last_updated: 0,
+            themes: vec![RepositoryTheme {
+                name: "Dark".to_string(),
+                author: "someone".to_string(),
+                author_url: None,
+                repo_url: "bundled://dark".to_string(),
+                preview_url: None,
+                preview_urls: Vec::new(),
+                description: None,
+                download_url: None,
+                tags: Vec::new(),
+            }],
+        };
+        fs::write(cache_dir.join("repository.json"), serde_json::to_string(&metadata).unwrap())
+            .unwrap();
+        fs::create_dir_all(cache_dir.join("themes")).unwrap();
+        fs::write(cache_dir.join("themes").join("Dark.bte"), "Background: #111111\n").unwrap();
+        fs::write(cache_dir.join("themes").join("Light.bte"), "Background: #eeeeee\n").unwrap();
+        fs::create_dir_all(cache_dir.join("previews").join("rendered")).unwrap();
+        fs::write(cache_dir.join("previews").join("Dark.png"), [0u8; 100]).unwrap();
+        fs::write(cache_dir.join("previews").join("rendered").join("ab.png"), [0u8; 50])
+            .unwrap();
+        fs::create_dir_all(cache_dir.join("backups").join("abc123")).unwrap();
+        fs::write(cache_dir.join("backups").join("abc123").join("1.jar"), [0u8; 1000]).unwrap();
+
+        let summary = cache_summary_in(cache_dir);
+        assert_eq!(summary.repository_entries, 1);
+        assert_eq!(summary.cached_theme_files, 2);
+        assert_eq!(summary.preview_images, 2);
+        assert_eq!(summary.backups_bytes, 1000);
+        let index_bytes = fs::metadata(cache_dir.join("repository.json")).unwrap().len();
+        assert_eq!(summary.total_bytes, index_bytes + 40 + 150 + 1000);
+    }
+
+    #[test]
+    fn test_debug_dumps() {
+        let temp = tempfile::tempdir().unwrap();
+        let debug_dir = temp.path().join("debug");
+        assert!(list_debug_dumps_in(&debug_dir).unwrap().is_empty());
+
+        let first = write_debug_dump_in(&debug_dir.join("100"), "convert Dark", "{bad").unwrap();
+        write_debug_dump_in(&debug_dir.join("200"), "convert Light", "{worse").unwrap();
+        assert_eq!(first, debug_dir.join("100").join("convert_Dark.txt"));
+
+        let dumps = list_debug_dumps_in(&debug_dir).unwrap();
+        assert_eq!(dumps.len(), 2);
+        assert_eq!(dumps[0], DebugDump { path: first, size_bytes: 4 });
+
+        assert_eq!(clear_debug_dumps_in(&debug_dir).unwrap(), 2);
+        assert!(!debug_dir.exists());
+        assert_eq!(clear_debug_dumps_in(&debug_dir).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_is_older_than() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("repo.README.md");
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(is_older_than(&path, day));
+
+        fs::write(&path, "# awesome-bitwig-themes\n").unwrap();
+        assert!(!is_older_than(&path, day));
+
+        let old = SystemTime::now() - 2 * day;
+        fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+        assert!(is_older_than(&path, day));
+
+        // Saving the same README again counts as a refresh
+        assert!(!write_if_changed(&path, "# awesome-bitwig-themes\n").unwrap());
+        assert!(!is_older_than(&path, day));
+    }
+
+    #[test]
+    fn test_image_data_url() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("Dark.png");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n").unwrap();
+        assert_eq!(
+            image_data_url(&png).as_deref(),
+            Some("data:image/png;base64,iVBORw0KGgo=")
+        );
+
+        // The extension doesn't matter, only the content
+        let text = dir.path().join("Fake.png");
+        fs::write(&text, "not an image").unwrap();
+        assert_eq!(image_data_url(&text), None);
+        assert_eq!(image_data_url(&dir.path().join("missing.png")), None);
+    }
+
+    #[test]
+    fn test_list_library_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(snapshot_file_name("5.2", 100)), b"zip").unwrap();
+        fs::write(dir.path().join(snapshot_file_name("5.3 Beta 1", 200)), b"zip").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+        fs::write(dir.path().join("nostamp.zip"), b"ignored").unwrap();
+
+        let snapshots = list_library_snapshots_in(dir.path()).unwrap();
+        let listed: Vec<(&str, u64)> = snapshots
+            .iter()
+            .map(|s| (s.bitwig_version.as_str(), s.created_at))
             .collect();
-        assert_eq!(safe, "Theme_With_Special_Chars");
+        assert_eq!(listed, vec![("5.3 Beta 1", 200), ("5.2", 100)]);
+        assert_eq!(snapshots[0].size_bytes, 3);
     }
 }
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{FetchError, RepositoryTheme};
+
+/// Identifies a favorited repository theme, in the form shared between users
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FavoriteTheme {
+    pub name: String,
+    pub repo_url: String,
+    pub author: String,
+}
+
+impl FavoriteTheme {
+    /// Whether two entries name the same theme, ignoring case and trailing slashes
+    fn same_theme(&self, other: &FavoriteTheme) -> bool {
+        let url = |f: &FavoriteTheme| f.repo_url.trim().trim_end_matches('/').to_lowercase();
+        self.name.trim().eq_ignore_ascii_case(other.name.trim()) && url(self) == url(other)
+    }
+}
+
+impl From<&RepositoryTheme> for FavoriteTheme {
+    fn from(theme: &RepositoryTheme) -> Self {
+        Self {
+            name: theme.name.clone(),
+            repo_url: theme.repo_url.clone(),
+            author: theme.author.clone(),
+        }
+    }
+}
+
+/// Get the file holding the user's favorite themes
+pub fn get_favorites_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("bitwig-theme-manager").join("favorites.json"))
+}
+
+/// Read a favorites file; a missing or unreadable file is empty
+pub fn load_favorites(path: &Path) -> Vec<FavoriteTheme> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_favorites(path: &Path, favorites: &[FavoriteTheme]) -> Result<(), FetchError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(favorites)?)?;
+    Ok(())
+}
+
+/// Add themes to the favorites store, skipping ones already there
+/// Returns how many were new
+pub fn merge_favorites(store: &Path, themes: Vec<FavoriteTheme>) -> Result<usize, FetchError> {
+    let mut favorites = load_favorites(store);
+    let before = favorites.len();
+    for theme in themes {
+        if !favorites.iter().any(|f| f.same_theme(&theme)) {
+            favorites.push(theme);
+        }
+    }
+
+    let added = favorites.len() - before;
+    if added > 0 {
+        write_favorites(store, &favorites)?;
+    }
+    Ok(added)
+}
+
+/// Remove a theme from the favorites store
+pub fn remove_favorite(store: &Path, theme: &FavoriteTheme) -> Result<(), FetchError> {
+    let mut favorites = load_favorites(store);
+    favorites.retain(|f| !f.same_theme(theme));
+    write_favorites(store, &favorites)
+}
+
+/// Write the favorites to a file another user can import
+pub fn export_favorites(store: &Path, dest: &Path) -> Result<usize, FetchError> {
+    let favorites = load_favorites(store);
+    write_favorites(dest, &favorites)?;
+    Ok(favorites.len())
+}
+
+/// Merge a shared favorites file into the store, returning how many were new
+/// Unlike the store itself, an unreadable import file is an error
+pub fn import_favorites(store: &Path, src: &Path) -> Result<usize, FetchError> {
+    let imported: Vec<FavoriteTheme> = serde_json::from_str(&fs::read_to_string(src)?)?;
+    merge_favorites(store, imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(name: &str, repo_url: &str) -> FavoriteTheme {
+        FavoriteTheme {
+            name: name.to_string(),
+            repo_url: repo_url.to_string(),
+            author: "someone".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_favorites_export_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mine = dir.path().join("mine.json");
+        let theirs = dir.path().join("theirs.json");
+        let shared = dir.path().join("shared.json");
+
+        merge_favorites(&mine, vec![favorite("Dark", "https://github.com/a/dark")]).unwrap();
+        merge_favorites(
+            &theirs,
+            vec![
+                favorite("dark", "https://github.com/a/dark/"),
+                favorite("Light", "https://github.com/b/light"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(export_favorites(&theirs, &shared).unwrap(), 2);
+        assert_eq!(import_favorites(&mine, &shared).unwrap(), 1);
+        assert_eq!(import_favorites(&mine, &shared).unwrap(), 0);
+
+        let names: Vec<String> = load_favorites(&mine).into_iter().map(|f| f.name).collect();
+        assert_eq!(names, vec!["Dark".to_string(), "Light".to_string()]);
+
+        fs::write(&shared, "not json").unwrap();
+        assert!(import_favorites(&mine, &shared).is_err());
+    }
+}
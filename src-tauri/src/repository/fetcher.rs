@@ -1,5 +1,15 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+
+use crate::settings;
+use crate::theme::{is_json_content, parse_theme_content};
 
 #[derive(Error, Debug)]
 pub enum FetchError {
@@ -8,18 +18,1321 @@ pub enum FetchError {
 
     #[error("JSON parse error: {0}")]
     Json(#[from] serde_json::Error),
+
+    #[error("Invalid theme index: {0}")]
+    InvalidIndex(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("GitHub rate limit reached, resets in {0}s")]
+    RateLimited(u64),
+
+    #[error("{0} is a Git LFS pointer and the real file could not be downloaded")]
+    LfsPointer(String),
+
+    #[error("Invalid proxy URL: {0}")]
+    InvalidProxy(String),
+
+    #[error("Could not connect through the proxy: {0}")]
+    Proxy(String),
+
+    #[error("Safe mode is on: {0}")]
+    SafeModeBlocked(String),
 }
 
 /// A theme entry from the repository
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct RepositoryTheme {
     pub name: String,
     pub author: String,
     pub author_url: Option<String>,
     pub repo_url: String,
     pub preview_url: Option<String>,
+    /// Every screenshot known for the theme; `preview_url` stays the primary one
+    pub preview_urls: Vec<String>,
     pub description: Option<String>,
     /// Direct download URL (for bundled themes)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download_url: Option<String>,
+    /// Keywords from `THEME_TAGS` found in the name or description
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Tags themes can be browsed by, each with the phrases that imply it
+/// Phrases match whole words; hyphens and other punctuation count as spaces
+pub const THEME_TAGS: &[(&str, &[&str])] = &[
+    ("dark", &["dark", "night", "midnight"]),
+    ("light", &["light", "bright"]),
+    ("high-contrast", &["high contrast"]),
+    ("monochrome", &["monochrome", "grayscale", "greyscale"]),
+    ("colorful", &["colorful", "colourful", "vibrant"]),
+    ("pastel", &["pastel"]),
+    ("neon", &["neon", "cyberpunk", "synthwave"]),
+    ("retro", &["retro", "vintage"]),
+    ("minimal", &["minimal", "minimalist", "clean"]),
+    ("nord", &["nord"]),
+    ("dracula", &["dracula"]),
+    ("solarized", &["solarized"]),
+    ("gruvbox", &["gruvbox"]),
+    ("monokai", &["monokai"]),
+    ("catppuccin", &["catppuccin"]),
+];
+
+/// Lowercase words of `text` joined by single spaces, padded so phrases can be
+/// matched as ` phrase `
+fn tag_words(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+/// Tags from `THEME_TAGS` that a theme's name or description mentions
+pub fn extract_theme_tags(name: &str, description: Option<&str>) -> Vec<String> {
+    let text = tag_words(&format!("{} {}", name, description.unwrap_or_default()));
+    THEME_TAGS
+        .iter()
+        .filter(|(_, phrases)| {
+            phrases
+                .iter()
+                .any(|phrase| text.contains(&tag_words(phrase)))
+        })
+        .map(|(tag, _)| tag.to_string())
+        .collect()
+}
+
+/// Validate the structure of a theme index before deserializing it
+/// `label` names the index in error messages (e.g. "bundled", "community")
+pub fn validate_theme_index(index: &Value, label: &str) -> Result<(), FetchError> {
+    let invalid = |msg: String| FetchError::InvalidIndex(format!("{} index {}", label, msg));
+
+    let root = index
+        .as_object()
+        .ok_or_else(|| invalid("is not a JSON object".to_string()))?;
+
+    // The index structs hold the version as a u32, so larger numbers wouldn't deserialize
+    let version = root.get("version").and_then(Value::as_u64);
+    if !version.is_some_and(|v| u32::try_from(v).is_ok()) {
+        return Err(invalid("is missing a numeric 'version'".to_string()));
+    }
+
+    let themes = root
+        .get("themes")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("is missing the 'themes' array".to_string()))?;
+
+    let mut seen_ids = HashSet::new();
+
+    for (i, entry) in themes.iter().enumerate() {
+        let entry = entry
+            .as_object()
+            .ok_or_else(|| invalid(format!("entry {} is not an object", i)))?;
+
+        for field in ["id", "name", "author", "file"] {
+            match entry.get(field) {
+                Some(Value::String(value)) if !value.trim().is_empty() => {}
+                Some(Value::String(_)) => {
+                    return Err(invalid(format!("entry {} has an empty '{}'", i, field)))
+                }
+                Some(_) => {
+                    return Err(invalid(format!("entry {} has a non-string '{}'", i, field)))
+                }
+                None => return Err(invalid(format!("entry {} is missing '{}'", i, field))),
+            }
+        }
+
+        for field in ["preview", "description"] {
+            if let Some(value) = entry.get(field) {
+                if !value.is_string() && !value.is_null() {
+                    return Err(invalid(format!("entry {} has a non-string '{}'", i, field)));
+                }
+            }
+        }
+
+        let file = entry["file"].as_str().unwrap_or_default().to_lowercase();
+        if !file.ends_with(".bte") && !file.ends_with(".json") {
+            return Err(invalid(format!(
+                "entry {} has 'file' that is not a .bte or .json file",
+                i
+            )));
+        }
+
+        let id = entry["id"].as_str().unwrap_or_default();
+        if !seen_ids.insert(id.to_string()) {
+            return Err(invalid(format!("entry {} has duplicate id '{}'", i, id)));
+        }
+    }
+
+    Ok(())
+}
+
+/// The community repository the theme index is built from
+pub const AWESOME_REPO_URL: &str = "https://github.com/Berikai/awesome-bitwig-themes";
+
+/// README file names tried for a repository, in order of preference
+const README_NAMES: [&str; 4] = ["README.md", "readme.md", "Readme.md", "README"];
+
+/// Branches tried when looking up a repository README
+const README_BRANCHES: [&str; 2] = ["main", "master"];
+
+/// Build the raw URLs that may hold a repository's README
+/// Only GitHub repositories are supported; other URLs yield no candidates
+pub fn readme_candidates(repo_url: &str) -> Vec<String> {
+    let trimmed = repo_url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let path = trimmed
+        .strip_prefix("https://github.com/")
+        .or_else(|| trimmed.strip_prefix("http://github.com/"));
+
+    let Some(path) = path else {
+        return Vec::new();
+    };
+
+    let mut parts = path.split('/').filter(|p| !p.is_empty());
+    let (Some(owner), Some(repo)) = (parts.next(), parts.next()) else {
+        return Vec::new();
+    };
+
+    README_BRANCHES
+        .iter()
+        .flat_map(|branch| {
+            README_NAMES.iter().map(move |name| {
+                format!(
+                    "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                    owner, repo, branch, name
+                )
+            })
+        })
+        .collect()
+}
+
+/// Image extensions treated as screenshots when scanning a README
+const PREVIEW_IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Base URL that relative README paths resolve against
+/// GitHub serves `raw/HEAD` from the repository's default branch
+pub fn readme_asset_base(repo_url: &str) -> Option<String> {
+    let trimmed = repo_url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let path = trimmed
+        .strip_prefix("https://github.com/")
+        .or_else(|| trimmed.strip_prefix("http://github.com/"))?;
+
+    let mut parts = path.split('/').filter(|p| !p.is_empty());
+    let (owner, repo) = (parts.next()?, parts.next()?);
+    Some(format!("https://github.com/{}/{}/raw/HEAD/", owner, repo))
+}
+
+/// Collect screenshot URLs from README markdown, in order of appearance
+/// Handles markdown images and HTML `<img>` tags; badges and other non-screenshot
+/// images (such as SVGs) are skipped. Relative paths are joined onto `base_url`
+pub fn extract_preview_urls(readme: &str, base_url: Option<&str>) -> Vec<String> {
+    let markdown = Regex::new(r#"!\[[^\]]*\]\(\s*<?([^)\s>]+)"#).unwrap();
+    let html = Regex::new(r#"(?i)<img[^>]*\ssrc\s*=\s*["']([^"']+)["']"#).unwrap();
+
+    let mut found: Vec<(usize, &str)> = markdown
+        .captures_iter(readme)
+        .chain(html.captures_iter(readme))
+        .filter_map(|caps| caps.get(1))
+        .map(|m| (m.start(), m.as_str()))
+        .collect();
+    found.sort_by_key(|(start, _)| *start);
+
+    let mut seen = HashSet::new();
+    found
+        .into_iter()
+        .filter(|(_, url)| {
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            path.rsplit_once('.').is_some_and(|(_, ext)| {
+                PREVIEW_IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+            })
+        })
+        .filter_map(|(_, url)| {
+            if url.starts_with("https://") || url.starts_with("http://") {
+                Some(url.to_string())
+            } else {
+                base_url.map(|base| format!("{}{}", base, url.trim_start_matches("./")))
+            }
+        })
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// Fill a theme's `preview_urls` from the screenshots in its README
+/// The primary `preview_url` stays first, and is taken from the README when missing
+pub fn apply_readme_previews(theme: &mut RepositoryTheme, readme: &str) {
+    let base = readme_asset_base(&theme.repo_url);
+    let mut urls: Vec<String> = theme.preview_url.iter().cloned().collect();
+    for url in extract_preview_urls(readme, base.as_deref()) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    if theme.preview_url.is_none() {
+        theme.preview_url = urls.first().cloned();
+    }
+    theme.preview_urls = urls;
+}
+
+/// GitHub request budget as last reported by a response
+/// The API sends `X-RateLimit-*` headers; raw.githubusercontent.com only answers 429 with
+/// `Retry-After`, which is recorded as an exhausted budget with an unknown (0) limit
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct RateLimitStatus {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix timestamp (seconds) at which the budget resets
+    pub reset: u64,
+}
+
+/// Rate limit state shared by every GitHub request
+static GITHUB_RATE_LIMIT: Mutex<Option<RateLimitStatus>> = Mutex::new(None);
+
+/// Remaining budget at or below which requests wait for the window to reset
+const RATE_LIMIT_LOW_WATER: u64 = 5;
+
+/// Longest wait for a reset before failing instead of blocking the request
+const RATE_LIMIT_MAX_WAIT_SECS: u64 = 60;
+
+/// Back-off after a 429 that doesn't say how long to wait
+const RATE_LIMIT_DEFAULT_RETRY_SECS: u64 = 60;
+
+fn parse_rate_limit(headers: &reqwest::header::HeaderMap) -> Option<RateLimitStatus> {
+    let header = |name: &str| -> Option<u64> {
+        headers.get(name)?.to_str().ok()?.trim().parse().ok()
+    };
+
+    Some(RateLimitStatus {
+        limit: header("x-ratelimit-limit")?,
+        remaining: header("x-ratelimit-remaining")?,
+        reset: header("x-ratelimit-reset")?,
+    })
+}
+
+/// Rate limit a response reports, from `X-RateLimit-*` headers or a 429 status
+/// A 429 without those headers (as raw.githubusercontent.com sends) exhausts the budget
+/// until `Retry-After` has passed
+fn response_rate_limit(
+    status: u16,
+    headers: &reqwest::header::HeaderMap,
+    now: u64,
+) -> Option<RateLimitStatus> {
+    if let Some(limit) = parse_rate_limit(headers) {
+        return Some(limit);
+    }
+    if status != 429 {
+        return None;
+    }
+
+    let retry_after = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(RATE_LIMIT_DEFAULT_RETRY_SECS);
+    Some(RateLimitStatus {
+        limit: 0,
+        remaining: 0,
+        reset: now + retry_after,
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Remember the rate limit reported by a GitHub response, if it reports one
+/// Returns what was recorded
+pub fn record_rate_limit(response: &reqwest::Response) -> Option<RateLimitStatus> {
+    let status = response_rate_limit(response.status().as_u16(), response.headers(), unix_now())?;
+    if let Ok(mut state) = GITHUB_RATE_LIMIT.lock() {
+        *state = Some(status);
+    }
+    Some(status)
+}
+
+/// Get the last known GitHub rate limit, if any request has reported one
+pub fn github_rate_limit_status() -> Option<RateLimitStatus> {
+    GITHUB_RATE_LIMIT.lock().ok().and_then(|state| *state)
+}
+
+/// Seconds to wait before the next request, or `None` if the budget allows it now
+fn rate_limit_wait(status: Option<RateLimitStatus>, now: u64) -> Option<u64> {
+    let status = status?;
+    if status.remaining > RATE_LIMIT_LOW_WATER || status.reset <= now {
+        return None;
+    }
+    Some(status.reset - now)
+}
+
+/// Wait until the shared budget allows another GitHub request
+/// Fails with `RateLimited` rather than blocking for longer than a minute
+pub async fn throttle_github_request() -> Result<(), FetchError> {
+    let Some(wait) = rate_limit_wait(github_rate_limit_status(), unix_now()) else {
+        return Ok(());
+    };
+    if wait > RATE_LIMIT_MAX_WAIT_SECS {
+        return Err(FetchError::RateLimited(wait));
+    }
+
+    tokio::time::sleep(Duration::from_secs(wait)).await;
+    Ok(())
+}
+
+/// Proxy config in effect, set from the settings by `set_proxy_setting`
+static PROXY_CONFIG: RwLock<Option<ProxyConfig>> = RwLock::new(None);
+
+/// Client shared by all requests, built on first use and dropped when the proxy changes
+static SHARED_CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+
+/// Apply the `proxy_url` setting to this process
+/// The shared client is rebuilt with the new proxies on the next request
+pub fn set_proxy_setting(proxy_url: Option<&str>) {
+    let config = ProxyConfig::resolve(proxy_url, |name| std::env::var(name).ok());
+    *PROXY_CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+    *SHARED_CLIENT.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Proxies outgoing requests go through
+/// The `proxy_url` setting covers both schemes; otherwise `HTTP_PROXY`/`HTTPS_PROXY` are used
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyConfig {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Hosts that bypass the proxy, in `NO_PROXY` syntax
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Build the config from a `proxy_url` setting and an environment lookup
+    /// Upper-case variables win over lower-case ones, matching curl
+    pub fn resolve(setting: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| {
+            env(&name.to_uppercase())
+                .or_else(|| env(name))
+                .filter(|v| !v.trim().is_empty())
+        };
+        let setting = setting.map(str::trim).filter(|s| !s.is_empty());
+        Self {
+            http: setting.map(str::to_string).or_else(|| var("http_proxy")),
+            https: setting.map(str::to_string).or_else(|| var("https_proxy")),
+            no_proxy: var("no_proxy"),
+        }
+    }
+
+    /// The config in effect for this process
+    /// Read from the settings file only until `set_proxy_setting` has run
+    pub fn current() -> Self {
+        if let Some(config) = PROXY_CONFIG.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            return config.clone();
+        }
+        let setting = settings::load_settings().ok().and_then(|s| s.proxy_url);
+        let config = Self::resolve(setting.as_deref(), |name| std::env::var(name).ok());
+        *PROXY_CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config.clone());
+        config
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none()
+    }
+}
+
+/// Check that a proxy URL is an absolute http(s) URL with a host
+pub fn validate_proxy_url(url: &str) -> Result<reqwest::Url, FetchError> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| FetchError::InvalidProxy(format!("{} ({})", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(FetchError::InvalidProxy(format!(
+            "{} (only http:// and https:// proxies are supported)",
+            url
+        )));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(FetchError::InvalidProxy(format!("{} (missing host)", url)));
+    }
+    Ok(parsed)
+}
+
+/// Build an HTTP client that routes requests through the configured proxies
+pub fn build_http_client(config: &ProxyConfig) -> Result<reqwest::Client, FetchError> {
+    let mut builder = reqwest::Client::builder();
+    let no_proxy = || {
+        config
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string)
+    };
+    let proxy_error = |e: reqwest::Error| FetchError::InvalidProxy(e.to_string());
+
+    if let Some(url) = &config.http {
+        let proxy = reqwest::Proxy::http(validate_proxy_url(url)?).map_err(proxy_error)?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy()));
+    }
+    if let Some(url) = &config.https {
+        let proxy = reqwest::Proxy::https(validate_proxy_url(url)?).map_err(proxy_error)?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy()));
+    }
+
+    builder
+        .build()
+        .map_err(|e| FetchError::Network(e.to_string()))
+}
+
+/// Refuse network access while safe mode is on
+pub fn ensure_network_allowed() -> Result<(), FetchError> {
+    network_allowed(settings::is_safe_mode())
+}
+
+fn network_allowed(safe_mode: bool) -> Result<(), FetchError> {
+    if safe_mode {
+        return Err(FetchError::SafeModeBlocked(
+            "network access is disabled, only cached data is available".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// HTTP client for all outgoing requests, honoring the proxy setting and environment
+/// Fails in safe mode, so every request is short-circuited before it is sent
+/// The client is shared, so requests reuse its connection pool
+pub fn http_client() -> Result<reqwest::Client, FetchError> {
+    ensure_network_allowed()?;
+    if let Some(client) = SHARED_CLIENT.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Ok(client.clone());
+    }
+
+    let mut shared = SHARED_CLIENT.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = shared.as_ref() {
+        return Ok(client.clone());
+    }
+    let client = build_http_client(&ProxyConfig::current())?;
+    *shared = Some(client.clone());
+    Ok(client)
+}
+
+/// Describe a failed request, telling proxy connection failures apart from other errors
+pub fn network_error(e: reqwest::Error) -> FetchError {
+    if e.is_connect() && !ProxyConfig::current().is_empty() {
+        return FetchError::Proxy(e.to_string());
+    }
+    FetchError::Network(e.to_string())
+}
+
+/// Raw-content hosts themes are downloaded from, probed by `check_connectivity`
+pub const GITHUB_RAW_HOST: &str = "https://raw.githubusercontent.com/";
+pub const CODEBERG_RAW_HOST: &str = "https://codeberg.org/";
+
+/// Hosts theme downloads may be sized or fetched from, always over https
+const DOWNLOAD_HOSTS: &[&str] = &[
+    "raw.githubusercontent.com",
+    "github.com",
+    "objects.githubusercontent.com",
+    "codeberg.org",
+];
+
+/// Whether a download URL is https and points at one of the known theme hosts
+pub fn is_allowed_download_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|parsed| {
+        parsed.scheme() == "https"
+            && parsed.host_str().is_some_and(|host| DOWNLOAD_HOSTS.contains(&host))
+    })
+}
+
+/// How long a connectivity probe waits before calling a host unreachable
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reachability of the theme hosts, for telling "offline" from "GitHub blocked"
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Connectivity {
+    pub github_reachable: bool,
+    pub codeberg_reachable: bool,
+    /// Round trip to each host, if it answered
+    pub github_latency_ms: Option<u64>,
+    pub codeberg_latency_ms: Option<u64>,
+    /// GitHub answered but refused the request for exceeding its rate limit
+    pub github_rate_limited: bool,
+}
+
+/// Outcome of a single probe: status code and round trip, or `None` if no reply came
+/// Probes hold a slot under the global request cap like any other request; only GitHub
+/// replies update the shared GitHub rate limit
+async fn probe_host(client: &reqwest::Client, url: &str, github: bool) -> Option<(u16, u64)> {
+    let _permit = acquire_network_permit().await.ok()?;
+    let started = std::time::Instant::now();
+    let response = client
+        .head(url)
+        .timeout(CONNECTIVITY_TIMEOUT)
+        .send()
+        .await
+        .ok()?;
+
+    let latency = started.elapsed().as_millis() as u64;
+    let status = response.status().as_u16();
+    let limit = if github {
+        record_rate_limit(&response)
+    } else {
+        response_rate_limit(status, response.headers(), unix_now())
+    };
+    let exhausted = limit.is_some_and(|s| s.remaining == 0);
+    Some((if exhausted { 429 } else { status }, latency))
+}
+
+/// Probe the given GitHub and Codeberg URLs concurrently
+/// Any HTTP reply counts as reachable; only connection failures and timeouts do not
+pub async fn check_connectivity_at(
+    client: &reqwest::Client,
+    github_url: &str,
+    codeberg_url: &str,
+) -> Connectivity {
+    let (github, codeberg) = tokio::join!(
+        probe_host(client, github_url, true),
+        probe_host(client, codeberg_url, false)
+    );
+
+    Connectivity {
+        github_reachable: github.is_some(),
+        codeberg_reachable: codeberg.is_some(),
+        github_latency_ms: github.map(|(_, latency)| latency),
+        codeberg_latency_ms: codeberg.map(|(_, latency)| latency),
+        github_rate_limited: github.is_some_and(|(status, _)| status == 429),
+    }
+}
+
+/// Check whether the theme hosts can be reached through the shared client
+pub async fn check_connectivity() -> Result<Connectivity, FetchError> {
+    let client = http_client()?;
+    Ok(check_connectivity_at(&client, GITHUB_RAW_HOST, CODEBERG_RAW_HOST).await)
+}
+
+/// Fetch the raw README markdown for a repository
+/// Returns `None` if none of the candidate locations exist
+pub async fn fetch_readme(repo_url: &str) -> Result<Option<String>, FetchError> {
+    let client = http_client()?;
+
+    for url in readme_candidates(repo_url) {
+        throttle_github_request().await?;
+        let _permit = acquire_network_permit().await?;
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .map_err(network_error)?;
+        record_rate_limit(&response);
+
+        if !response.status().is_success() {
+            continue;
+        }
+
+        let text = response.text().await.map_err(network_error)?;
+        return Ok(Some(text));
+    }
+
+    Ok(None)
+}
+
+/// Shared cap on network requests, sized from the `max_concurrent_requests` setting
+struct NetworkLimit {
+    semaphore: Arc<Semaphore>,
+    max: usize,
+}
+
+static NETWORK_LIMIT: OnceLock<NetworkLimit> = OnceLock::new();
+
+fn network_limit() -> &'static NetworkLimit {
+    NETWORK_LIMIT.get_or_init(|| {
+        let max = settings::load_settings()
+            .map(|s| s.max_concurrent_requests)
+            .unwrap_or_else(|_| settings::Settings::default().max_concurrent_requests)
+            .max(1);
+        NetworkLimit {
+            semaphore: Arc::new(Semaphore::new(max)),
+            max,
+        }
+    })
+}
+
+/// Wait for a slot under the global request cap
+/// Every outgoing request holds a permit until its response has been read
+pub async fn acquire_network_permit() -> Result<OwnedSemaphorePermit, FetchError> {
+    Arc::clone(&network_limit().semaphore)
+        .acquire_owned()
+        .await
+        .map_err(|e| FetchError::Network(e.to_string()))
+}
+
+/// Number of network requests currently holding a permit
+pub fn network_requests_in_flight() -> usize {
+    let limit = network_limit();
+    limit.max - limit.semaphore.available_permits()
+}
+
+/// First line of a Git LFS pointer file
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/v1";
+
+/// Check whether a downloaded body is a Git LFS pointer rather than the file itself
+pub fn is_lfs_pointer(body: &[u8]) -> bool {
+    // Pointers are a few short lines; anything large is real content
+    body.len() < 1024 && body.starts_with(LFS_POINTER_PREFIX)
+}
+
+/// Map a GitHub raw file URL to the media endpoint that serves LFS content
+/// Handles `raw.githubusercontent.com/<owner>/<repo>/<ref>/<path>` and
+/// `github.com/<owner>/<repo>/{raw,blob}/<ref>/<path>` URLs
+pub fn lfs_media_url(url: &str) -> Option<String> {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+
+    let path = if let Some(rest) = url.strip_prefix("https://raw.githubusercontent.com/") {
+        rest.to_string()
+    } else {
+        let rest = url.strip_prefix("https://github.com/")?;
+        let parts: Vec<&str> = rest.splitn(4, '/').collect();
+        let [owner, repo, kind, file] = parts[..] else {
+            return None;
+        };
+        if kind != "raw" && kind != "blob" {
+            return None;
+        }
+        format!("{}/{}/{}", owner, repo, file)
+    };
+
+    // Need at least owner/repo/ref/file
+    if path.split('/').filter(|p| !p.is_empty()).count() < 4 {
+        return None;
+    }
+    Some(format!("https://media.githubusercontent.com/media/{}", path))
+}
+
+async fn get_bytes(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, FetchError> {
+    let _permit = acquire_network_permit().await?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(network_error)?;
+    let bytes = response.bytes().await.map_err(network_error)?;
+    Ok(bytes.to_vec())
+}
+
+/// Download a file, following Git LFS pointers to the real content
+pub async fn download_bytes(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, FetchError> {
+    let body = get_bytes(client, url).await?;
+    if !is_lfs_pointer(&body) {
+        return Ok(body);
+    }
+
+    let media_url = lfs_media_url(url).ok_or_else(|| FetchError::LfsPointer(url.to_string()))?;
+    let media = get_bytes(client, &media_url)
+        .await
+        .map_err(|_| FetchError::LfsPointer(url.to_string()))?;
+    if is_lfs_pointer(&media) {
+        return Err(FetchError::LfsPointer(url.to_string()));
+    }
+    Ok(media)
+}
+
+/// File format a repository theme is published in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceFormat {
+    Bte,
+    Json,
+    Zip,
+    /// Content that didn't parse as any theme format
+    Unknown,
+}
+
+/// Guess a theme's format from its download URL's extension
+pub fn format_from_url(url: &str) -> Option<SourceFormat> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let (_, ext) = path.rsplit_once('.')?;
+    match ext.to_lowercase().as_str() {
+        "bte" => Some(SourceFormat::Bte),
+        "json" => Some(SourceFormat::Json),
+        "zip" => Some(SourceFormat::Zip),
+        _ => None,
+    }
+}
+
+/// Classify theme content that is already on disk
+pub fn format_from_content(content: &str) -> SourceFormat {
+    if is_json_content(content) {
+        return SourceFormat::Json;
+    }
+    match parse_theme_content(content, None) {
+        Ok(theme) if !theme.colors.is_empty() => SourceFormat::Bte,
+        _ => SourceFormat::Unknown,
+    }
+}
+
+/// How many repository themes are published in each format
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct FormatStats {
+    pub bte: usize,
+    pub json: usize,
+    pub zip: usize,
+    pub unknown: usize,
+    /// Themes whose format couldn't be told without downloading them
+    pub skipped: usize,
+}
+
+impl FormatStats {
+    /// Tally formats, counting `None` as skipped
+    pub fn from_formats(formats: impl IntoIterator<Item = Option<SourceFormat>>) -> Self {
+        let mut stats = Self::default();
+        for format in formats {
+            match format {
+                Some(SourceFormat::Bte) => stats.bte += 1,
+                Some(SourceFormat::Json) => stats.json += 1,
+                Some(SourceFormat::Zip) => stats.zip += 1,
+                Some(SourceFormat::Unknown) => stats.unknown += 1,
+                None => stats.skipped += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// Themes published by one author
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AuthorSummary {
+    /// The first spelling seen, without a leading `@`
+    pub author: String,
+    pub author_url: Option<String>,
+    pub theme_count: usize,
+}
+
+/// Key authors are grouped by, so `@notoyz` and `Notoyz` count as one
+fn author_key(author: &str) -> String {
+    author.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// Group themes by author, most prolific first (ties by name)
+pub fn summarize_authors(themes: &[RepositoryTheme]) -> Vec<AuthorSummary> {
+    let mut summaries: Vec<AuthorSummary> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for theme in themes {
+        let key = author_key(&theme.author);
+        if key.is_empty() {
+            continue;
+        }
+        match index.get(&key) {
+            Some(&i) => {
+                let summary = &mut summaries[i];
+                summary.theme_count += 1;
+                if summary.author_url.is_none() {
+                    summary.author_url = theme.author_url.clone();
+                }
+            }
+            None => {
+                index.insert(key, summaries.len());
+                summaries.push(AuthorSummary {
+                    author: theme.author.trim().trim_start_matches('@').to_string(),
+                    author_url: theme.author_url.clone(),
+                    theme_count: 1,
+                });
+            }
+        }
+    }
+
+    summaries.sort_by(|a, b| {
+        b.theme_count
+            .cmp(&a.theme_count)
+            .then_with(|| author_key(&a.author).cmp(&author_key(&b.author)))
+    });
+    summaries
+}
+
+/// Estimated download size of a single theme
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ThemeDownloadSize {
+    pub name: String,
+    /// `None` when the size couldn't be determined
+    pub bytes: Option<u64>,
+}
+
+/// Estimated download size of a set of themes
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DownloadSizeEstimate {
+    /// Sum of the sizes that could be determined
+    pub total_bytes: u64,
+    pub themes: Vec<ThemeDownloadSize>,
+    /// Themes whose size couldn't be determined
+    pub unknown: Vec<String>,
+}
+
+impl DownloadSizeEstimate {
+    /// Build an estimate from per-theme sizes
+    pub fn from_sizes(sizes: Vec<(String, Option<u64>)>) -> Self {
+        let mut estimate = Self::default();
+        for (name, bytes) in sizes {
+            match bytes {
+                Some(bytes) => estimate.total_bytes += bytes,
+                None => estimate.unknown.push(name.clone()),
+            }
+            estimate.themes.push(ThemeDownloadSize { name, bytes });
+        }
+        estimate
+    }
+}
+
+/// Ask the server how large a download is without fetching it
+/// Tries a HEAD request first, then a single-byte range request
+pub async fn fetch_content_length(client: &reqwest::Client, url: &str) -> Option<u64> {
+    let _permit = acquire_network_permit().await.ok()?;
+    if let Ok(response) = client.head(url).send().await {
+        let length = response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        if response.status().is_success() && length.is_some() {
+            return length;
+        }
+    }
+
+    // Some hosts don't answer HEAD; "Content-Range: bytes 0-0/<total>" gives the size
+    let response = client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+        .ok()?;
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Look up the download size of each `(theme_name, url)` pair, in input order
+/// Lookups run concurrently under the global request cap; URLs outside the host
+/// allowlist are never requested and come back unknown
+pub async fn fetch_download_sizes(entries: Vec<(String, String)>) -> Vec<(String, Option<u64>)> {
+    let client = match http_client() {
+        Ok(client) => client,
+        Err(e) => {
+            crate::log_event(&format!("Could not size downloads: {}", e));
+            return entries.into_iter().map(|(name, _)| (name, None)).collect();
+        }
+    };
+    let mut lookups = JoinSet::new();
+    for (index, (name, url)) in entries.iter().enumerate() {
+        if !is_allowed_download_url(url) {
+            crate::log_event(&format!("Not sizing '{}': {} is not a theme host", name, url));
+            continue;
+        }
+        let client = client.clone();
+        let url = url.clone();
+        lookups.spawn(async move { (index, fetch_content_length(&client, &url).await) });
+    }
+
+    let mut sizes: Vec<Option<u64>> = vec![None; entries.len()];
+    while let Some(joined) = lookups.join_next().await {
+        if let Ok((index, bytes)) = joined {
+            sizes[index] = bytes;
+        }
+    }
+
+    entries
+        .into_iter()
+        .zip(sizes)
+        .map(|((name, _), bytes)| (name, bytes))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve a single canned HTTP response on a local port
+    fn serve_once(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        format!("http://{}/theme.bte", addr)
+    }
+
+    fn entry(id: &str, file: &str) -> Value {
+        json!({ "id": id, "name": "Theme", "author": "someone", "file": file })
+    }
+
+    #[test]
+    fn test_format_stats() {
+        assert_eq!(format_from_url("https://x.org/dark.BTE"), Some(SourceFormat::Bte));
+        assert_eq!(format_from_url("https://x.org/pack.zip?dl=1"), Some(SourceFormat::Zip));
+        assert_eq!(format_from_url("https://x.org/theme"), None);
+
+        assert_eq!(format_from_content(r##"{"window": {"Accent": "#fff"}}"##), SourceFormat::Json);
+        assert_eq!(format_from_content("Accent: #e94560\n"), SourceFormat::Bte);
+        assert_eq!(format_from_content("<html>Not found</html>"), SourceFormat::Unknown);
+
+        let stats = FormatStats::from_formats([
+            Some(SourceFormat::Json),
+            Some(SourceFormat::Json),
+            Some(SourceFormat::Bte),
+            None,
+        ]);
+        assert_eq!(
+            stats,
+            FormatStats { bte: 1, json: 2, zip: 0, unknown: 0, skipped: 1 }
+        );
+    }
+
+    #[test]
+    fn test_extract_preview_urls() {
+        let readme = r#"# Dark Theme
+[![Stars](https://img.shields.io/github/stars/a/b.svg)](https://github.com/a/b)
+
+![Arranger](screenshots/arranger.png)
+<img src="https://example.com/mixer.jpg?raw=true" width="600">
+![Device](./screenshots/device.webp "Device view")
+![Arranger again](screenshots/arranger.png)
+"#;
+        let base = readme_asset_base("https://github.com/a/b");
+        assert_eq!(base.as_deref(), Some("https://github.com/a/b/raw/HEAD/"));
+
+        let urls = extract_preview_urls(readme, base.as_deref());
+        assert_eq!(
+            urls,
+            vec![
+                "https://github.com/a/b/raw/HEAD/screenshots/arranger.png".to_string(),
+                "https://example.com/mixer.jpg?raw=true".to_string(),
+                "https://github.com/a/b/raw/HEAD/screenshots/device.webp".to_string(),
+            ]
+        );
+
+        // Relative images are dropped when there is nothing to resolve them against
+        assert_eq!(extract_preview_urls(readme, None).len(), 1);
+    }
+
+    #[test]
+    fn test_apply_readme_previews() {
+        let readme = "![Main](docs/main.png)\n![Mixer](docs/mixer.png)\n";
+        let mut theme = RepositoryTheme {
+            name: "Polar".to_string(),
+            repo_url: "https://github.com/alice/polar".to_string(),
+            ..Default::default()
+        };
+
+        apply_readme_previews(&mut theme, readme);
+        let main = "https://github.com/alice/polar/raw/HEAD/docs/main.png";
+        assert_eq!(theme.preview_url.as_deref(), Some(main));
+        assert_eq!(theme.preview_urls.len(), 2);
+        assert_eq!(theme.preview_urls[0], main);
+
+        // A known primary preview stays first
+        theme.preview_url = Some("https://example.com/cover.png".to_string());
+        apply_readme_previews(&mut theme, readme);
+        assert_eq!(theme.preview_urls.len(), 3);
+        assert_eq!(theme.preview_urls[0], "https://example.com/cover.png");
+    }
+
+    #[test]
+    fn test_valid_index() {
+        let index = json!({
+            "version": 1,
+            "themes": [entry("a", "a.json"), entry("b", "b.bte")]
+        });
+        assert!(validate_theme_index(&index, "bundled").is_ok());
+    }
+
+    #[test]
+    fn test_missing_field_reports_entry() {
+        let index = json!({
+            "version": 1,
+            "themes": [
+                entry("a", "a.json"),
+                entry("b", "b.json"),
+                entry("c", "c.json"),
+                { "id": "d", "name": "D", "author": "x" }
+            ]
+        });
+        let err = validate_theme_index(&index, "community").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid theme index: community index entry 3 is missing 'file'"
+        );
+    }
+
+    #[test]
+    fn test_bad_extension_and_duplicates() {
+        let index = json!({ "version": 1, "themes": [entry("a", "a.zip")] });
+        assert!(validate_theme_index(&index, "bundled")
+            .unwrap_err()
+            .to_string()
+            .contains("entry 0 has 'file'"));
+
+        let index = json!({ "version": 1, "themes": [entry("a", "a.json"), entry("a", "b.json")] });
+        assert!(validate_theme_index(&index, "bundled")
+            .unwrap_err()
+            .to_string()
+            .contains("entry 1 has duplicate id 'a'"));
+    }
+
+    #[test]
+    fn test_missing_structure() {
+        assert!(validate_theme_index(&json!([]), "bundled").is_err());
+        assert!(validate_theme_index(&json!({ "themes": [] }), "bundled").is_err());
+        assert!(validate_theme_index(&json!({ "version": 1 }), "bundled").is_err());
+
+        let too_big = json!({ "version": u64::from(u32::MAX) + 1, "themes": [] });
+        assert!(validate_theme_index(&too_big, "bundled").is_err());
+        let largest = json!({ "version": u32::MAX, "themes": [] });
+        assert!(validate_theme_index(&largest, "bundled").is_ok());
+    }
+
+    #[test]
+    fn test_readme_candidates() {
+        let candidates = readme_candidates("https://github.com/someone/my-theme.git/");
+        assert_eq!(candidates.len(), 8);
+        assert_eq!(
+            candidates[0],
+            "https://raw.githubusercontent.com/someone/my-theme/main/README.md"
+        );
+        assert!(candidates
+            .contains(&"https://raw.githubusercontent.com/someone/my-theme/master/README".to_string()));
+
+        assert!(readme_candidates("bundled://blackwig").is_empty());
+        assert!(readme_candidates("https://github.com/someone").is_empty());
+    }
+
+    #[test]
+    fn test_fetch_content_length_from_head() {
+        let url = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Length: 4096\r\nConnection: close\r\n\r\n",
+        );
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let client = reqwest::Client::new();
+
+        assert_eq!(runtime.block_on(fetch_content_length(&client, &url)), Some(4096));
+    }
+
+    #[test]
+    fn test_is_allowed_download_url() {
+        assert!(is_allowed_download_url(
+            "https://raw.githubusercontent.com/someone/themes/main/dark.bte"
+        ));
+        assert!(is_allowed_download_url("https://codeberg.org/someone/themes/raw/dark.bte"));
+        assert!(!is_allowed_download_url("http://raw.githubusercontent.com/someone/dark.bte"));
+        assert!(!is_allowed_download_url("https://example.com/dark.bte"));
+        assert!(!is_allowed_download_url("https://github.com.example.com/dark.bte"));
+        assert!(!is_allowed_download_url("bundled://dark.bte"));
+    }
+
+    #[test]
+    fn test_download_sizes_skip_unlisted_hosts() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let entries = vec![("local".to_string(), "http://127.0.0.1:9/theme.bte".to_string())];
+
+        let sizes = runtime.block_on(fetch_download_sizes(entries));
+        assert_eq!(sizes, vec![("local".to_string(), None)]);
+    }
+
+    #[test]
+    fn test_download_size_estimate() {
+        let estimate = DownloadSizeEstimate::from_sizes(vec![
+            ("a".to_string(), Some(1000)),
+            ("b".to_string(), None),
+            ("c".to_string(), Some(500)),
+        ]);
+
+        assert_eq!(estimate.total_bytes, 1500);
+        assert_eq!(estimate.themes.len(), 3);
+        assert_eq!(estimate.unknown, vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn test_rate_limit_headers_and_wait() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-RateLimit-Limit", "60".parse().unwrap());
+        headers.insert("X-RateLimit-Remaining", "3".parse().unwrap());
+        headers.insert("X-RateLimit-Reset", "1000".parse().unwrap());
+
+        let status = parse_rate_limit(&headers).unwrap();
+        assert_eq!(
+            status,
+            RateLimitStatus {
+                limit: 60,
+                remaining: 3,
+                reset: 1000
+            }
+        );
+
+        assert_eq!(rate_limit_wait(Some(status), 990), Some(10));
+        assert_eq!(rate_limit_wait(Some(status), 1000), None);
+        assert_eq!(
+            rate_limit_wait(Some(RateLimitStatus { remaining: 30, ..status }), 990),
+            None
+        );
+        assert_eq!(rate_limit_wait(None, 990), None);
+
+        headers.remove("X-RateLimit-Reset");
+        assert!(parse_rate_limit(&headers).is_none());
+
+        // raw.githubusercontent.com only sends a 429, optionally with Retry-After
+        let empty = reqwest::header::HeaderMap::new();
+        assert_eq!(response_rate_limit(200, &empty, 990), None);
+        let limited = response_rate_limit(429, &empty, 990).unwrap();
+        assert_eq!(limited.remaining, 0);
+        assert_eq!(limited.reset, 990 + RATE_LIMIT_DEFAULT_RETRY_SECS);
+        let mut retry = reqwest::header::HeaderMap::new();
+        retry.insert("Retry-After", "30".parse().unwrap());
+        assert_eq!(response_rate_limit(429, &retry, 990).unwrap().reset, 1020);
+        assert_eq!(rate_limit_wait(Some(limited), 990), Some(RATE_LIMIT_DEFAULT_RETRY_SECS));
+    }
+
+    #[test]
+    fn test_lfs_pointer_detection() {
+        let pointer = b"version https://git-lfs.github.com/spec/v1\n\
+oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+size 12345\n";
+        assert!(is_lfs_pointer(pointer));
+        assert!(!is_lfs_pointer(b"\x89PNG\r\n\x1a\n"));
+        assert!(!is_lfs_pointer(b"Background: #111111\n"));
+
+        assert_eq!(
+            lfs_media_url("https://raw.githubusercontent.com/a/b/main/previews/x.png").as_deref(),
+            Some("https://media.githubusercontent.com/media/a/b/main/previews/x.png")
+        );
+        assert_eq!(
+            lfs_media_url("https://github.com/a/b/blob/main/x.png?raw=true").as_deref(),
+            Some("https://media.githubusercontent.com/media/a/b/main/x.png")
+        );
+        assert_eq!(lfs_media_url("https://example.com/a/b/main/x.png"), None);
+    }
+
+    #[test]
+    fn test_summarize_authors() {
+        let theme = |name: &str, author: &str, author_url: Option<&str>| RepositoryTheme {
+            name: name.to_string(),
+            author: author.to_string(),
+            author_url: author_url.map(str::to_string),
+            repo_url: format!("https://github.com/x/{}", name),
+            preview_url: None,
+            preview_urls: Vec::new(),
+            description: None,
+            download_url: None,
+            tags: Vec::new(),
+        };
+        let themes = vec![
+            theme("Dark", "@notoyz", None),
+            theme("Ghosty", "Alice", Some("https://github.com/alice")),
+            theme("Light", "notoyz ", Some("https://github.com/notoyz")),
+            theme("Noir", "NOTOYZ", None),
+            theme("Blank", "  ", None),
+        ];
+
+        assert_eq!(
+            summarize_authors(&themes),
+            vec![
+                AuthorSummary {
+                    author: "notoyz".to_string(),
+                    author_url: Some("https://github.com/notoyz".to_string()),
+                    theme_count: 3,
+                },
+                AuthorSummary {
+                    author: "Alice".to_string(),
+                    author_url: Some("https://github.com/alice".to_string()),
+                    theme_count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_theme_tags() {
+        assert_eq!(
+            extract_theme_tags("Polar", Some("A dark nord-inspired theme")),
+            vec!["dark".to_string(), "nord".to_string()]
+        );
+        assert_eq!(
+            extract_theme_tags("Stark High-Contrast", None),
+            vec!["high-contrast".to_string()]
+        );
+        // Words that merely contain a keyword don't count
+        assert!(extract_theme_tags("Lightweight", Some("Darkness falls")).is_empty());
+    }
+
+    #[test]
+    fn test_proxy_config() {
+        let env = |name: &str| match name {
+            "HTTPS_PROXY" => Some("http://proxy.corp:3128".to_string()),
+            "http_proxy" => Some("http://lower.corp:8080".to_string()),
+            "NO_PROXY" => Some("localhost,.internal".to_string()),
+            _ => None,
+        };
+
+        let config = ProxyConfig::resolve(None, env);
+        assert_eq!(config.http.as_deref(), Some("http://lower.corp:8080"));
+        assert_eq!(config.https.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(config.no_proxy.as_deref(), Some("localhost,.internal"));
+        assert!(build_http_client(&config).is_ok());
+
+        // The setting overrides the environment for both schemes
+        let config = ProxyConfig::resolve(Some(" http://setting:8888 "), env);
+        assert_eq!(config.http.as_deref(), Some("http://setting:8888"));
+        assert_eq!(config.https.as_deref(), Some("http://setting:8888"));
+
+        assert!(ProxyConfig::resolve(Some(""), |_| None).is_empty());
+
+        assert!(validate_proxy_url("http://proxy.corp:3128").is_ok());
+        assert!(matches!(validate_proxy_url("proxy.corp:3128"), Err(FetchError::InvalidProxy(_))));
+        assert!(matches!(validate_proxy_url("ftp://proxy.corp"), Err(FetchError::InvalidProxy(_))));
+        let invalid = ProxyConfig {
+            https: Some("not a url".to_string()),
+            ..ProxyConfig::default()
+        };
+        assert!(matches!(build_http_client(&invalid), Err(FetchError::InvalidProxy(_))));
+    }
+
+    #[test]
+    fn test_check_connectivity() {
+        let github = serve_once(
+            "HTTP/1.1 403 Forbidden\r\nX-RateLimit-Limit: 60\r\nX-RateLimit-Remaining: 0\r\n\
+             X-RateLimit-Reset: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        // Bind and drop a listener so nothing answers on this port
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let client = reqwest::Client::new();
+
+        let result = runtime.block_on(check_connectivity_at(
+            &client,
+            &github,
+            &format!("http://{}/", closed),
+        ));
+
+        assert!(result.github_reachable);
+        assert!(!result.codeberg_reachable);
+        assert!(result.github_rate_limited);
+        assert!(result.github_latency_ms.is_some());
+        assert_eq!(result.codeberg_latency_ms, None);
+    }
+
+    #[test]
+    fn test_network_blocked_in_safe_mode() {
+        assert!(network_allowed(false).is_ok());
+        let err = network_allowed(true).unwrap_err();
+        assert!(matches!(err, FetchError::SafeModeBlocked(_)));
+        assert!(err.to_string().contains("network access is disabled"));
+    }
 }
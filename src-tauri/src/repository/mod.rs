@@ -1,7 +1,9 @@
 pub mod fetcher;
 pub mod cache;
 pub mod bundled;
+pub mod favorites;
 
 pub use fetcher::*;
 pub use cache::*;
 pub use bundled::*;
+pub use favorites::*;
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use thiserror::Error;
 
+use crate::theme::parser::write_atomic;
+
 #[derive(Error, Debug)]
 pub enum SettingsError {
     #[error("IO error: {0}")]
@@ -13,6 +18,9 @@ pub enum SettingsError {
 
     #[error("Could not determine config directory")]
     NoConfigDir,
+
+    #[error("Unknown setting: {0}")]
+    UnknownKey(String),
 }
 
 /// Application settings
@@ -34,6 +42,9 @@ pub struct Settings {
     /// Custom theme directory override (if not using default)
     pub custom_theme_directory: Option<String>,
 
+    /// Extra directories scanned for Bitwig installations (supports `~` and `$VAR`)
+    pub custom_search_paths: Vec<String>,
+
     /// Repository cache duration in hours
     pub cache_duration_hours: u32,
 
@@ -45,6 +56,30 @@ pub struct Settings {
 
     /// Version that user chose to skip (won't prompt for this version)
     pub skipped_version: Option<String>,
+
+    /// Copy the active theme into `snapshots/` before applying over it
+    pub snapshot_before_apply: bool,
+
+    /// Most network requests allowed in flight at once (read at startup)
+    pub max_concurrent_requests: usize,
+
+    /// Embed a `// Checksum:` comment when saving themes, for integrity checks
+    pub embed_theme_checksum: bool,
+
+    /// Record each patch/restore as a JSON line in `patch-history.jsonl`
+    pub patch_history_log: bool,
+
+    /// Restore the previous active theme when patching fails during apply
+    pub rollback_on_patch_failure: bool,
+
+    /// Proxy for all network requests, overriding `HTTP_PROXY`/`HTTPS_PROXY`
+    pub proxy_url: Option<String>,
+
+    /// Most keys a theme file may have before it is rejected as corrupt
+    pub max_theme_keys: usize,
+
+    /// Never elevate, touch the network, or download the patcher
+    pub safe_mode: bool,
 }
 
 impl Default for Settings {
@@ -55,14 +90,54 @@ impl Default for Settings {
             watch_theme_directory: true,
             selected_bitwig_version: None,
             custom_theme_directory: None,
+            custom_search_paths: Vec::new(),
             cache_duration_hours: 1,
             show_preview_images: true,
             last_view: "browse".to_string(),
             skipped_version: None,
+            snapshot_before_apply: false,
+            max_concurrent_requests: 8,
+            embed_theme_checksum: false,
+            patch_history_log: false,
+            rollback_on_patch_failure: false,
+            proxy_url: None,
+            max_theme_keys: crate::theme::DEFAULT_MAX_THEME_KEYS,
+            safe_mode: false,
         }
     }
 }
 
+/// Launch flag that turns safe mode on whatever the setting says
+pub const SAFE_MODE_FLAG: &str = "--safe-mode";
+
+static SAFE_MODE_SETTING: AtomicBool = AtomicBool::new(false);
+static SAFE_MODE_LAUNCH: AtomicBool = AtomicBool::new(false);
+
+/// Apply the `safe_mode` setting to this process
+pub fn set_safe_mode(enabled: bool) {
+    SAFE_MODE_SETTING.store(enabled, Ordering::Relaxed);
+}
+
+/// Record whether the process was launched with `--safe-mode`
+pub fn set_safe_mode_launch_flag(enabled: bool) {
+    SAFE_MODE_LAUNCH.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether elevation, network access and the patcher download are disabled
+pub fn is_safe_mode() -> bool {
+    SAFE_MODE_SETTING.load(Ordering::Relaxed) || SAFE_MODE_LAUNCH.load(Ordering::Relaxed)
+}
+
+/// Whether the command line asks for safe mode
+/// Arguments are compared as `OsStr`, so a non-UTF-8 argument elsewhere can't cause a panic
+pub fn safe_mode_flag_passed<I, S>(args: I) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    args.into_iter().any(|arg| arg.as_ref() == SAFE_MODE_FLAG)
+}
+
 /// Get the settings file path
 pub fn settings_path() -> Result<PathBuf, SettingsError> {
     let config_dir = dirs::config_dir().ok_or(SettingsError::NoConfigDir)?;
@@ -70,15 +145,25 @@ pub fn settings_path() -> Result<PathBuf, SettingsError> {
     Ok(app_config.join("settings.json"))
 }
 
+/// Serializes every read-modify-write of the settings file in this process
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_settings() -> MutexGuard<'static, ()> {
+    // A panic mid-update leaves the file intact thanks to the atomic write
+    SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 /// Load settings from disk
 pub fn load_settings() -> Result<Settings, SettingsError> {
-    let path = settings_path()?;
+    load_settings_from(&settings_path()?)
+}
 
+fn load_settings_from(path: &Path) -> Result<Settings, SettingsError> {
     if !path.exists() {
         return Ok(Settings::default());
     }
 
-    let content = fs::read_to_string(&path)?;
+    let content = fs::read_to_string(path)?;
     let settings: Settings = serde_json::from_str(&content)?;
     Ok(settings)
 }
@@ -86,25 +171,62 @@ pub fn load_settings() -> Result<Settings, SettingsError> {
 /// Save settings to disk
 pub fn save_settings(settings: &Settings) -> Result<(), SettingsError> {
     let path = settings_path()?;
+    let _guard = lock_settings();
+    save_settings_to(&path, settings)
+}
 
+/// Write settings through a temporary file, so readers never see a partial file
+fn save_settings_to(path: &Path, settings: &Settings) -> Result<(), SettingsError> {
     // Create parent directories if needed
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
     let content = serde_json::to_string_pretty(settings)?;
-    fs::write(&path, content)?;
+    write_atomic(path, &content)?;
     Ok(())
 }
 
 /// Update a single setting
+/// Load, change and save happen under one lock, so concurrent updates aren't lost
 pub fn update_setting<F>(updater: F) -> Result<Settings, SettingsError>
 where
     F: FnOnce(&mut Settings),
 {
-    let mut settings = load_settings()?;
+    update_settings_at(&settings_path()?, updater)
+}
+
+fn update_settings_at<F>(path: &Path, updater: F) -> Result<Settings, SettingsError>
+where
+    F: FnOnce(&mut Settings),
+{
+    let _guard = lock_settings();
+    let mut settings = load_settings_from(path)?;
     updater(&mut settings);
-    save_settings(&settings)?;
+    save_settings_to(path, &settings)?;
+    Ok(settings)
+}
+
+/// Set one setting by its JSON field name
+/// Fails for unknown keys and for values of the wrong type, leaving the file untouched
+pub fn set_setting(key: &str, value: serde_json::Value) -> Result<Settings, SettingsError> {
+    set_setting_at(&settings_path()?, key, value)
+}
+
+fn set_setting_at(
+    path: &Path,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<Settings, SettingsError> {
+    let _guard = lock_settings();
+    let mut fields = serde_json::to_value(load_settings_from(path)?)?;
+    let field = fields
+        .get_mut(key)
+        .ok_or_else(|| SettingsError::UnknownKey(key.to_string()))?;
+    *field = value;
+
+    let settings: Settings = serde_json::from_value(fields)?;
+    save_settings_to(path, &settings)?;
     Ok(settings)
 }
 
@@ -127,4 +249,64 @@ mod tests {
         let deserialized: Settings = serde_json::from_str(&json).unwrap();
         assert_eq!(settings.check_updates_on_startup, deserialized.check_updates_on_startup);
     }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    update_settings_at(&path, |s| s.custom_search_paths.push(format!("/p{}", i)))
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut paths = load_settings_from(&path).unwrap().custom_search_paths;
+        paths.sort();
+        let mut expected: Vec<String> = (0..16).map(|i| format!("/p{}", i)).collect();
+        expected.sort();
+        assert_eq!(paths, expected);
+        // Only the settings file is left behind, no temporary files
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_set_setting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        let updated = set_setting_at(&path, "max_theme_keys", serde_json::json!(200)).unwrap();
+        assert_eq!(updated.max_theme_keys, 200);
+        assert_eq!(load_settings_from(&path).unwrap().max_theme_keys, 200);
+
+        assert!(matches!(
+            set_setting_at(&path, "no_such_setting", serde_json::json!(true)),
+            Err(SettingsError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            set_setting_at(&path, "max_theme_keys", serde_json::json!("lots")),
+            Err(SettingsError::Json(_))
+        ));
+        assert_eq!(load_settings_from(&path).unwrap().max_theme_keys, 200);
+    }
+
+    #[test]
+    fn test_safe_mode_flag_passed() {
+        assert!(safe_mode_flag_passed(["app", "--safe-mode"]));
+        assert!(!safe_mode_flag_passed(["app", "--safe-mode=false", "--verbose"]));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let not_utf8 = std::ffi::OsString::from_vec(vec![0xff, 0xfe]);
+            assert!(safe_mode_flag_passed([not_utf8, "--safe-mode".into()]));
+        }
+        assert!(!Settings::default().safe_mode);
+    }
 }
@@ -0,0 +1,159 @@
+use serde::Serialize;
+
+use super::Theme;
+
+/// A theme key that was renamed or dropped in a Bitwig major version
+struct KeyAlias {
+    /// Major version in which the change happened
+    since: u32,
+    old: &'static str,
+    /// The current key, or `None` if the key was removed without a replacement
+    new: Option<&'static str>,
+}
+
+/// Keys renamed or removed between Bitwig versions
+const KEY_ALIASES: &[KeyAlias] = &[
+    KeyAlias {
+        since: 6,
+        old: "Selection",
+        new: Some("Selected Tree Item Background"),
+    },
+    KeyAlias {
+        since: 6,
+        old: "Standby selection",
+        new: Some("Selected Tree Item Background (standby)"),
+    },
+    KeyAlias {
+        since: 6,
+        old: "Hole (medium)",
+        new: Some("Knob Value Background"),
+    },
+    KeyAlias {
+        since: 6,
+        old: "Hole (dark)",
+        new: Some("Knob Value Background (dark)"),
+    },
+    KeyAlias {
+        since: 6,
+        old: "Hitech background",
+        new: None,
+    },
+    KeyAlias {
+        since: 6,
+        old: "Hitech on",
+        new: None,
+    },
+];
+
+/// Result of remapping a theme's keys to another Bitwig version
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRemapResult {
+    pub theme: Theme,
+    /// `(old, new)` pairs that were renamed
+    pub remapped: Vec<(String, String)>,
+    /// Keys that no longer exist in the target version and have no replacement
+    pub unmapped: Vec<String>,
+}
+
+/// Extract the major version from a Bitwig version string such as "5.2" or "6.0 Beta 6"
+pub fn major_version(version: &str) -> Option<u32> {
+    version
+        .trim()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .and_then(|major| major.parse().ok())
+}
+
+/// Rename keys that changed between `from_version` and `to_version`
+/// Keys whose new name is already set in the theme are left alone
+pub fn remap_theme_keys(theme: Theme, from_version: &str, to_version: &str) -> KeyRemapResult {
+    let mut result = KeyRemapResult {
+        theme,
+        remapped: Vec::new(),
+        unmapped: Vec::new(),
+    };
+
+    let (Some(from), Some(to)) = (major_version(from_version), major_version(to_version)) else {
+        return result;
+    };
+
+    for alias in KEY_ALIASES.iter().filter(|a| from < a.since && a.since <= to) {
+        if !result.theme.colors.contains_key(alias.old) {
+            continue;
+        }
+
+        match alias.new {
+            Some(new) if !result.theme.colors.contains_key(new) => {
+                let value = result.theme.colors.remove(alias.old).unwrap_or_default();
+                result.theme.colors.insert(new.to_string(), value);
+                result.remapped.push((alias.old.to_string(), new.to_string()));
+            }
+            Some(_) => {}
+            None => result.unmapped.push(alias.old.to_string()),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_major_version() {
+        assert_eq!(major_version("5.2"), Some(5));
+        assert_eq!(major_version("6.0 Beta 6"), Some(6));
+        assert_eq!(major_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_remap_known_renames() {
+        let theme = Theme::with_name("Old")
+            .color("Selection", "#ff5a00")
+            .color("Hole (dark)", "#111111")
+            .color("Hitech on", "#00ff00")
+            .color("Window background", "#222222");
+
+        let result = remap_theme_keys(theme, "5.2", "6.0 Beta 6");
+
+        assert_eq!(
+            result.theme.colors.get("Selected Tree Item Background"),
+            Some(&"#ff5a00".to_string())
+        );
+        assert_eq!(
+            result.theme.colors.get("Knob Value Background (dark)"),
+            Some(&"#111111".to_string())
+        );
+        assert!(!result.theme.colors.contains_key("Selection"));
+        assert_eq!(result.remapped.len(), 2);
+        assert_eq!(result.unmapped, vec!["Hitech on".to_string()]);
+        assert_eq!(
+            result.theme.colors.get("Window background"),
+            Some(&"#222222".to_string())
+        );
+    }
+
+    #[test]
+    fn test_remap_same_major_is_noop() {
+        let theme = Theme::with_name("Old").color("Selection", "#ff5a00");
+        let result = remap_theme_keys(theme, "5.1", "5.2");
+
+        assert!(result.remapped.is_empty());
+        assert!(result.theme.colors.contains_key("Selection"));
+    }
+
+    #[test]
+    fn test_remap_keeps_existing_new_key() {
+        let theme = Theme::with_name("Mixed")
+            .color("Selection", "#ff5a00")
+            .color("Selected Tree Item Background", "#0000ff");
+        let result = remap_theme_keys(theme, "5.2", "6.0");
+
+        assert!(result.remapped.is_empty());
+        assert_eq!(
+            result.theme.colors.get("Selected Tree Item Background"),
+            Some(&"#0000ff".to_string())
+        );
+    }
+}
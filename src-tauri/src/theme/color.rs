@@ -0,0 +1,375 @@
+use serde::{Deserialize, Serialize};
+
+use super::{infer_color_group, Theme, ThemeColor};
+
+/// An sRGB color parsed from a theme hex value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Alpha channel, only present for 8-digit hex values
+    pub a: Option<u8>,
+}
+
+impl Rgba {
+    /// Format the color as a lowercase hex string, keeping alpha if present
+    pub fn to_hex(&self) -> String {
+        match self.a {
+            Some(a) => format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, a),
+            None => format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b),
+        }
+    }
+}
+
+/// Parse a `#rrggbb` or `#rrggbbaa` hex color
+pub fn parse_hex(value: &str) -> Option<Rgba> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    match hex.len() {
+        6 => Some(Rgba {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+            a: None,
+        }),
+        8 => Some(Rgba {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+            a: Some(channel(6)?),
+        }),
+        _ => None,
+    }
+}
+
+/// A color in HSL space (hue in degrees, saturation and lightness in 0..=1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+/// Convert an sRGB color to HSL
+pub fn rgb_to_hsl(color: Rgba) -> Hsl {
+    let r = color.r as f64 / 255.0;
+    let g = color.g as f64 / 255.0;
+    let b = color.b as f64 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+
+    if delta == 0.0 {
+        return Hsl { h: 0.0, s: 0.0, l };
+    }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * (((g - b) / delta).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    Hsl { h, s, l }
+}
+
+/// Convert an HSL color to sRGB with the given alpha
+pub fn hsl_to_rgb(hsl: Hsl, alpha: Option<u8>) -> Rgba {
+    let h = hsl.h.rem_euclid(360.0);
+    let s = hsl.s.clamp(0.0, 1.0);
+    let l = hsl.l.clamp(0.0, 1.0);
+
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    let channel = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    Rgba {
+        r: channel(r),
+        g: channel(g),
+        b: channel(b),
+        a: alpha,
+    }
+}
+
+/// Convert an 8-bit sRGB channel to linear light
+fn srgb_to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear light value back to an 8-bit sRGB channel
+fn linear_to_srgb(value: f64) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// Color vision deficiency types supported by the simulator
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CvdType {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl CvdType {
+    /// Simulation matrix (Machado et al. 2009, full severity) applied in linear RGB
+    fn matrix(&self) -> [[f64; 3]; 3] {
+        match self {
+            CvdType::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            CvdType::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            CvdType::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Simulate how a single color appears with the given deficiency
+pub fn simulate_color(color: Rgba, kind: CvdType) -> Rgba {
+    let linear = [
+        srgb_to_linear(color.r),
+        srgb_to_linear(color.g),
+        srgb_to_linear(color.b),
+    ];
+    let m = kind.matrix();
+    let mix = |row: [f64; 3]| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+
+    Rgba {
+        r: linear_to_srgb(mix(m[0])),
+        g: linear_to_srgb(mix(m[1])),
+        b: linear_to_srgb(mix(m[2])),
+        a: color.a,
+    }
+}
+
+/// Return a copy of the theme with every color transformed by the simulator
+/// Values that aren't valid hex colors are kept unchanged
+pub fn simulate_colorblindness(theme: &Theme, kind: CvdType) -> Theme {
+    let mut simulated = theme.clone();
+    for value in simulated.colors.values_mut() {
+        if let Some(color) = parse_hex(value) {
+            *value = simulate_color(color, kind).to_hex();
+        }
+    }
+    simulated
+}
+
+/// Perceptual orderings for a palette view
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ColorSort {
+    /// Around the color wheel from red
+    Hue,
+    /// Dark to light (HSL lightness)
+    Luminance,
+    /// Grey to vivid
+    Saturation,
+}
+
+/// List a theme's colors in a perceptual order, ties broken by key
+/// Values that aren't valid hex colors come last, by key
+pub fn sort_colors_by(theme: &Theme, order: ColorSort) -> Vec<ThemeColor> {
+    let mut keyed: Vec<(Option<f64>, ThemeColor)> = theme
+        .colors
+        .iter()
+        .map(|(key, value)| {
+            let sort_key = parse_hex(value).map(|color| {
+                let hsl = rgb_to_hsl(color);
+                match order {
+                    ColorSort::Hue => hsl.h,
+                    ColorSort::Luminance => hsl.l,
+                    ColorSort::Saturation => hsl.s,
+                }
+            });
+            let color = ThemeColor {
+                key: key.clone(),
+                value: value.clone(),
+                group: Some(infer_color_group(key)),
+            };
+            (sort_key, color)
+        })
+        .collect();
+
+    keyed.sort_by(|(a, a_color), (b, b_color)| {
+        let by_value = match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        by_value.then_with(|| a_color.key.cmp(&b_color.key))
+    });
+    keyed.into_iter().map(|(_, color)| color).collect()
+}
+
+/// Most common color among a set of values, ignoring alpha; ties go to the lowest hex
+fn most_frequent_color<'a>(values: impl Iterator<Item = &'a String>) -> Option<String> {
+    let mut counts: std::collections::BTreeMap<String, usize> = Default::default();
+    for color in values.filter_map(|value| parse_hex(value)) {
+        let opaque = Rgba { a: None, ..color };
+        *counts.entry(opaque.to_hex()).or_default() += 1;
+    }
+    let max = counts.values().copied().max()?;
+    counts.into_iter().find(|(_, count)| *count == max).map(|(hex, _)| hex)
+}
+
+/// Single color representing a theme, for tinting its card in the browser
+/// The most frequent color in the `Background` group wins, since that is what
+/// fills most of the window; themes without background keys use the most
+/// frequent color overall. `None` when the theme has no valid colors
+pub fn theme_dominant_color(theme: &Theme) -> Option<String> {
+    let backgrounds = theme
+        .colors
+        .iter()
+        .filter(|(key, _)| infer_color_group(key) == "Background")
+        .map(|(_, value)| value);
+    most_frequent_color(backgrounds).or_else(|| most_frequent_color(theme.colors.values()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(
+            parse_hex("#1A1A2E"),
+            Some(Rgba { r: 0x1a, g: 0x1a, b: 0x2e, a: None })
+        );
+        assert_eq!(
+            parse_hex("#0000003c"),
+            Some(Rgba { r: 0, g: 0, b: 0, a: Some(0x3c) })
+        );
+        assert_eq!(parse_hex("#fff"), None);
+        assert_eq!(parse_hex("#gggggg"), None);
+        assert_eq!(parse_hex("1a1a2e"), None);
+    }
+
+    #[test]
+    fn test_hsl_conversion() {
+        let red = rgb_to_hsl(parse_hex("#ff0000").unwrap());
+        assert_eq!(red, Hsl { h: 0.0, s: 1.0, l: 0.5 });
+
+        let grey = rgb_to_hsl(parse_hex("#808080").unwrap());
+        assert_eq!(grey.s, 0.0);
+
+        for hex in ["#1a1a2e", "#e94560", "#3094d9", "#26d96f", "#ffffff", "#000000"] {
+            let color = parse_hex(hex).unwrap();
+            assert_eq!(hsl_to_rgb(rgb_to_hsl(color), None).to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn test_simulate_reference_colors() {
+        let red = parse_hex("#ff0000").unwrap();
+        assert_eq!(simulate_color(red, CvdType::Protanopia).to_hex(), "#6d5f00");
+        assert_eq!(simulate_color(red, CvdType::Deuteranopia).to_hex(), "#a39000");
+        assert_eq!(simulate_color(red, CvdType::Tritanopia).to_hex(), "#ff000f");
+
+        let accent = parse_hex("#e94560").unwrap();
+        assert_eq!(simulate_color(accent, CvdType::Protanopia).to_hex(), "#706d60");
+    }
+
+    #[test]
+    fn test_simulate_preserves_neutrals_and_alpha() {
+        for kind in [CvdType::Protanopia, CvdType::Deuteranopia, CvdType::Tritanopia] {
+            assert_eq!(
+                simulate_color(parse_hex("#ffffff").unwrap(), kind).to_hex(),
+                "#ffffff"
+            );
+            assert_eq!(
+                simulate_color(parse_hex("#00000080").unwrap(), kind).to_hex(),
+                "#00000080"
+            );
+        }
+    }
+
+    #[test]
+    fn test_simulate_colorblindness_theme() {
+        let theme = Theme::with_name("Test")
+            .color("Accent", "#ff0000")
+            .color("Broken", "not a color");
+
+        let simulated = simulate_colorblindness(&theme, CvdType::Protanopia);
+
+        assert_eq!(simulated.colors.get("Accent"), Some(&"#6d5f00".to_string()));
+        assert_eq!(simulated.colors.get("Broken"), Some(&"not a color".to_string()));
+        assert_eq!(simulated.metadata.name, Some("Test".to_string()));
+    }
+
+    #[test]
+    fn test_sort_colors_by_hue() {
+        let theme = Theme::with_name("Palette")
+            .color("Blue", "#0000ff")
+            .color("Broken", "not a color")
+            .color("Green", "#00ff00")
+            .color("Red", "#ff0000")
+            .color("Yellow", "#ffff00");
+        let keys = |order| -> Vec<String> {
+            sort_colors_by(&theme, order).into_iter().map(|c| c.key).collect()
+        };
+
+        assert_eq!(keys(ColorSort::Hue), ["Red", "Yellow", "Green", "Blue", "Broken"]);
+        // Pure colors share saturation and lightness, so keys break the tie
+        assert_eq!(keys(ColorSort::Luminance), ["Blue", "Green", "Red", "Yellow", "Broken"]);
+    }
+
+    #[test]
+    fn test_theme_dominant_color() {
+        let theme = Theme::with_name("Dark")
+            .color("Window background", "#1A1A2E")
+            .color("Panel background", "#1a1a2eff")
+            .color("Dialog background", "#333333")
+            .color("Text", "#eeeeee")
+            .color("Label text", "#eeeeee")
+            .color("Accent", "#eeeeee");
+        assert_eq!(theme_dominant_color(&theme).as_deref(), Some("#1a1a2e"));
+
+        let no_background = Theme::with_name("Flat")
+            .color("Accent", "#e94560")
+            .color("Text", "#ffffff")
+            .color("Label", "#ffffff");
+        assert_eq!(theme_dominant_color(&no_background).as_deref(), Some("#ffffff"));
+
+        assert_eq!(theme_dominant_color(&Theme::with_name("Empty")), None);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use super::{parse_hex, Theme};
+
+/// Prefix shared by every theme custom property
+const CSS_VARIABLE_PREFIX: &str = "--bitwig-";
+
+/// Turn a theme key into a CSS identifier: lowercase ASCII letters, digits and dashes
+/// e.g. `Background color` and `Window.Background` become `background-color` and
+/// `window-background`
+pub fn css_identifier(key: &str) -> String {
+    let mut ident = String::with_capacity(key.len());
+    for c in key.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            ident.push(c.to_ascii_lowercase());
+        } else if !ident.ends_with('-') {
+            ident.push('-');
+        }
+    }
+    ident.trim_matches('-').to_string()
+}
+
+/// Emit a theme's colors as `--bitwig-<key>: <hex>;` lines, sorted by key
+/// Keys that collapse to the same identifier get `-2`, `-3`, ... suffixes so no color
+/// is lost; values that aren't hex colors are skipped
+pub fn theme_to_css_variables(theme: &Theme) -> String {
+    let mut keys: Vec<&String> = theme.colors.keys().collect();
+    keys.sort();
+
+    let mut used = HashSet::new();
+    let mut css = String::new();
+    for key in keys {
+        let Some(color) = parse_hex(&theme.colors[key]) else {
+            continue;
+        };
+        let base = css_identifier(key);
+        let mut ident = base.clone();
+        let mut n = 2;
+        while !used.insert(ident.clone()) {
+            ident = format!("{}-{}", base, n);
+            n += 1;
+        }
+        css.push_str(&format!("{}{}: {};\n", CSS_VARIABLE_PREFIX, ident, color.to_hex()));
+    }
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_to_css_variables() {
+        let theme = Theme::with_name("Css")
+            .color("Background color", "#1A1A2E")
+            .color("Window.Background", "#0000003c")
+            .color("Window Background", "#222222")
+            .color("Broken", "red");
+
+        assert_eq!(
+            theme_to_css_variables(&theme),
+            "--bitwig-background-color: #1a1a2e;\n\
+             --bitwig-window-background: #222222;\n\
+             --bitwig-window-background-2: #0000003c;\n"
+        );
+        assert_eq!(css_identifier("  Selected / Tree Item  "), "selected-tree-item");
+    }
+}
@@ -0,0 +1,215 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{
+    diff_themes, infer_color_group, major_version, parse_theme_content, Theme, ThemeDiff,
+};
+
+/// Community reference palettes shipped with the app, keyed by Bitwig major version
+/// Each holds the most common value of every key across community themes for that
+/// release. They are not Bitwig's stock theme, so they serve as a comparison baseline and
+/// key list only, never as the authority on whether a theme is valid
+const DEFAULT_THEMES: &[(u32, &str)] = &[
+    (5, include_str!("../../defaults/bitwig-5.bte")),
+    (6, include_str!("../../defaults/bitwig-6.bte")),
+];
+
+/// Get the community reference palette for a Bitwig version
+/// Returns None if no palette is bundled for the version's major release
+pub fn default_theme(bitwig_version: &str) -> Option<Theme> {
+    let major = major_version(bitwig_version)?;
+    let (_, content) = DEFAULT_THEMES.iter().find(|(m, _)| *m == major)?;
+    parse_theme_content(content, None).ok()
+}
+
+/// A theme key known to a Bitwig version
+#[derive(Debug, Clone, Serialize)]
+pub struct KeySchema {
+    pub key: String,
+    pub group: String,
+}
+
+/// List the keys community themes set for a Bitwig version, sorted by key
+/// Returns None if no reference palette is bundled for the version's major release
+pub fn theme_key_schema(bitwig_version: &str) -> Option<Vec<KeySchema>> {
+    let mut schema: Vec<KeySchema> = default_theme(bitwig_version)?
+        .colors
+        .into_keys()
+        .map(|key| KeySchema {
+            group: infer_color_group(&key),
+            key,
+        })
+        .collect();
+
+    schema.sort_by(|a, b| a.key.cmp(&b.key));
+    Some(schema)
+}
+
+/// Percentage (0-100) of the reference palette's keys that the theme sets to a different value
+/// Keys missing from the theme count as unchanged
+pub fn customization_percentage(theme: &Theme, bitwig_version: &str) -> Option<f64> {
+    let default = default_theme(bitwig_version)?;
+    if default.colors.is_empty() {
+        return None;
+    }
+
+    let changed = default
+        .colors
+        .iter()
+        .filter(|(key, value)| {
+            theme
+                .colors
+                .get(*key)
+                .is_some_and(|v| !v.eq_ignore_ascii_case(value))
+        })
+        .count();
+
+    Some(changed as f64 * 100.0 / default.colors.len() as f64)
+}
+
+/// Result of matching a theme's keys to a version's canonical spelling
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyCaseResult {
+    pub theme: Theme,
+    /// `(original, canonical)` pairs that were corrected
+    pub corrected: Vec<(String, String)>,
+}
+
+/// Fold a key for loose matching: lowercase with whitespace runs collapsed
+fn fold_key(key: &str) -> String {
+    key.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Rename keys that only differ from a known key by case or whitespace
+/// Keys that already match exactly, or whose canonical key is already set, are left alone
+pub fn normalize_key_casing(theme: Theme, bitwig_version: &str) -> KeyCaseResult {
+    let mut result = KeyCaseResult {
+        theme,
+        corrected: Vec::new(),
+    };
+    let Some(default) = default_theme(bitwig_version) else {
+        return result;
+    };
+
+    let canonical: HashMap<String, &String> =
+        default.colors.keys().map(|key| (fold_key(key), key)).collect();
+
+    let mut keys: Vec<String> = result.theme.colors.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+        if default.colors.contains_key(&key) {
+            continue;
+        }
+        let Some(&canonical_key) = canonical.get(&fold_key(&key)) else {
+            continue;
+        };
+        if result.theme.colors.contains_key(canonical_key) {
+            continue;
+        }
+        let value = result.theme.colors.remove(&key).unwrap_or_default();
+        result.theme.colors.insert(canonical_key.clone(), value);
+        result.corrected.push((key, canonical_key.clone()));
+    }
+
+    result
+}
+
+/// Compare a theme against the community reference palette for a Bitwig version
+/// `old` values are the reference values; `removed` lists reference keys the theme leaves unset
+pub fn diff_against_default(theme: &Theme, bitwig_version: &str) -> Option<ThemeDiff> {
+    let default = default_theme(bitwig_version)?;
+    Some(diff_themes(&default, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_themes_parse() {
+        for version in ["5.2", "6.0 Beta 6"] {
+            let theme = default_theme(version).unwrap();
+            assert!(theme.colors.len() > 200);
+        }
+        assert!(default_theme("4.4").is_none());
+        assert!(default_theme("unknown").is_none());
+    }
+
+    #[test]
+    fn test_customization_percentage() {
+        let default = default_theme("5.2").unwrap();
+        assert_eq!(customization_percentage(&default, "5.2"), Some(0.0));
+
+        let mut customized = default.clone();
+        for value in customized.colors.values_mut() {
+            *value = "#123456".to_string();
+        }
+        assert_eq!(customization_percentage(&customized, "5.2"), Some(100.0));
+
+        let mut half = default.clone();
+        let mut keys: Vec<String> = half.colors.keys().cloned().collect();
+        keys.sort();
+        let count = keys.len() / 2;
+        for key in keys.into_iter().take(count) {
+            half.colors.insert(key, "#123456".to_string());
+        }
+        let pct = customization_percentage(&half, "5.2").unwrap();
+        assert!((pct - count as f64 * 100.0 / default.colors.len() as f64).abs() < 1e-9);
+
+        assert_eq!(customization_percentage(&default, "4.0"), None);
+    }
+
+    #[test]
+    fn test_theme_key_schema() {
+        let schema = theme_key_schema("5.2").unwrap();
+        let window = schema.iter().find(|k| k.key == "Window background").unwrap();
+        assert_eq!(window.group, infer_color_group("Window background"));
+        assert_eq!(schema.len(), default_theme("5.2").unwrap().colors.len());
+
+        assert!(theme_key_schema("6.0").is_some());
+        assert!(theme_key_schema("3.0").is_none());
+    }
+
+    #[test]
+    fn test_diff_against_default() {
+        let default = default_theme("5.2").unwrap();
+        assert!(diff_against_default(&default, "5.2").unwrap().is_empty());
+
+        let (key, _) = default.colors.iter().next().unwrap();
+        let mut theme = Theme::with_name("Tweaked").color(key, "#123456");
+        theme.colors.insert("Custom key".to_string(), "#abcdef".to_string());
+
+        let diff = diff_against_default(&theme, "5.2").unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].key, *key);
+        assert_eq!(diff.added, vec![("Custom key".to_string(), "#abcdef".to_string())]);
+        assert_eq!(diff.removed.len(), default.colors.len() - 1);
+
+        assert!(diff_against_default(&theme, "4.0").is_none());
+    }
+
+    #[test]
+    fn test_normalize_key_casing() {
+        let theme = Theme::with_name("Miscased")
+            .color("window BACKGROUND", "#111111")
+            .color("  Window   background ", "#222222")
+            .color("Not a bitwig key", "#333333");
+
+        let result = normalize_key_casing(theme, "5.2");
+
+        assert_eq!(
+            result.corrected,
+            vec![("  Window   background ".to_string(), "Window background".to_string())]
+        );
+        assert_eq!(
+            result.theme.colors.get("Window background"),
+            Some(&"#222222".to_string())
+        );
+        // The canonical key was already taken, so the second spelling stays as it was
+        assert!(result.theme.colors.contains_key("window BACKGROUND"));
+        assert!(result.theme.colors.contains_key("Not a bitwig key"));
+    }
+}
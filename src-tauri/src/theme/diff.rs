@@ -0,0 +1,133 @@
+use serde::Serialize;
+
+use super::{normalize_color_value, Theme};
+
+/// A key whose color differs between two themes
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ColorChange {
+    pub key: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Key/value differences going from one theme to another
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct ThemeDiff {
+    /// Keys only present in the new theme, with their value
+    pub added: Vec<(String, String)>,
+    /// Keys only present in the old theme, with their value
+    pub removed: Vec<(String, String)>,
+    pub changed: Vec<ColorChange>,
+}
+
+impl ThemeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare the colors of two themes, ignoring hex case
+/// All lists are sorted by key
+pub fn diff_themes(old: &Theme, new: &Theme) -> ThemeDiff {
+    let mut diff = ThemeDiff::default();
+
+    for (key, new_value) in &new.colors {
+        match old.colors.get(key) {
+            None => diff.added.push((key.clone(), new_value.clone())),
+            Some(old_value)
+                if normalize_color_value(old_value) != normalize_color_value(new_value) =>
+            {
+                diff.changed.push(ColorChange {
+                    key: key.clone(),
+                    old: old_value.clone(),
+                    new: new_value.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, old_value) in &old.colors {
+        if !new.colors.contains_key(key) {
+            diff.removed.push((key.clone(), old_value.clone()));
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|a, b| a.key.cmp(&b.key));
+    diff
+}
+
+/// Build an overlay holding only the keys `modified` adds or changes relative to `base`
+/// Keys removed in `modified` can't be expressed as an overlay and are left out
+pub fn theme_overlay(base: &Theme, modified: &Theme) -> Theme {
+    let diff = diff_themes(base, modified);
+    let mut overlay = Theme::new();
+    overlay.metadata = modified.metadata.clone();
+    overlay.colors.extend(diff.added);
+    overlay
+        .colors
+        .extend(diff.changed.into_iter().map(|change| (change.key, change.new)));
+    overlay
+}
+
+/// Lay an overlay's colors over a base theme, keeping the base's metadata
+pub fn apply_theme_overlay(base: &Theme, overlay: &Theme) -> Theme {
+    let mut merged = base.clone();
+    merged
+        .colors
+        .extend(overlay.colors.iter().map(|(k, v)| (k.clone(), v.clone())));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_themes() {
+        let old = Theme::with_name("Mine")
+            .color("Background", "#111111")
+            .color("Accent", "#E94560")
+            .color("Text", "#ffffff");
+        let new = Theme::with_name("Upstream")
+            .color("Background", "#222222")
+            .color("Accent", "#e94560")
+            .color("Selection", "#ff5a00");
+
+        let diff = diff_themes(&old, &new);
+
+        assert_eq!(diff.added, vec![("Selection".to_string(), "#ff5a00".to_string())]);
+        assert_eq!(diff.removed, vec![("Text".to_string(), "#ffffff".to_string())]);
+        assert_eq!(
+            diff.changed,
+            vec![ColorChange {
+                key: "Background".to_string(),
+                old: "#111111".to_string(),
+                new: "#222222".to_string(),
+            }]
+        );
+        assert!(diff_themes(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_theme_overlay_round_trip() {
+        let base = Theme::with_name("Base")
+            .color("Background", "#111111")
+            .color("Accent", "#e94560")
+            .color("Text", "#ffffff");
+        let modified = Theme::with_name("Tweaked")
+            .color("Background", "#222222")
+            .color("Accent", "#E94560")
+            .color("Text", "#ffffff")
+            .color("Selection", "#ff5a00");
+
+        let overlay = theme_overlay(&base, &modified);
+        assert_eq!(overlay.colors.len(), 2);
+        assert_eq!(overlay.colors.get("Background").map(String::as_str), Some("#222222"));
+        assert_eq!(overlay.colors.get("Selection").map(String::as_str), Some("#ff5a00"));
+
+        let merged = apply_theme_overlay(&base, &overlay);
+        assert!(diff_themes(&modified, &merged).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::color::{hsl_to_rgb, parse_hex, Hsl};
+use super::parser::infer_color_group;
+use super::Theme;
+
+/// Hue offsets (degrees) used for accent colors, relative to the base hue
+const ACCENT_HUE_OFFSETS: [f64; 5] = [0.0, 30.0, 150.0, 180.0, 210.0];
+
+/// Small deterministic PRNG (SplitMix64) so a seed always reproduces a palette
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in `min..max`
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        min + unit * (max - min)
+    }
+}
+
+/// Generate a coherent random palette over the base theme's keys
+///
+/// Backgrounds are dark and desaturated around a random base hue, text is
+/// high-contrast, and accents use fixed hue offsets from the base. The same
+/// seed always produces the same theme.
+pub fn generate_random_theme(seed: Option<u64>, base: &Theme) -> Theme {
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    });
+    let mut rng = SplitMix64(seed);
+    let base_hue = rng.range(0.0, 360.0);
+
+    let mut keys: Vec<&String> = base.colors.keys().collect();
+    keys.sort();
+
+    let mut theme = Theme::with_name(&format!("Random {}", seed));
+    theme.metadata.description = Some(format!("Generated from seed {}", seed));
+
+    for key in keys {
+        let alpha = parse_hex(&base.colors[key]).and_then(|c| c.a);
+        let hsl = match infer_color_group(key).as_str() {
+            "Background" => Hsl {
+                h: base_hue,
+                s: rng.range(0.08, 0.18),
+                l: rng.range(0.08, 0.22),
+            },
+            "Text" => Hsl {
+                h: base_hue,
+                s: rng.range(0.05, 0.12),
+                l: rng.range(0.85, 0.95),
+            },
+            "Accent" => {
+                let offset = ACCENT_HUE_OFFSETS[(rng.next_u64() % 5) as usize];
+                Hsl {
+                    h: base_hue + offset,
+                    s: rng.range(0.6, 0.8),
+                    l: rng.range(0.5, 0.6),
+                }
+            }
+            "Controls" => Hsl {
+                h: base_hue,
+                s: rng.range(0.1, 0.2),
+                l: rng.range(0.25, 0.35),
+            },
+            "Border" => Hsl {
+                h: base_hue,
+                s: rng.range(0.05, 0.15),
+                l: rng.range(0.15, 0.3),
+            },
+            "Tracks" => Hsl {
+                h: base_hue + rng.range(-60.0, 60.0),
+                s: rng.range(0.45, 0.6),
+                l: rng.range(0.45, 0.55),
+            },
+            "Selection" => Hsl {
+                h: base_hue + 180.0,
+                s: rng.range(0.45, 0.6),
+                l: rng.range(0.4, 0.5),
+            },
+            _ => Hsl {
+                h: base_hue,
+                s: rng.range(0.1, 0.3),
+                l: rng.range(0.2, 0.6),
+            },
+        };
+        theme.colors.insert(key.clone(), hsl_to_rgb(hsl, alpha).to_hex());
+    }
+
+    theme
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::color::rgb_to_hsl;
+
+    fn base_theme() -> Theme {
+        Theme::new()
+            .color("Window background", "#222222")
+            .color("Lighter Text", "#ececec")
+            .color("Accent (default)", "#ff5a00")
+            .color("Emboss Shadow", "#0000003c")
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let base = base_theme();
+        let a = generate_random_theme(Some(42), &base);
+        let b = generate_random_theme(Some(42), &base);
+        let c = generate_random_theme(Some(43), &base);
+
+        assert_eq!(a.colors, b.colors);
+        assert_ne!(a.colors, c.colors);
+        assert_eq!(a.colors.len(), base.colors.len());
+    }
+
+    #[test]
+    fn test_generate_keeps_alpha_and_contrast() {
+        let theme = generate_random_theme(Some(7), &base_theme());
+
+        assert_eq!(theme.colors["Emboss Shadow"].len(), 9);
+        assert!(theme.colors["Emboss Shadow"].ends_with("3c"));
+
+        let bg = rgb_to_hsl(parse_hex(&theme.colors["Window background"]).unwrap());
+        let text = rgb_to_hsl(parse_hex(&theme.colors["Lighter Text"]).unwrap());
+        assert!(text.l - bg.l > 0.5);
+    }
+}
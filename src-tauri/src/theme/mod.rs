@@ -1,5 +1,25 @@
+pub mod aliases;
+pub mod color;
+pub mod css;
+pub mod defaults;
+pub mod diff;
+pub mod generator;
+pub mod package;
 pub mod parser;
+pub mod preview;
+pub mod validation;
+pub mod verified;
 pub mod watcher;
 
+pub use aliases::*;
+pub use color::*;
+pub use css::*;
+pub use defaults::*;
+pub use diff::*;
+pub use generator::*;
+pub use package::*;
 pub use parser::*;
+pub use preview::*;
+pub use validation::*;
+pub use verified::*;
 pub use watcher::*;
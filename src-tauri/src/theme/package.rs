@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+use super::{
+    convert_json_to_bte, is_json_content, parse_theme_auto, ThemeError, ACTIVE_THEME_FILE,
+};
+
+/// Manifest entry identifying a single-theme package
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Current single-theme package layout
+const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+/// Image types a package preview may have; anything else is refused on import
+pub const PREVIEW_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+/// Lowercased extension of a preview file name, if it is an allowed image type
+fn preview_extension(name: &str) -> Option<String> {
+    let ext = Path::new(name).extension()?.to_string_lossy().to_lowercase();
+    PREVIEW_IMAGE_EXTENSIONS.contains(&ext.as_str()).then_some(ext)
+}
+
+/// Check that text is a BTE or JSON theme with at least one color
+pub fn validate_theme_text(content: &str) -> Result<(), ThemeError> {
+    let theme = parse_theme_auto(content, None, None)?;
+    if theme.colors.is_empty() {
+        return Err(ThemeError::InvalidFormat("No colors found in the theme".to_string()));
+    }
+    Ok(())
+}
+
+/// Describes the contents of a single-theme package
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThemePackageManifest {
+    pub format_version: u32,
+    pub name: String,
+    /// Entry holding the `.bte` theme
+    pub theme_file: String,
+    /// Entry holding the preview image, if one was included
+    pub preview_file: Option<String>,
+}
+
+/// A theme read back from a single-theme package
+#[derive(Debug, Clone)]
+pub struct ThemePackage {
+    pub manifest: ThemePackageManifest,
+    pub theme_content: String,
+    /// Preview image bytes and the file extension they were stored with
+    pub preview: Option<(String, Vec<u8>)>,
+}
+
+/// Write a zip with the theme, an optional preview image and a manifest
+pub fn write_theme_package(
+    dest: &Path,
+    name: &str,
+    theme_content: &str,
+    preview: Option<&Path>,
+) -> Result<(), ThemeError> {
+    // Previews of other types would be refused on import, so they are left out
+    let preview_ext = preview.and_then(|path| preview_extension(&path.to_string_lossy()));
+    let preview = match (preview, preview_ext) {
+        (Some(path), Some(ext)) => Some((format!("preview.{}", ext), std::fs::read(path)?)),
+        _ => None,
+    };
+
+    let manifest = ThemePackageManifest {
+        format_version: PACKAGE_FORMAT_VERSION,
+        name: name.to_string(),
+        theme_file: "theme.bte".to_string(),
+        preview_file: preview.as_ref().map(|(entry, _)| entry.clone()),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| ThemeError::InvalidFormat(e.to_string()))?;
+
+    let mut zip = zip::ZipWriter::new(File::create(dest)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(manifest_json.as_bytes())?;
+    zip.start_file(manifest.theme_file.as_str(), options)?;
+    zip.write_all(theme_content.as_bytes())?;
+    if let Some((entry, bytes)) = &preview {
+        zip.start_file(entry.as_str(), options)?;
+        zip.write_all(bytes)?;
+    }
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Check whether a file is a single-theme package rather than a bare theme
+pub fn is_theme_package(path: &Path) -> bool {
+    File::open(path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(file).ok())
+        .is_some_and(|archive| archive.file_names().any(|name| name == MANIFEST_NAME))
+}
+
+/// Read the theme and preview out of a single-theme package
+/// Entries are size-capped, the theme must parse, and the preview must be an image
+pub fn read_theme_package(path: &Path) -> Result<ThemePackage, ThemeError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+
+    let manifest_json = read_text_entry_capped(archive.by_name(MANIFEST_NAME)?, MANIFEST_NAME)?;
+    let manifest: ThemePackageManifest = serde_json::from_str(&manifest_json)
+        .map_err(|e| ThemeError::InvalidFormat(format!("Invalid package manifest: {}", e)))?;
+
+    let theme_content =
+        read_text_entry_capped(archive.by_name(&manifest.theme_file)?, &manifest.theme_file)?;
+    validate_theme_text(&theme_content)?;
+
+    let preview = match &manifest.preview_file {
+        Some(entry) => {
+            let ext = preview_extension(entry).ok_or_else(|| {
+                ThemeError::InvalidFormat(format!(
+                    "Package preview {} is not a PNG, JPEG or WebP image",
+                    entry
+                ))
+            })?;
+            Some((ext, read_entry_capped(archive.by_name(entry)?, entry)?))
+        }
+        None => None,
+    };
+
+    Ok(ThemePackage {
+        manifest,
+        theme_content,
+        preview,
+    })
+}
+
+/// Read the theme out of a plain zip that carries no package manifest
+/// Prefers `.bte` or `.json` entries, then larger text entries; the first that parses to
+/// at least one color is used. Entries over `MAX_ARCHIVE_ENTRY_BYTES` are skipped.
+/// JSON themes are returned as BTE text
+pub fn extract_theme_from_zip(path: &Path) -> Result<String, ThemeError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let Ok(text) = read_text_entry_capped(entry, &name) else {
+            continue;
+        };
+        if !text.contains('\0') {
+            entries.push((name, text));
+        }
+    }
+
+    let has_theme_extension = |name: &str| {
+        Path::new(name).extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("bte") || ext.eq_ignore_ascii_case("json")
+        })
+    };
+
+    entries.sort_by_key(|(name, content)| {
+        (!has_theme_extension(name), std::cmp::Reverse(content.len()))
+    });
+    let content = entries
+        .into_iter()
+        .map(|(_, content)| content)
+        .find(|content| validate_theme_text(content).is_ok())
+        .ok_or_else(|| ThemeError::InvalidFormat("No theme found in the zip archive".to_string()))?;
+
+    if is_json_content(&content) {
+        return convert_json_to_bte(&content, None);
+    }
+    Ok(content)
+}
+
+/// Largest zip entry read into memory; packages and snapshots come from outside the app
+pub const MAX_ARCHIVE_ENTRY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Read a zip entry, failing once it grows past `MAX_ARCHIVE_ENTRY_BYTES`
+/// The size an entry declares can't be trusted, so the read itself is bounded
+pub(crate) fn read_entry_capped(entry: impl Read, name: &str) -> Result<Vec<u8>, ThemeError> {
+    let mut bytes = Vec::new();
+    entry
+        .take(MAX_ARCHIVE_ENTRY_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_ARCHIVE_ENTRY_BYTES {
+        return Err(ThemeError::InvalidFormat(format!(
+            "{} is larger than {} MB",
+            name,
+            MAX_ARCHIVE_ENTRY_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Read a zip entry as UTF-8 text, bounded like `read_entry_capped`
+fn read_text_entry_capped(entry: impl Read, name: &str) -> Result<String, ThemeError> {
+    String::from_utf8(read_entry_capped(entry, name)?)
+        .map_err(|_| ThemeError::InvalidFormat(format!("{} is not UTF-8 text", name)))
+}
+
+/// Whether a path relative to a theme directory is a library theme, as opposed to the
+/// active theme, its sidecars (`theme.bte.lock`, ...) or the `snapshots/` directory
+pub fn is_library_theme_file(relative: &Path) -> bool {
+    let mut components = relative.components();
+    let (Some(Component::Normal(name)), None) = (components.next(), components.next()) else {
+        return false;
+    };
+    let name = Path::new(name);
+    name != Path::new(ACTIVE_THEME_FILE)
+        && name.extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("bte") || ext.eq_ignore_ascii_case("json")
+        })
+}
+
+/// Zip the library themes in a theme directory
+/// Returns how many files were stored
+pub fn write_library_snapshot(theme_dir: &Path, dest: &Path) -> Result<usize, ThemeError> {
+    let mut zip = zip::ZipWriter::new(File::create(dest)?);
+    let options = SimpleFileOptions::default();
+    let mut count = 0;
+
+    for entry in walkdir::WalkDir::new(theme_dir).max_depth(1).sort_by_file_name() {
+        let entry = entry.map_err(|e| ThemeError::Io(e.into()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(theme_dir) else {
+            continue;
+        };
+        if !is_library_theme_file(relative) {
+            continue;
+        }
+
+        zip.start_file(relative.to_string_lossy(), options)?;
+        zip.write_all(&std::fs::read(entry.path())?)?;
+        count += 1;
+    }
+    zip.finish()?;
+
+    Ok(count)
+}
+
+/// Read the library themes out of a snapshot as paths relative to the theme directory
+/// Anything else, including entries that would land outside the theme directory, is
+/// skipped
+pub fn read_library_snapshot(path: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>, ThemeError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut files = Vec::new();
+
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir() || !is_library_theme_file(&relative) {
+            continue;
+        }
+        let bytes = read_entry_capped(entry, &relative.to_string_lossy())?;
+        files.push((relative, bytes));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_package_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let preview = dir.path().join("Dark.png");
+        let package = dir.path().join("Dark.zip");
+        std::fs::write(&preview, b"\x89PNG\r\n\x1a\nimage").unwrap();
+
+        write_theme_package(&package, "Dark", "Background: #111111\n", Some(&preview)).unwrap();
+
+        assert!(is_theme_package(&package));
+        assert!(!is_theme_package(&preview));
+
+        let read = read_theme_package(&package).unwrap();
+        assert_eq!(read.manifest.name, "Dark");
+        assert_eq!(read.theme_content, "Background: #111111\n");
+        assert_eq!(
+            read.preview,
+            Some(("png".to_string(), b"\x89PNG\r\n\x1a\nimage".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_theme_package_without_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("Plain.zip");
+
+        write_theme_package(&package, "Plain", "Background: #222222\n", None).unwrap();
+
+        let read = read_theme_package(&package).unwrap();
+        assert_eq!(read.manifest.preview_file, None);
+        assert!(read.preview.is_none());
+    }
+
+    #[test]
+    fn test_theme_package_rejects_unsafe_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, theme: &str, preview: &str| {
+            let path = dir.path().join(name);
+            let manifest = ThemePackageManifest {
+                format_version: PACKAGE_FORMAT_VERSION,
+                name: "Evil".to_string(),
+                theme_file: "theme.bte".to_string(),
+                preview_file: Some(preview.to_string()),
+            };
+            let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = SimpleFileOptions::default();
+            zip.start_file(MANIFEST_NAME, options).unwrap();
+            zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes()).unwrap();
+            zip.start_file("theme.bte", options).unwrap();
+            zip.write_all(theme.as_bytes()).unwrap();
+            zip.start_file(preview, options).unwrap();
+            zip.write_all(b"MZ").unwrap();
+            zip.finish().unwrap();
+            path
+        };
+
+        let exe = write("exe.zip", "Background: #111111\n", "preview.exe");
+        assert!(matches!(read_theme_package(&exe), Err(ThemeError::InvalidFormat(_))));
+
+        let not_a_theme = write("text.zip", "just some notes\n", "preview.png");
+        assert!(read_theme_package(&not_a_theme).is_err());
+
+        let jpeg = write("jpeg.zip", "Background: #111111\n", "preview.JPG");
+        assert_eq!(read_theme_package(&jpeg).unwrap().preview.unwrap().0, "jpg");
+    }
+
+    #[test]
+    fn test_extract_theme_from_zip_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("Dark.zip");
+
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        zip.start_file("notes.txt", options).unwrap();
+        zip.write_all(b"Made by someone").unwrap();
+        zip.start_file("theme.txt", options).unwrap();
+        zip.write_all(b"Background: #111111\nAccent: #e94560\n").unwrap();
+        zip.finish().unwrap();
+
+        let content = extract_theme_from_zip(&archive).unwrap();
+        assert_eq!(content, "Background: #111111\nAccent: #e94560\n");
+
+        let empty = dir.path().join("Empty.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&empty).unwrap());
+        zip.start_file("notes.txt", options).unwrap();
+        zip.write_all(b"Made by someone").unwrap();
+        zip.finish().unwrap();
+
+        assert!(matches!(
+            extract_theme_from_zip(&empty),
+            Err(ThemeError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_extract_theme_from_zip_skips_invalid_and_oversized_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("Mixed.zip");
+
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        zip.start_file("broken.bte", options).unwrap();
+        zip.write_all(b"not a theme at all").unwrap();
+        zip.start_file("huge.bte", options).unwrap();
+        let line = b"Background: #111111\n";
+        let repeats = MAX_ARCHIVE_ENTRY_BYTES as usize / line.len() + 1;
+        zip.write_all(&line.repeat(repeats)).unwrap();
+        zip.start_file("colors.txt", options).unwrap();
+        zip.write_all(b"Accent: #e94560\n").unwrap();
+        zip.finish().unwrap();
+
+        assert_eq!(extract_theme_from_zip(&archive).unwrap(), "Accent: #e94560\n");
+    }
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -16,6 +19,56 @@ pub enum ThemeError {
 
     #[error("Theme not found: {0}")]
     NotFound(PathBuf),
+
+    #[error("Failed to render preview: {0}")]
+    Render(String),
+
+    #[error("Theme package error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Theme has more than {limit} keys; the file may be corrupt")]
+    TooManyKeys { limit: usize },
+
+    #[error("Theme directory {} must be inside your home or app data directory", .0.display())]
+    DirectoryNotAllowed(PathBuf),
+}
+
+/// Default for the most keys a theme may have before parsing gives up
+pub const DEFAULT_MAX_THEME_KEYS: usize = 5000;
+
+/// Key limit in effect, set from the `max_theme_keys` setting
+static MAX_THEME_KEYS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_THEME_KEYS);
+
+/// Change the most keys a theme may have; untrusted files beyond it are rejected
+pub fn set_max_theme_keys(limit: usize) {
+    MAX_THEME_KEYS.store(limit.max(1), Ordering::Relaxed);
+}
+
+/// The most keys a theme may have
+pub fn max_theme_keys() -> usize {
+    MAX_THEME_KEYS.load(Ordering::Relaxed)
+}
+
+/// Fail once a theme holds more keys than the limit
+fn check_key_count(count: usize) -> Result<(), ThemeError> {
+    let limit = max_theme_keys();
+    if count > limit {
+        return Err(ThemeError::TooManyKeys { limit });
+    }
+    Ok(())
+}
+
+/// Bytes of JSON allowed per key before a theme is parsed
+/// Real entries take well under 100 bytes, even pretty-printed
+const MAX_JSON_BYTES_PER_KEY: usize = 256;
+
+/// Fail on JSON too large to stay within the key limit, before deserializing any of it
+fn check_json_size(content: &str) -> Result<(), ThemeError> {
+    let limit = max_theme_keys();
+    if content.len() > limit.saturating_mul(MAX_JSON_BYTES_PER_KEY) {
+        return Err(ThemeError::TooManyKeys { limit });
+    }
+    Ok(())
 }
 
 /// A color property in a theme
@@ -41,6 +94,24 @@ pub struct Theme {
     pub metadata: ThemeMetadata,
     pub colors: HashMap<String, String>,
     pub path: Option<PathBuf>,
+    /// Top-level JSON entries the app doesn't model as colors, so JSON themes round-trip
+    /// losslessly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+    /// JSON section each color was read from, for colors outside `window`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub color_sections: HashMap<String, String>,
+    /// Keys a JSON theme defines differently in more than one section
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<KeyConflict>,
+}
+
+/// A key given different values in more than one JSON color section
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyConflict {
+    pub key: String,
+    /// Every `(section, value)` pair in `JSON_COLOR_SECTIONS` order; the last one is used
+    pub candidates: Vec<(String, String)>,
 }
 
 impl Theme {
@@ -50,6 +121,9 @@ impl Theme {
             metadata: ThemeMetadata::default(),
             colors: HashMap::new(),
             path: None,
+            extras: None,
+            color_sections: HashMap::new(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -62,9 +136,63 @@ impl Theme {
             },
             colors: HashMap::new(),
             path: None,
+            extras: None,
+            color_sections: HashMap::new(),
+            conflicts: Vec::new(),
         }
     }
 
+    /// Create a named theme from an existing color map
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use bitwig_theme_manager_lib::Theme;
+    ///
+    /// let colors = HashMap::from([("Window.Background".to_string(), "#1a1a2e".to_string())]);
+    /// let theme = Theme::from_colors("Midnight", colors);
+    ///
+    /// assert_eq!(theme.metadata.name.as_deref(), Some("Midnight"));
+    /// assert_eq!(theme.colors["Window.Background"], "#1a1a2e");
+    /// ```
+    pub fn from_colors(name: &str, colors: HashMap<String, String>) -> Self {
+        Self {
+            colors,
+            ..Self::with_name(name)
+        }
+    }
+
+    /// Set the author, returning the theme for chaining
+    ///
+    /// ```
+    /// use bitwig_theme_manager_lib::Theme;
+    ///
+    /// let theme = Theme::with_name("Midnight")
+    ///     .author("someone")
+    ///     .description("A dark theme")
+    ///     .color("Window.Background", "#1a1a2e")
+    ///     .color("Accent", "#e94560");
+    ///
+    /// assert_eq!(theme.metadata.author.as_deref(), Some("someone"));
+    /// assert_eq!(theme.metadata.description.as_deref(), Some("A dark theme"));
+    /// assert_eq!(theme.colors.len(), 2);
+    /// ```
+    pub fn author(mut self, author: &str) -> Self {
+        self.metadata.author = Some(author.to_string());
+        self
+    }
+
+    /// Set the description, returning the theme for chaining
+    pub fn description(mut self, description: &str) -> Self {
+        self.metadata.description = Some(description.to_string());
+        self
+    }
+
+    /// Set a color, returning the theme for chaining
+    pub fn color(mut self, key: &str, value: &str) -> Self {
+        self.colors.insert(key.to_string(), value.to_string());
+        self
+    }
+
     /// Get colors grouped by category
     pub fn get_grouped_colors(&self) -> HashMap<String, Vec<ThemeColor>> {
         let mut groups: HashMap<String, Vec<ThemeColor>> = HashMap::new();
@@ -86,6 +214,43 @@ impl Theme {
 
         groups
     }
+
+    /// Get colors grouped by category, with groups in the canonical `COLOR_GROUPS` order
+    /// Empty groups are omitted
+    pub fn grouped_colors_ordered(&self) -> Vec<(String, Vec<ThemeColor>)> {
+        let mut groups = self.get_grouped_colors();
+
+        COLOR_GROUPS
+            .iter()
+            .filter_map(|group| groups.remove_entry(*group))
+            .collect()
+    }
+
+    /// Grouped, sorted colors for the editor, straight from the in-memory theme
+    pub fn grouped_colors_view(&self) -> GroupedColors {
+        GroupedColors {
+            color_count: self.colors.len(),
+            groups: self
+                .grouped_colors_ordered()
+                .into_iter()
+                .map(|(name, colors)| ColorGroup { name, colors })
+                .collect(),
+        }
+    }
+}
+
+/// A named group of colors in the editor
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ColorGroup {
+    pub name: String,
+    pub colors: Vec<ThemeColor>,
+}
+
+/// A theme's colors as the editor shows them, groups in `COLOR_GROUPS` order
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GroupedColors {
+    pub groups: Vec<ColorGroup>,
+    pub color_count: usize,
 }
 
 impl Default for Theme {
@@ -94,8 +259,35 @@ impl Default for Theme {
     }
 }
 
+/// All color groups produced by `infer_color_group`
+pub const COLOR_GROUPS: &[&str] = &[
+    "Background",
+    "Text",
+    "Accent",
+    "Controls",
+    "Border",
+    "Tracks",
+    "Selection",
+    "Other",
+];
+
+/// Check whether a group name is one of the known color groups
+pub fn is_known_color_group(group: &str) -> bool {
+    COLOR_GROUPS.contains(&group)
+}
+
+/// Get the colors of a theme that belong to any of the given groups
+pub fn filter_colors_by_groups(theme: &Theme, groups: &[String]) -> HashMap<String, String> {
+    theme
+        .colors
+        .iter()
+        .filter(|(key, _)| groups.contains(&infer_color_group(key)))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
 /// Infer the color group from a property key
-fn infer_color_group(key: &str) -> String {
+pub fn infer_color_group(key: &str) -> String {
     let key_lower = key.to_lowercase();
 
     if key_lower.contains("background") || key_lower.contains("bg") {
@@ -144,40 +336,20 @@ pub fn parse_theme_content(content: &str, path: Option<PathBuf>) -> Result<Theme
 
 /// Parse JSON format theme (with "window" and "advanced" sections)
 fn parse_json_theme(content: &str, path: Option<PathBuf>) -> Result<Theme, ThemeError> {
+    check_json_size(content)?;
     let json: Value = serde_json::from_str(content).map_err(|e| {
         ThemeError::InvalidFormat(format!("Invalid JSON: {}", e))
     })?;
 
     let mut theme = Theme::new();
     theme.path = path;
+    theme.extras = unknown_json_sections(&json);
 
     if let Value::Object(map) = &json {
-        // Handle "window" section
-        if let Some(Value::Object(window)) = map.get("window") {
-            for (key, value) in window {
-                if let Value::String(color_value) = value {
-                    theme.colors.insert(key.clone(), color_value.clone());
-                }
-            }
-        }
-
-        // Handle "advanced" section
-        if let Some(Value::Object(advanced)) = map.get("advanced") {
-            for (key, value) in advanced {
-                if let Value::String(color_value) = value {
-                    theme.colors.insert(key.clone(), color_value.clone());
-                }
-            }
-        }
-
-        // Handle "arranger" section (used in some older themes)
-        if let Some(Value::Object(arranger)) = map.get("arranger") {
-            for (key, value) in arranger {
-                if let Value::String(color_value) = value {
-                    theme.colors.insert(key.clone(), color_value.clone());
-                }
-            }
-        }
+        let (colors, conflicts) = section_colors(map);
+        theme.colors.extend(colors);
+        theme.conflicts = conflicts;
+        theme.color_sections = color_origins(map);
 
         // If no sections found, try parsing as flat key-value object
         if theme.colors.is_empty() {
@@ -191,9 +363,57 @@ fn parse_json_theme(content: &str, path: Option<PathBuf>) -> Result<Theme, Theme
         }
     }
 
+    check_key_count(theme.colors.len())?;
     Ok(theme)
 }
 
+/// Read colors from the `window`, `advanced` and `arranger` (older themes) sections
+/// A key in several sections takes its value from the last one, so `arranger` wins
+/// over `advanced`, which wins over `window`. Keys whose values differ between
+/// sections are returned as conflicts, sorted by key
+fn section_colors(
+    map: &serde_json::Map<String, Value>,
+) -> (Vec<(String, String)>, Vec<KeyConflict>) {
+    let mut candidates: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for section in JSON_COLOR_SECTIONS {
+        let Some(Value::Object(colors)) = map.get(section) else {
+            continue;
+        };
+        for (key, value) in colors {
+            let Value::String(color_value) = value else {
+                continue;
+            };
+            let slot = *index.entry(key.clone()).or_insert_with(|| {
+                candidates.push((key.clone(), Vec::new()));
+                candidates.len() - 1
+            });
+            candidates[slot].1.push((section.to_string(), color_value.clone()));
+        }
+    }
+
+    let mut conflicts: Vec<KeyConflict> = candidates
+        .iter()
+        .filter(|(_, values)| {
+            values
+                .iter()
+                .any(|(_, v)| normalize_color_value(v) != normalize_color_value(&values[0].1))
+        })
+        .map(|(key, values)| KeyConflict {
+            key: key.clone(),
+            candidates: values.clone(),
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let colors = candidates
+        .into_iter()
+        .filter_map(|(key, mut values)| values.pop().map(|(_, value)| (key, value)))
+        .collect();
+    (colors, conflicts)
+}
+
 /// Parse legacy text format theme
 fn parse_text_theme(content: &str, path: Option<PathBuf>) -> Result<Theme, ThemeError> {
     let mut theme = Theme::new();
@@ -225,76 +445,212 @@ fn parse_text_theme(content: &str, path: Option<PathBuf>) -> Result<Theme, Theme
                 theme.metadata.description = Some(desc.trim().to_string());
             } else if let Some(version) = comment.strip_prefix("Version:") {
                 theme.metadata.version = Some(version.trim().to_string());
+            } else if let Some(extras) = comment.strip_prefix("Extras:") {
+                theme.extras = serde_json::from_str(extras.trim()).ok();
             }
             continue;
         }
 
-        // Parse color definitions
-        // Handle both formats:
-        // - key=value (legacy format)
-        // - Key: #value // optional comment (Bitwig Theme Editor format)
-        let (key, raw_value) = if let Some((k, v)) = line.split_once(": ") {
-            (k, v)
-        } else if let Some((k, v)) = line.split_once('=') {
-            (k, v)
-        } else {
+        let Some((key, value)) = split_color_line(line) else {
             continue;
         };
 
-        let key = key.trim().to_string();
-        // Remove any trailing comment (after //)
-        let value = raw_value
-            .split("//")
-            .next()
-            .unwrap_or(raw_value)
-            .trim()
-            .to_string();
-
         // Validate color format (should be hex color with 6 or 8 hex chars)
         if value.starts_with('#') && (value.len() == 7 || value.len() == 9) {
             theme.colors.insert(key, value);
+            check_key_count(theme.colors.len())?;
         }
     }
 
     Ok(theme)
 }
 
-/// Convert JSON theme content to BTE text format
-/// Outputs the text format expected by patched Bitwig (key: value pairs)
-pub fn convert_json_to_bte(json_content: &str, theme_name: Option<&str>) -> Result<String, ThemeError> {
-    let json: Value = serde_json::from_str(json_content).map_err(|e| {
-        ThemeError::InvalidFormat(format!("Invalid JSON: {}", e))
-    })?;
+/// Split a trimmed, non-comment theme line into its key and value
+/// Handles these formats:
+/// - key=value (legacy format)
+/// - Key: #value // optional comment (Bitwig Theme Editor format)
+/// - Key #value (space-delimited exports)
+fn split_color_line(line: &str) -> Option<(String, String)> {
+    let (key, raw_value) = line
+        .split_once(": ")
+        .or_else(|| line.split_once('='))
+        .or_else(|| split_space_delimited(line))?;
+
+    // Remove any trailing comment (after //)
+    let value = raw_value.split("//").next().unwrap_or(raw_value).trim();
+    Some((key.trim().to_string(), value.to_string()))
+}
 
-    let mut colors: Vec<(String, String)> = Vec::new();
+/// Canonical form of a color value: lowercase `#rrggbb`/`#rrggbbaa`, with
+/// `#rgb`/`#rgba` shorthand expanded. `None` if the value isn't a hex color
+pub fn canonical_color_value(value: &str) -> Option<String> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+    Some(format!("#{}", hex.to_ascii_lowercase()))
+}
 
-    if let Value::Object(map) = &json {
-        // Handle "window" section
-        if let Some(Value::Object(window)) = map.get("window") {
-            for (key, value) in window {
-                if let Value::String(color_value) = value {
-                    colors.push((key.clone(), color_value.clone()));
+/// A theme's text after applying the normalization rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedTheme {
+    pub content: String,
+    /// Colors whose value was rewritten (case, shorthand or whitespace)
+    pub changed_colors: usize,
+    /// Keys dropped because their value isn't a hex color
+    pub dropped_keys: Vec<String>,
+}
+
+/// Normalize BTE text: canonical color values, invalid colors dropped, keys sorted
+/// An embedded checksum is kept and recomputed
+pub fn normalize_theme_text(content: &str) -> Result<NormalizedTheme, ThemeError> {
+    let mut theme = parse_text_theme(content, None)?;
+    let mut changed_colors = 0;
+    let mut dropped_keys = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let Some((key, value)) = split_color_line(line) else {
+            continue;
+        };
+        match canonical_color_value(&value) {
+            Some(canonical) => {
+                if canonical != value {
+                    changed_colors += 1;
                 }
+                theme.colors.insert(key, canonical);
+            }
+            None => {
+                theme.colors.remove(&key);
+                dropped_keys.push(key);
             }
         }
+    }
 
-        // Handle "advanced" section
-        if let Some(Value::Object(advanced)) = map.get("advanced") {
-            for (key, value) in advanced {
-                if let Value::String(color_value) = value {
-                    colors.push((key.clone(), color_value.clone()));
+    Ok(NormalizedTheme {
+        content: serialize_theme_with(&theme, embedded_checksum(content).is_some()),
+        changed_colors,
+        dropped_keys,
+    })
+}
+
+/// Outcome of normalizing one library theme
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NormalizeResult {
+    pub path: PathBuf,
+    pub changed_colors: usize,
+    pub dropped_keys: Vec<String>,
+    /// False when the file was already canonical, or was skipped
+    pub rewritten: bool,
+    pub error: Option<String>,
+}
+
+/// Write through a temporary file in the same directory, so a crash never
+/// leaves a half-written file
+pub(crate) fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&temp_path, content)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    crate::user::hand_back(path);
+    Ok(())
+}
+
+/// Normalize theme files in place, leaving canonical files untouched
+/// JSON-format files and locked active themes are skipped
+pub fn normalize_theme_files(paths: &[PathBuf]) -> Vec<NormalizeResult> {
+    paths
+        .iter()
+        .map(|path| {
+            let mut result = NormalizeResult {
+                path: path.clone(),
+                changed_colors: 0,
+                dropped_keys: Vec::new(),
+                rewritten: false,
+                error: None,
+            };
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    result.error = Some(e.to_string());
+                    return result;
                 }
+            };
+            if is_json_content(&content) || is_active_theme_locked(path) {
+                return result;
             }
-        }
 
-        // Handle "arranger" section
-        if let Some(Value::Object(arranger)) = map.get("arranger") {
-            for (key, value) in arranger {
-                if let Value::String(color_value) = value {
-                    colors.push((key.clone(), color_value.clone()));
+            match normalize_theme_text(&content) {
+                Ok(normalized) => {
+                    result.changed_colors = normalized.changed_colors;
+                    result.dropped_keys = normalized.dropped_keys;
+                    if normalized.content != content {
+                        match write_atomic(path, &normalized.content) {
+                            Ok(()) => result.rewritten = true,
+                            Err(e) => result.error = Some(e.to_string()),
+                        }
+                    }
                 }
+                Err(e) => result.error = Some(e.to_string()),
             }
-        }
+            result
+        })
+        .collect()
+}
+
+/// Lowercase hex color values so converted and serialized output is stable across round-trips
+/// Anything that isn't a hex color is passed through trimmed but otherwise untouched
+pub fn normalize_color_value(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('#') {
+        Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => value.to_ascii_lowercase(),
+        _ => value.to_string(),
+    }
+}
+
+/// Split a `Key Name #value` line on its last whitespace
+/// Keys may contain spaces, so this only applies to lines without `:` or `=` whose
+/// last token is a hex color
+fn split_space_delimited(line: &str) -> Option<(&str, &str)> {
+    let body = line.split("//").next().unwrap_or(line).trim();
+    if body.contains(':') || body.contains('=') {
+        return None;
+    }
+
+    let (key, value) = body.rsplit_once(char::is_whitespace)?;
+    let hex = value.strip_prefix('#')?;
+    let is_color = (hex.len() == 6 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_color || key.trim().is_empty() {
+        return None;
+    }
+    Some((key.trim_end(), value))
+}
+
+/// Convert JSON theme content to BTE text format
+/// Outputs the text format expected by patched Bitwig (key: value pairs)
+pub fn convert_json_to_bte(json_content: &str, theme_name: Option<&str>) -> Result<String, ThemeError> {
+    check_json_size(json_content)?;
+    let json: Value = serde_json::from_str(json_content).map_err(|e| {
+        ThemeError::InvalidFormat(format!("Invalid JSON: {}", e))
+    })?;
+
+    let mut colors: Vec<(String, String)> = Vec::new();
+
+    if let Value::Object(map) = &json {
+        colors = section_colors(map).0;
 
         // If no sections found, treat as flat format
         if colors.is_empty() {
@@ -308,6 +664,8 @@ pub fn convert_json_to_bte(json_content: &str, theme_name: Option<&str>) -> Resu
         }
     }
 
+    check_key_count(colors.len())?;
+
     // Validate that we found some colors
     if colors.is_empty() {
         return Err(ThemeError::InvalidFormat(
@@ -324,10 +682,13 @@ pub fn convert_json_to_bte(json_content: &str, theme_name: Option<&str>) -> Resu
     if let Some(name) = theme_name {
         output.push_str(&format!("// Theme: {}\n", name));
     }
+    if let Some(extras) = unknown_json_sections(&json) {
+        output.push_str(&format!("// Extras: {}\n", extras));
+    }
     output.push('\n');
 
     for (key, value) in colors {
-        output.push_str(&format!("{}: {}\n", key, value));
+        output.push_str(&format!("{}: {}\n", key, normalize_color_value(&value)));
     }
 
     Ok(output)
@@ -339,6 +700,42 @@ pub fn is_json_content(content: &str) -> bool {
     trimmed.starts_with('{') && trimmed.ends_with('}')
 }
 
+/// On-disk theme file formats
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ThemeFormat {
+    /// Text `key: value` format read by patched Bitwig
+    Bte,
+    /// Sectioned JSON format
+    Json,
+}
+
+impl ThemeFormat {
+    /// Detect the format of theme content
+    pub fn detect(content: &str) -> Self {
+        if is_json_content(content) {
+            ThemeFormat::Json
+        } else {
+            ThemeFormat::Bte
+        }
+    }
+}
+
+/// Convert theme content to the target format
+/// Content already in the target format is returned unchanged
+pub fn convert_theme_content(
+    content: &str,
+    target: ThemeFormat,
+    theme_name: Option<&str>,
+) -> Result<String, ThemeError> {
+    match (ThemeFormat::detect(content), target) {
+        (ThemeFormat::Json, ThemeFormat::Bte) => convert_json_to_bte(content, theme_name),
+        (ThemeFormat::Bte, ThemeFormat::Json) => {
+            convert_bte_to_json(&parse_theme_content(content, None)?)
+        }
+        _ => Ok(content.to_string()),
+    }
+}
+
 /// Parse theme content, auto-detecting format (BTE or JSON)
 pub fn parse_theme_auto(content: &str, path: Option<PathBuf>, theme_name: Option<&str>) -> Result<Theme, ThemeError> {
     if is_json_content(content) {
@@ -349,9 +746,44 @@ pub fn parse_theme_auto(content: &str, path: Option<PathBuf>, theme_name: Option
     }
 }
 
+/// Parse theme text pasted by the user, e.g. copied from a forum post
+/// BTE and JSON are both accepted; the text must contain at least one valid hex color
+pub fn parse_pasted_theme(content: &str, name: &str) -> Result<Theme, ThemeError> {
+    let mut theme = parse_theme_auto(content, None, Some(name))?;
+    if !theme.colors.values().any(|value| super::parse_hex(value).is_some()) {
+        return Err(ThemeError::InvalidFormat(
+            "Pasted text contains no valid theme colors".to_string(),
+        ));
+    }
+    if theme.metadata.name.is_none() {
+        theme.metadata.name = Some(name.to_string());
+    }
+    Ok(theme)
+}
+
 /// Serialize a theme to .bte text format
 /// Outputs the text format expected by patched Bitwig (key: value pairs)
 pub fn serialize_theme(theme: &Theme) -> String {
+    serialize_theme_with(theme, false)
+}
+
+/// Comment prefix of the embedded color checksum
+const CHECKSUM_PREFIX: &str = "Checksum:";
+
+/// SHA256 over a theme's colors, independent of key order and hex case
+pub fn colors_checksum(colors: &HashMap<String, String>) -> String {
+    let mut entries: Vec<(&String, &String)> = colors.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut hasher = Sha256::new();
+    for (key, value) in entries {
+        hasher.update(format!("{}: {}\n", key, normalize_color_value(value)).as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Serialize a theme, optionally embedding a `// Checksum:` comment over its colors
+pub fn serialize_theme_with(theme: &Theme, embed_checksum: bool) -> String {
     let mut output = String::new();
 
     // Add metadata comments
@@ -367,6 +799,16 @@ pub fn serialize_theme(theme: &Theme) -> String {
     if let Some(version) = &theme.metadata.version {
         output.push_str(&format!("// Version: {}\n", version));
     }
+    if let Some(extras) = &theme.extras {
+        output.push_str(&format!("// Extras: {}\n", extras));
+    }
+    if embed_checksum {
+        output.push_str(&format!(
+            "// {} {}\n",
+            CHECKSUM_PREFIX,
+            colors_checksum(&theme.colors)
+        ));
+    }
 
     if !output.is_empty() {
         output.push('\n');
@@ -378,65 +820,549 @@ pub fn serialize_theme(theme: &Theme) -> String {
 
     // Output color definitions
     for (key, value) in colors {
-        output.push_str(&format!("{}: {}\n", key, value));
+        output.push_str(&format!("{}: {}\n", key, normalize_color_value(value)));
     }
 
     output
 }
 
+/// Sections of a JSON theme that are read as colors
+const JSON_COLOR_SECTIONS: [&str; 3] = ["window", "advanced", "arranger"];
+
+/// Collect the parts of a JSON theme the app doesn't model as colors
+fn unknown_json_sections(json: &Value) -> Option<Value> {
+    let map = json.as_object()?;
+    let has_sections = JSON_COLOR_SECTIONS.iter().any(|s| map.contains_key(*s));
+
+    let unknown: serde_json::Map<String, Value> = map
+        .iter()
+        .filter(|(key, value)| {
+            if has_sections {
+                !JSON_COLOR_SECTIONS.contains(&key.as_str())
+            } else {
+                // Flat themes store colors at the top level
+                !value.as_str().is_some_and(|v| v.starts_with('#'))
+            }
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    if unknown.is_empty() {
+        None
+    } else {
+        Some(Value::Object(unknown))
+    }
+}
+
+/// The first JSON color section each key appears in, for keys that aren't in `window`
+fn color_origins(map: &serde_json::Map<String, Value>) -> HashMap<String, String> {
+    let mut origins: HashMap<String, String> = HashMap::new();
+    for section in JSON_COLOR_SECTIONS {
+        if let Some(Value::Object(colors)) = map.get(section) {
+            for key in colors.keys() {
+                origins.entry(key.clone()).or_insert_with(|| section.to_string());
+            }
+        }
+    }
+    origins.retain(|_, section| section != "window");
+    origins
+}
+
+/// Convert a theme to the sectioned JSON format
+/// Colors go back into the section they were read from (new colors go into `window`)
+/// and sections the app doesn't model are re-emitted from the theme's extras
+pub fn convert_bte_to_json(theme: &Theme) -> Result<String, ThemeError> {
+    let mut root = match &theme.extras {
+        Some(Value::Object(map)) => map.clone(),
+        _ => serde_json::Map::new(),
+    };
+
+    let mut sections: HashMap<&str, serde_json::Map<String, Value>> = HashMap::new();
+    let mut colors: Vec<(&String, &String)> = theme.colors.iter().collect();
+    colors.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in colors {
+        let section = theme
+            .color_sections
+            .get(key)
+            .and_then(|s| JSON_COLOR_SECTIONS.iter().copied().find(|known| *known == s.as_str()))
+            .unwrap_or("window");
+        sections
+            .entry(section)
+            .or_default()
+            .insert(key.clone(), Value::String(normalize_color_value(value)));
+    }
+
+    for section in JSON_COLOR_SECTIONS {
+        match sections.remove(section) {
+            Some(colors) => {
+                root.insert(section.to_string(), Value::Object(colors));
+            }
+            None => {
+                root.remove(section);
+            }
+        }
+    }
+
+    serde_json::to_string_pretty(&Value::Object(root))
+        .map_err(|e| ThemeError::InvalidFormat(e.to_string()))
+}
+
 /// Save a theme to a file
 pub fn save_theme(theme: &Theme, path: &Path) -> Result<(), ThemeError> {
-    let content = serialize_theme(theme);
+    save_theme_with(theme, path, false)
+}
+
+/// Save a theme, optionally embedding a checksum of its colors
+pub fn save_theme_with(theme: &Theme, path: &Path, embed_checksum: bool) -> Result<(), ThemeError> {
+    let content = serialize_theme_with(theme, embed_checksum);
     fs::write(path, content)?;
+    crate::user::hand_back(path);
     Ok(())
 }
 
+/// Read the checksum embedded in theme text, if any
+fn embedded_checksum(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| {
+        let comment = line.trim().strip_prefix("//")?.trim();
+        comment.strip_prefix(CHECKSUM_PREFIX).map(str::trim)
+    })
+}
+
+/// Check a saved theme's colors against its embedded checksum
+/// Returns false when the colors changed since saving, or when no checksum was embedded
+pub fn verify_theme_integrity(path: &Path) -> Result<bool, ThemeError> {
+    let content = fs::read_to_string(path)?;
+    let Some(expected) = embedded_checksum(&content) else {
+        return Ok(false);
+    };
+    let theme = parse_theme_content(&content, Some(path.to_path_buf()))?;
+    Ok(colors_checksum(&theme.colors).eq_ignore_ascii_case(expected))
+}
+
+/// Environment variable that forces the theme directory
+pub const THEME_DIR_ENV: &str = "BITWIG_THEME_DIR";
+
+/// Resolve an overridden theme directory for a version
+/// The version subdirectory is appended unless `base` already names that version
+pub fn theme_directory_under(base: &Path, bitwig_version: &str) -> PathBuf {
+    if base.file_name().is_some_and(|name| name == bitwig_version) {
+        base.to_path_buf()
+    } else {
+        base.join(bitwig_version)
+    }
+}
+
+/// `custom_theme_directory` setting in effect, set when settings are loaded or saved
+static CUSTOM_THEME_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Change the directory themes are stored under; `None` restores the default
+pub fn set_custom_theme_directory(dir: Option<PathBuf>) {
+    *CUSTOM_THEME_DIR.write().unwrap_or_else(|e| e.into_inner()) = dir;
+}
+
+/// The `custom_theme_directory` setting, if one is in effect
+pub fn custom_theme_directory() -> Option<PathBuf> {
+    CUSTOM_THEME_DIR.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Bases a theme directory is resolved against, highest precedence first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeDirBases {
+    /// The `custom_theme_directory` setting
+    pub custom: Option<PathBuf>,
+    /// The `BITWIG_THEME_DIR` environment variable
+    pub env: Option<PathBuf>,
+    /// The bitwig-theme-editor directory, see `get_theme_root`
+    pub root: Option<PathBuf>,
+}
+
+impl ThemeDirBases {
+    /// The bases in effect for this process
+    pub fn current() -> Self {
+        Self {
+            custom: custom_theme_directory(),
+            env: std::env::var_os(THEME_DIR_ENV)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+            root: get_theme_root(),
+        }
+    }
+}
+
+/// Resolve the theme directory for a version against explicit bases
+pub fn theme_directory_in(bitwig_version: &str, bases: &ThemeDirBases) -> Option<PathBuf> {
+    if let Some(base) = bases.custom.as_ref().or(bases.env.as_ref()) {
+        return Some(theme_directory_under(base, bitwig_version));
+    }
+
+    let root = bases.root.as_ref()?;
+    let base = root.join("versions").join(bitwig_version);
+    let legacy = root.join(bitwig_version);
+    if legacy.exists() && !base.exists() {
+        return Some(legacy);
+    }
+    Some(base)
+}
+
 /// Get the theme directory for a specific Bitwig version
-/// This must match where bitwig-theme-editor patcher expects themes:
-/// - Linux/macOS: ~/.bitwig-theme-editor/versions/<version>/
-/// - Windows: %APPDATA%\.bitwig-theme-editor\versions\<version>\
+/// Every theme directory lookup goes through here. Precedence, highest first:
+/// 1. the `custom_theme_directory` setting
+/// 2. the `BITWIG_THEME_DIR` environment variable
+/// 3. where bitwig-theme-editor patcher expects themes:
+///    - Linux/macOS: ~/.bitwig-theme-editor/versions/<version>/
+///    - Windows: %APPDATA%\.bitwig-theme-editor\versions\<version>\
 pub fn get_theme_directory(bitwig_version: &str) -> Option<PathBuf> {
-    #[cfg(target_os = "windows")]
-    {
-        let base = dirs::data_dir()?
-            .join(".bitwig-theme-editor")
-            .join("versions")
-            .join(bitwig_version);
-        let legacy = dirs::data_dir()?
-            .join(".bitwig-theme-editor")
-            .join(bitwig_version);
-        if legacy.exists() && !base.exists() {
-            return Some(legacy);
+    theme_directory_in(bitwig_version, &ThemeDirBases::current())
+}
+
+/// Canonicalize a path that may not exist yet through its nearest existing ancestor
+fn canonicalize_lenient(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(canonical) = current.canonicalize() {
+            return Some(missing.iter().rev().fold(canonical, |acc, part| acc.join(part)));
         }
-        Some(
-            dirs::data_dir()?
-                .join(".bitwig-theme-editor")
-                .join("versions")
-                .join(bitwig_version),
-        )
+        // `..` has no file name, so a missing path can't climb back out
+        missing.push(current.file_name()?.to_os_string());
+        current = current.parent()?;
     }
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Use home directory directly, NOT config_dir
-        // This matches bitwig-theme-editor's expected path
-        let base = dirs::home_dir()?
-            .join(".bitwig-theme-editor")
-            .join("versions")
-            .join(bitwig_version);
-        let legacy = dirs::home_dir()?
-            .join(".bitwig-theme-editor")
-            .join(bitwig_version);
-        if legacy.exists() && !base.exists() {
-            return Some(legacy);
-        }
-        Some(base)
+/// Check that a theme directory override lies strictly inside one of `allowed`
+/// Returns the canonical path
+fn check_theme_directory_within(dir: &Path, allowed: &[PathBuf]) -> Result<PathBuf, ThemeError> {
+    let not_allowed = || ThemeError::DirectoryNotAllowed(dir.to_path_buf());
+    if !dir.is_absolute() {
+        return Err(not_allowed());
+    }
+    let canonical = canonicalize_lenient(dir).ok_or_else(not_allowed)?;
+    let inside = allowed
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| canonical.starts_with(&root) && canonical != root);
+    if inside {
+        Ok(canonical)
+    } else {
+        Err(not_allowed())
     }
 }
 
+/// Check that a custom theme directory is inside the user's home or app data directory
+/// Without this, a setting like `/` would let `open_path` open anything
+pub fn validate_theme_directory_override(dir: &Path) -> Result<PathBuf, ThemeError> {
+    let allowed: Vec<PathBuf> = [crate::user::home_dir(), dirs::data_dir()]
+        .into_iter()
+        .flatten()
+        .collect();
+    check_theme_directory_within(dir, &allowed)
+}
+
+/// Get the bitwig-theme-editor directory that holds the themes for every version
+pub fn get_theme_root() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = dirs::data_dir()?;
+    // Use home directory directly, NOT config_dir
+    // This matches bitwig-theme-editor's expected path
+    #[cfg(not(target_os = "windows"))]
+    let base = crate::user::home_dir()?;
+
+    Some(base.join(".bitwig-theme-editor"))
+}
+
+/// File name the patched Bitwig loads the active theme from
+pub const ACTIVE_THEME_FILE: &str = "theme.bte";
+
 /// Get the active theme file path for a Bitwig version
 pub fn get_active_theme_path(bitwig_version: &str) -> Option<PathBuf> {
-    get_theme_directory(bitwig_version).map(|dir| dir.join("theme.bte"))
+    get_theme_directory(bitwig_version).map(|dir| dir.join(ACTIVE_THEME_FILE))
+}
+
+/// Where an applied theme originally came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveThemeSource {
+    pub path: PathBuf,
+    pub content_hash: String,
+}
+
+/// Compute the SHA256 hash of theme content
+pub fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Get the sidecar file that records an active theme's source
+pub fn get_active_source_path(active_theme: &Path) -> PathBuf {
+    active_theme.with_extension("bte.source")
+}
+
+/// Contents of the active theme and its source record before an apply
+#[derive(Debug, Clone, Default)]
+pub struct ActiveThemeBackup {
+    /// None when there was no active theme yet
+    theme: Option<Vec<u8>>,
+    source: Option<Vec<u8>>,
+}
+
+/// Read the active theme and its source record so they can be put back
+pub fn backup_active_theme(active_theme: &Path) -> Result<ActiveThemeBackup, ThemeError> {
+    let read = |path: &Path| match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    };
+    Ok(ActiveThemeBackup {
+        theme: read(active_theme)?,
+        source: read(&get_active_source_path(active_theme))?,
+    })
+}
+
+/// Put back what `backup_active_theme` read; files that didn't exist are removed
+pub fn restore_active_backup(
+    active_theme: &Path,
+    backup: &ActiveThemeBackup,
+) -> Result<(), ThemeError> {
+    let restore = |path: &Path, bytes: &Option<Vec<u8>>| match bytes {
+        Some(bytes) => fs::write(path, bytes),
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    };
+    restore(active_theme, &backup.theme)?;
+    restore(&get_active_source_path(active_theme), &backup.source)?;
+    Ok(())
+}
+
+/// Get the marker file that locks an active theme against overwrites
+pub fn get_active_lock_path(active_theme: &Path) -> PathBuf {
+    active_theme.with_extension("bte.lock")
+}
+
+/// Check whether an active theme is locked
+pub fn is_active_theme_locked(active_theme: &Path) -> bool {
+    get_active_lock_path(active_theme).exists()
+}
+
+/// Lock or unlock an active theme
+pub fn set_active_theme_locked(active_theme: &Path, locked: bool) -> Result<(), ThemeError> {
+    let lock_path = get_active_lock_path(active_theme);
+    if locked {
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&lock_path, b"")?;
+        crate::user::hand_back(&lock_path);
+    } else if lock_path.exists() {
+        fs::remove_file(&lock_path)?;
+    }
+    Ok(())
+}
+
+/// Record the library file an active theme was applied from
+pub fn record_active_theme_source(active_theme: &Path, source: &Path) -> Result<(), ThemeError> {
+    let content = fs::read_to_string(source)?;
+    let record = ActiveThemeSource {
+        path: source.to_path_buf(),
+        content_hash: content_hash(&content),
+    };
+    let json = serde_json::to_string_pretty(&record)
+        .map_err(|e| ThemeError::InvalidFormat(e.to_string()))?;
+    fs::write(get_active_source_path(active_theme), json)?;
+    Ok(())
+}
+
+/// Read the recorded source of an active theme
+/// Returns None when nothing was recorded or the source file no longer exists
+pub fn read_active_theme_source(active_theme: &Path) -> Option<ActiveThemeSource> {
+    read_recorded_theme_source(active_theme).filter(|record| record.path.is_file())
+}
+
+/// Read the recorded source of an active theme, even if that file was since deleted
+pub fn read_recorded_theme_source(active_theme: &Path) -> Option<ActiveThemeSource> {
+    let content = fs::read_to_string(get_active_source_path(active_theme)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Get the sidecar file recording which installation an active theme was applied for
+/// Installations of the same version share the theme directory, as it is the only one
+/// the patched Bitwig reads, so the record tells them apart
+pub fn get_active_installation_path(active_theme: &Path) -> PathBuf {
+    active_theme.with_extension("bte.installation")
+}
+
+/// Record the installation an active theme was applied for; `None` clears the record
+pub fn record_active_installation(
+    active_theme: &Path,
+    installation: Option<&Path>,
+) -> Result<(), ThemeError> {
+    let record_path = get_active_installation_path(active_theme);
+    let result = match installation {
+        Some(installation) => fs::write(&record_path, installation.to_string_lossy().as_bytes()),
+        None => match fs::remove_file(&record_path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        },
+    };
+    result.map_err(Into::into)
+}
+
+/// Read the installation an active theme was applied for, if one was recorded
+pub fn read_active_installation(active_theme: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(get_active_installation_path(active_theme)).ok()?;
+    let trimmed = content.trim();
+    (!trimmed.is_empty()).then(|| PathBuf::from(trimmed))
+}
+
+/// Number of active theme snapshots kept per theme directory
+pub const MAX_ACTIVE_SNAPSHOTS: usize = 10;
+
+/// Get the directory holding snapshots of an active theme file
+pub fn get_snapshots_dir(active_theme: &Path) -> PathBuf {
+    active_theme
+        .parent()
+        .map(|dir| dir.join("snapshots"))
+        .unwrap_or_else(|| PathBuf::from("snapshots"))
+}
+
+/// Copy the active theme to a timestamped file in the snapshots directory
+/// Returns None if there is no active theme yet; the oldest snapshots beyond
+/// `max_keep` are removed
+pub fn snapshot_active_theme(
+    active_theme: &Path,
+    max_keep: usize,
+) -> Result<Option<PathBuf>, ThemeError> {
+    if !active_theme.is_file() {
+        return Ok(None);
+    }
+
+    let snapshots_dir = get_snapshots_dir(active_theme);
+    fs::create_dir_all(&snapshots_dir)?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut snapshot = snapshots_dir.join(format!("theme-{:010}.bte", timestamp));
+    let mut counter = 1;
+    while snapshot.exists() {
+        snapshot = snapshots_dir.join(format!("theme-{:010}_{:03}.bte", timestamp, counter));
+        counter += 1;
+    }
+    fs::copy(active_theme, &snapshot)?;
+
+    for old in list_active_snapshots(active_theme)?.into_iter().skip(max_keep) {
+        fs::remove_file(old)?;
+    }
+
+    Ok(Some(snapshot))
+}
+
+/// List snapshots of an active theme, newest first
+pub fn list_active_snapshots(active_theme: &Path) -> Result<Vec<PathBuf>, ThemeError> {
+    let snapshots_dir = get_snapshots_dir(active_theme);
+    if !snapshots_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&snapshots_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "bte") {
+            snapshots.push(path);
+        }
+    }
+
+    // Names embed a zero-padded timestamp, so name order is age order
+    snapshots.sort();
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
+/// Copy a snapshot back over the active theme
+/// Only files inside the active theme's snapshots directory are accepted. With
+/// `keep_current`, the theme being replaced is snapshotted first, keeping at most that many
+/// snapshots; the one being restored is read beforehand so pruning can't remove it
+/// Returns the snapshot of the replaced theme, if one was made
+pub fn restore_active_snapshot(
+    active_theme: &Path,
+    snapshot: &Path,
+    keep_current: Option<usize>,
+) -> Result<Option<PathBuf>, ThemeError> {
+    if !list_active_snapshots(active_theme)?.iter().any(|s| s == snapshot) {
+        return Err(ThemeError::NotFound(snapshot.to_path_buf()));
+    }
+    let content = fs::read(snapshot)?;
+    let replaced = match keep_current {
+        Some(max_keep) => snapshot_active_theme(active_theme, max_keep)?,
+        None => None,
+    };
+    fs::write(active_theme, content)?;
+    Ok(replaced)
+}
+
+/// Longest path the Win32 file APIs accept without the `\\?\` prefix
+pub const WINDOWS_MAX_PATH: usize = 260;
+
+/// Path length limit for theme files on the current platform
+fn max_theme_path_len() -> usize {
+    if cfg!(target_os = "windows") {
+        WINDOWS_MAX_PATH
+    } else {
+        4096
+    }
+}
+
+/// A destination for a new theme file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeFilePath {
+    pub path: PathBuf,
+    /// Whether the file name was shortened to fit the path length limit
+    pub truncated: bool,
+}
+
+/// Pick a theme file path in `dir` for an already sanitized name that doesn't clash with
+/// existing files, appending `_1`, `_2`, ... as needed
+/// Long names are shortened (keeping a hash of the full name) so the path stays within
+/// the platform's path length limit
+pub fn unique_theme_file_path(dir: &Path, safe_name: &str) -> ThemeFilePath {
+    unique_theme_file_path_within(dir, safe_name, max_theme_path_len())
+}
+
+fn unique_theme_file_path_within(dir: &Path, safe_name: &str, max_len: usize) -> ThemeFilePath {
+    let mut counter = 0;
+    loop {
+        let suffix = if counter == 0 {
+            ".bte".to_string()
+        } else {
+            format!("_{}.bte", counter)
+        };
+        let (stem, truncated) = fit_file_stem(dir, safe_name, &suffix, max_len);
+        let path = dir.join(format!("{}{}", stem, suffix));
+        if !path.exists() {
+            return ThemeFilePath { path, truncated };
+        }
+        counter += 1;
+    }
+}
+
+/// Shorten `stem` so that `dir/<stem><suffix>` fits in `max_len` characters
+fn fit_file_stem(dir: &Path, stem: &str, suffix: &str, max_len: usize) -> (String, bool) {
+    // Separator plus the trailing NUL the Windows limit includes
+    let fixed = dir.to_string_lossy().chars().count() + 1 + suffix.chars().count() + 1;
+    let available = max_len.saturating_sub(fixed);
+
+    if stem.chars().count() <= available {
+        return (stem.to_string(), false);
+    }
+
+    let hash = &content_hash(stem)[..8];
+    let keep = available.saturating_sub(hash.len() + 1);
+    if keep == 0 {
+        return (hash.to_string(), true);
+    }
+
+    let prefix: String = stem.chars().take(keep).collect();
+    (format!("{}-{}", prefix, hash), true)
 }
 
 /// List all theme files in the theme directory
@@ -489,10 +1415,10 @@ text.primary=#ffffff
 
     #[test]
     fn test_serialize_theme() {
-        let mut theme = Theme::with_name("Test Theme");
-        theme.metadata.author = Some("test_user".to_string());
-        theme.colors.insert("background.main".to_string(), "#1a1a2e".to_string());
-        theme.colors.insert("accent.primary".to_string(), "#e94560".to_string());
+        let theme = Theme::with_name("Test Theme")
+            .author("test_user")
+            .color("background.main", "#1a1a2e")
+            .color("accent.primary", "#e94560");
 
         let output = serialize_theme(&theme);
 
@@ -511,6 +1437,40 @@ text.primary=#ffffff
         assert_eq!(infer_color_group("unknown.property"), "Other");
     }
 
+    #[test]
+    fn test_grouped_colors_ordered() {
+        let theme = Theme::new()
+            .color("Selection", "#ff5a00")
+            .color("Window background", "#222222")
+            .color("Default text", "#ececec")
+            .color("Accent (default)", "#ff5a00")
+            .color("Zebra stripes", "#123456");
+
+        let groups: Vec<String> = theme
+            .grouped_colors_ordered()
+            .into_iter()
+            .map(|(group, _)| group)
+            .collect();
+
+        assert_eq!(groups, vec!["Background", "Text", "Accent", "Selection", "Other"]);
+    }
+
+    #[test]
+    fn test_filter_colors_by_groups() {
+        let mut theme = Theme::new();
+        theme.colors.insert("Window background".to_string(), "#222222".to_string());
+        theme.colors.insert("Accent (default)".to_string(), "#ff5a00".to_string());
+        theme.colors.insert("Lighter Text".to_string(), "#ececec".to_string());
+
+        let filtered = filter_colors_by_groups(&theme, &["Background".to_string(), "Text".to_string()]);
+
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.contains_key("Window background"));
+        assert!(filtered.contains_key("Lighter Text"));
+        assert!(is_known_color_group("Accent"));
+        assert!(!is_known_color_group("Arranger"));
+    }
+
     #[test]
     fn test_parse_bte_colon_format() {
         let content = r#"
@@ -558,6 +1518,138 @@ Accent color: #e94560
         assert!(!bte.contains("}"));
     }
 
+    #[test]
+    fn test_active_theme_source_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("library.bte");
+        let active = dir.path().join("theme.bte");
+        fs::write(&source, "Accent: #e94560\n").unwrap();
+
+        record_active_theme_source(&active, &source).unwrap();
+        let record = read_active_theme_source(&active).unwrap();
+        assert_eq!(record.path, source);
+        assert_eq!(record.content_hash, content_hash("Accent: #e94560\n"));
+
+        fs::remove_file(&source).unwrap();
+        assert!(read_active_theme_source(&active).is_none());
+    }
+
+    #[test]
+    fn test_theme_dir_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".bitwig-theme-editor");
+        let env = dir.path().join("env");
+        let custom = dir.path().join("custom");
+        let mut bases = ThemeDirBases {
+            custom: None,
+            env: None,
+            root: Some(root.clone()),
+        };
+        assert_eq!(theme_directory_in("5.2", &bases), Some(root.join("versions").join("5.2")));
+
+        // A legacy directory is used until the versions/ one exists
+        fs::create_dir_all(root.join("5.2")).unwrap();
+        assert_eq!(theme_directory_in("5.2", &bases), Some(root.join("5.2")));
+
+        bases.env = Some(env.clone());
+        assert_eq!(theme_directory_in("5.2", &bases), Some(env.join("5.2")));
+        bases.env = Some(env.join("5.2"));
+        assert_eq!(theme_directory_in("5.2", &bases), Some(env.join("5.2")));
+
+        bases.custom = Some(custom.clone());
+        assert_eq!(theme_directory_in("5.2", &bases), Some(custom.join("5.2")));
+    }
+
+    #[test]
+    fn test_active_installation_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("theme.bte");
+        assert_eq!(read_active_installation(&active), None);
+
+        let install = Path::new("/opt/bitwig-studio/5.2");
+        record_active_installation(&active, Some(install)).unwrap();
+        assert_eq!(read_active_installation(&active), Some(install.to_path_buf()));
+
+        record_active_installation(&active, None).unwrap();
+        assert_eq!(read_active_installation(&active), None);
+        assert!(!get_active_installation_path(&active).exists());
+    }
+
+    #[test]
+    fn test_unique_theme_file_path_truncates_long_names() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("a".repeat(150));
+        fs::create_dir_all(&dir).unwrap();
+        let long_name = "Very_Long_Theme_Name_".repeat(10);
+
+        let first = unique_theme_file_path_within(&dir, &long_name, WINDOWS_MAX_PATH);
+        assert!(first.truncated);
+        assert!(first.path.to_string_lossy().chars().count() < WINDOWS_MAX_PATH);
+        assert!(first
+            .path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("Very_Long_Theme_Name_"));
+
+        fs::write(&first.path, "").unwrap();
+        let second = unique_theme_file_path_within(&dir, &long_name, WINDOWS_MAX_PATH);
+        assert_ne!(second.path, first.path);
+        assert!(second.path.to_string_lossy().ends_with("_1.bte"));
+        assert!(second.path.to_string_lossy().chars().count() < WINDOWS_MAX_PATH);
+
+        let short = unique_theme_file_path_within(&dir, "Short", WINDOWS_MAX_PATH);
+        assert_eq!(short.path, dir.join("Short.bte"));
+        assert!(!short.truncated);
+    }
+
+    #[test]
+    fn test_json_extras_survive_bte_round_trip() {
+        let json = r##"{
+            "window": { "Background": "#111111" },
+            "advanced": { "Accent": "#e94560" },
+            "extra": { "fonts": ["Inter"], "scale": 1.25 }
+        }"##;
+
+        let bte = convert_json_to_bte(json, Some("Extra Theme")).unwrap();
+        assert!(bte.contains("// Extras: "));
+
+        let theme = parse_theme_content(&bte, None).unwrap();
+        let reparsed = parse_theme_content(&serialize_theme(&theme), None).unwrap();
+        let output: Value = serde_json::from_str(&convert_bte_to_json(&reparsed).unwrap()).unwrap();
+
+        assert_eq!(output["extra"]["fonts"][0], "Inter");
+        assert_eq!(output["extra"]["scale"], 1.25);
+        assert_eq!(output["window"]["Background"], "#111111");
+        assert_eq!(output["window"]["Accent"], "#e94560");
+    }
+
+    #[test]
+    fn test_convert_bte_to_json_keeps_original_sections() {
+        let json = r##"{
+            "window": { "Background": "#111111" },
+            "advanced": { "Accent": "#e94560" },
+            "extra": true
+        }"##;
+
+        let mut theme = parse_theme_content(json, None).unwrap();
+        assert_eq!(theme.extras, Some(serde_json::json!({ "extra": true })));
+        theme.colors.insert("Accent".to_string(), "#ff0000".to_string());
+
+        let output: Value = serde_json::from_str(&convert_bte_to_json(&theme).unwrap()).unwrap();
+
+        assert_eq!(output["advanced"]["Accent"], "#ff0000");
+        assert_eq!(output["window"]["Background"], "#111111");
+        assert_eq!(output["extra"], true);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash("a: #ffffff\n"), content_hash("a: #ffffff\n"));
+        assert_ne!(content_hash("a: #ffffff\n"), content_hash("a: #000000\n"));
+        assert_eq!(content_hash("").len(), 64);
+    }
+
     #[test]
     fn test_is_json_content() {
         assert!(is_json_content(r#"{"key": "value"}"#));
@@ -565,4 +1657,327 @@ Accent color: #e94560
         assert!(!is_json_content("# Theme: Test\nkey=#ffffff"));
         assert!(!is_json_content("Background: #1a1a2e"));
     }
+
+    #[test]
+    fn test_convert_theme_content() {
+        let json = r##"{ "window": { "Background": "#111111" } }"##;
+
+        assert_eq!(ThemeFormat::detect(json), ThemeFormat::Json);
+        assert_eq!(convert_theme_content(json, ThemeFormat::Json, None).unwrap(), json);
+
+        let bte = convert_theme_content(json, ThemeFormat::Bte, Some("Dark")).unwrap();
+        assert_eq!(ThemeFormat::detect(&bte), ThemeFormat::Bte);
+        assert!(bte.contains("Background: #111111"));
+
+        let back: Value =
+            serde_json::from_str(&convert_theme_content(&bte, ThemeFormat::Json, None).unwrap())
+                .unwrap();
+        assert_eq!(back["window"]["Background"], "#111111");
+    }
+
+    #[test]
+    fn test_conversion_normalizes_hex_case() {
+        let json = r##"{ "window": { "Background": "#AABBCCDD", "Accent": "#E94560" } }"##;
+
+        let bte = convert_json_to_bte(json, Some("Loud")).unwrap();
+        assert!(bte.contains("Background: #aabbccdd\n"));
+        assert!(bte.contains("Accent: #e94560\n"));
+        assert_eq!(convert_json_to_bte(json, Some("Loud")).unwrap(), bte);
+
+        // BTE -> JSON -> BTE reproduces the same bytes
+        let theme = parse_theme_content(&bte, None).unwrap();
+        let json_again = convert_bte_to_json(&theme).unwrap();
+        assert_eq!(convert_json_to_bte(&json_again, Some("Loud")).unwrap(), bte);
+
+        let reserialized = serialize_theme(&theme);
+        let reparsed = parse_theme_content(&reserialized, None).unwrap();
+        assert_eq!(serialize_theme(&reparsed), reserialized);
+        assert_eq!(normalize_color_value(" #FFF "), "#fff");
+        assert_eq!(normalize_color_value("Red"), "Red");
+    }
+
+    #[test]
+    fn test_snapshot_active_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("theme.bte");
+
+        assert!(snapshot_active_theme(&active, 3).unwrap().is_none());
+
+        fs::write(&active, "Background: #111111\n").unwrap();
+        let snapshot = snapshot_active_theme(&active, 3).unwrap().unwrap();
+        // Applying a new theme overwrites the active file; the snapshot keeps the old one
+        fs::write(&active, "Background: #222222\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&snapshot).unwrap(), "Background: #111111\n");
+        assert_eq!(snapshot.parent(), Some(get_snapshots_dir(&active).as_path()));
+
+        for _ in 0..4 {
+            snapshot_active_theme(&active, 3).unwrap();
+        }
+        let snapshots = list_active_snapshots(&active).unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert!(!snapshots.contains(&snapshot));
+
+        restore_active_snapshot(&active, &snapshots[0], None).unwrap();
+        assert_eq!(fs::read_to_string(&active).unwrap(), "Background: #222222\n");
+        assert!(restore_active_snapshot(&active, &dir.path().join("other.bte"), None).is_err());
+
+        // Restoring the oldest snapshot at the cap keeps the replaced theme without losing it
+        fs::write(&active, "Background: #333333\n").unwrap();
+        let oldest = list_active_snapshots(&active).unwrap().pop().unwrap();
+        let replaced = restore_active_snapshot(&active, &oldest, Some(3)).unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&replaced).unwrap(), "Background: #333333\n");
+        assert_eq!(fs::read_to_string(&active).unwrap(), "Background: #222222\n");
+        assert_eq!(list_active_snapshots(&active).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_parse_space_delimited() {
+        let content = "Window background #2e2e2e\n\
+Selected Tree Item Background #ff5a00aa // accent\n\
+Panel body    #303030\n";
+        let theme = parse_theme_content(content, None).unwrap();
+
+        assert_eq!(theme.colors.len(), 3);
+        assert_eq!(theme.colors.get("Window background"), Some(&"#2e2e2e".to_string()));
+        assert_eq!(
+            theme.colors.get("Selected Tree Item Background"),
+            Some(&"#ff5a00aa".to_string())
+        );
+        assert_eq!(theme.colors.get("Panel body"), Some(&"#303030".to_string()));
+    }
+
+    #[test]
+    fn test_parse_space_delimited_ambiguous_lines() {
+        // Colon and equals lines keep their own delimiters
+        let theme = parse_theme_content("Key: #111111\nOther=#222222\n", None).unwrap();
+        assert_eq!(theme.colors.get("Key"), Some(&"#111111".to_string()));
+        assert_eq!(theme.colors.get("Other"), Some(&"#222222".to_string()));
+
+        // A colon without a following space is not split on whitespace either
+        assert_eq!(split_space_delimited("Key:x #111111"), None);
+        // The last token must be a hex color
+        assert_eq!(split_space_delimited("Window background dark"), None);
+        assert_eq!(split_space_delimited("Window background #12345"), None);
+        assert_eq!(split_space_delimited("#111111"), None);
+    }
+
+    #[test]
+    fn test_active_theme_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("5.2").join("theme.bte");
+
+        assert!(!is_active_theme_locked(&active));
+        set_active_theme_locked(&active, true).unwrap();
+        assert!(is_active_theme_locked(&active));
+        assert_eq!(get_active_lock_path(&active), dir.path().join("5.2").join("theme.bte.lock"));
+
+        set_active_theme_locked(&active, false).unwrap();
+        assert!(!is_active_theme_locked(&active));
+        set_active_theme_locked(&active, false).unwrap();
+    }
+
+    #[test]
+    fn test_parse_pasted_theme() {
+        let pasted = "  Window background: #1A1A2E\r\n  Accent: #e94560\r\n";
+        let theme = parse_pasted_theme(pasted, "Forum Dark").unwrap();
+        assert_eq!(theme.metadata.name, Some("Forum Dark".to_string()));
+        assert_eq!(theme.colors.len(), 2);
+
+        let json = r##"{"window": {"Accent": "#e94560"}}"##;
+        assert_eq!(parse_pasted_theme(json, "Fallback").unwrap().colors.len(), 1);
+
+        assert!(parse_pasted_theme("just some chat message", "Nope").is_err());
+        assert!(parse_pasted_theme("Accent: not a color", "Nope").is_err());
+    }
+
+    #[test]
+    fn test_verify_theme_integrity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checked.bte");
+        let theme = Theme::with_name("Checked")
+            .color("Window background", "#1A1A2E")
+            .color("Accent", "#e94560");
+
+        save_theme_with(&theme, &path, true).unwrap();
+        assert!(verify_theme_integrity(&path).unwrap());
+        assert_eq!(parse_theme_file(&path).unwrap().colors, theme.colors);
+
+        let tampered = fs::read_to_string(&path).unwrap().replace("#e94560", "#00ff00");
+        fs::write(&path, tampered).unwrap();
+        assert!(!verify_theme_integrity(&path).unwrap());
+
+        save_theme(&theme, &path).unwrap();
+        assert!(!verify_theme_integrity(&path).unwrap());
+    }
+
+    #[test]
+    fn test_restore_active_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let active = dir.path().join("theme.bte");
+
+        // No prior active theme: restoring removes what the apply wrote
+        let empty = backup_active_theme(&active).unwrap();
+        fs::write(&active, "Background: #222222\n").unwrap();
+        fs::write(get_active_source_path(&active), "{}").unwrap();
+        restore_active_backup(&active, &empty).unwrap();
+        assert!(!active.exists());
+        assert!(!get_active_source_path(&active).exists());
+
+        fs::write(&active, "Background: #111111\n").unwrap();
+        let backup = backup_active_theme(&active).unwrap();
+        fs::write(&active, "Background: #222222\n").unwrap();
+        restore_active_backup(&active, &backup).unwrap();
+        assert_eq!(fs::read_to_string(&active).unwrap(), "Background: #111111\n");
+    }
+
+    #[test]
+    fn test_too_many_keys() {
+        let limit = max_theme_keys();
+        let oversized: String = (0..=limit).map(|i| format!("Key {}: #000000\n", i)).collect();
+        assert!(matches!(
+            parse_theme_content(&oversized, None),
+            Err(ThemeError::TooManyKeys { limit: l }) if l == limit
+        ));
+
+        let entries: Vec<String> = (0..=limit)
+            .map(|i| format!("\"Key {}\": \"#000000\"", i))
+            .collect();
+        let json = format!("{{\"window\": {{{}}}}}", entries.join(", "));
+        assert!(matches!(
+            convert_json_to_bte(&json, None),
+            Err(ThemeError::TooManyKeys { .. })
+        ));
+        assert!(matches!(parse_theme_content(&json, None), Err(ThemeError::TooManyKeys { .. })));
+
+        let at_limit: String = (0..limit).map(|i| format!("Key {}: #000000\n", i)).collect();
+        assert_eq!(parse_theme_content(&at_limit, None).unwrap().colors.len(), limit);
+
+        // Oversized JSON is turned away by its length, whatever it holds
+        let padded = format!("{{\"window\": {{}}{}}}", " ".repeat(limit * MAX_JSON_BYTES_PER_KEY));
+        assert!(matches!(check_json_size(&padded), Err(ThemeError::TooManyKeys { .. })));
+        assert!(matches!(
+            convert_json_to_bte(&padded, None),
+            Err(ThemeError::TooManyKeys { .. })
+        ));
+    }
+
+    #[test]
+    fn test_grouped_colors_view_of_unsaved_theme() {
+        let theme = Theme::with_name("Generated")
+            .color("Window background", "#111111")
+            .color("Accent", "#e94560")
+            .color("Panel background", "#222222")
+            .color("Label text", "#eeeeee");
+        assert!(theme.path.is_none());
+
+        let view = theme.grouped_colors_view();
+        assert_eq!(view.color_count, 4);
+
+        let names: Vec<&str> = view.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Background", "Text", "Accent"]);
+
+        let backgrounds: Vec<&str> =
+            view.groups[0].colors.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(backgrounds, vec!["Panel background", "Window background"]);
+    }
+
+    #[test]
+    fn test_normalize_theme_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let messy = dir.path().join("Messy.bte");
+        let clean = dir.path().join("Clean.bte");
+        fs::write(
+            &messy,
+            "// Theme: Messy\nText: #FFF\nBackground: #1A1A2E\nBorder: red\nAccent: #e94560\n",
+        )
+        .unwrap();
+        let clean_content = "// Theme: Clean\n\nAccent: #e94560\nBackground: #111111\n";
+        fs::write(&clean, clean_content).unwrap();
+        let clean_modified = fs::metadata(&clean).unwrap().modified().unwrap();
+
+        let results = normalize_theme_files(&[messy.clone(), clean.clone()]);
+
+        assert_eq!(results[0].changed_colors, 2);
+        assert_eq!(results[0].dropped_keys, vec!["Border".to_string()]);
+        assert!(results[0].rewritten);
+        assert_eq!(
+            fs::read_to_string(&messy).unwrap(),
+            "// Theme: Messy\n\nAccent: #e94560\nBackground: #1a1a2e\nText: #ffffff\n"
+        );
+
+        assert_eq!(results[1].changed_colors, 0);
+        assert!(results[1].dropped_keys.is_empty());
+        assert!(!results[1].rewritten);
+        assert_eq!(fs::read_to_string(&clean).unwrap(), clean_content);
+        assert_eq!(fs::metadata(&clean).unwrap().modified().unwrap(), clean_modified);
+
+        assert_eq!(canonical_color_value("#AbCd"), Some("#aabbccdd".to_string()));
+        assert_eq!(canonical_color_value("#12345"), None);
+    }
+
+    #[test]
+    fn test_json_section_conflicts() {
+        let json = r##"{
+            "window": {"Background": "#111111", "Accent": "#E94560", "Text": "#eeeeee"},
+            "advanced": {"Background": "#222222", "Accent": "#e94560"}
+        }"##;
+
+        let theme = parse_theme_content(json, None).unwrap();
+        assert_eq!(theme.colors["Background"], "#222222");
+        assert_eq!(theme.colors["Text"], "#eeeeee");
+
+        // Same color in different case isn't a conflict
+        assert_eq!(
+            theme.conflicts,
+            vec![KeyConflict {
+                key: "Background".to_string(),
+                candidates: vec![
+                    ("window".to_string(), "#111111".to_string()),
+                    ("advanced".to_string(), "#222222".to_string()),
+                ],
+            }]
+        );
+
+        // Conversion emits each key once, with the same winner
+        let bte = convert_json_to_bte(json, None).unwrap();
+        assert_eq!(bte.matches("Background:").count(), 1);
+        assert!(bte.contains("Background: #222222"));
+    }
+
+    #[test]
+    fn test_theme_directory_override_must_be_inside_allowed_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        fs::create_dir_all(home.join("themes")).unwrap();
+        let allowed = vec![home.clone()];
+        let canonical_home = home.canonicalize().unwrap();
+
+        assert_eq!(
+            check_theme_directory_within(&home.join("themes"), &allowed).unwrap(),
+            canonical_home.join("themes")
+        );
+        // Directories that don't exist yet resolve through their parent
+        assert_eq!(
+            check_theme_directory_within(&home.join("new").join("dir"), &allowed).unwrap(),
+            canonical_home.join("new").join("dir")
+        );
+
+        for rejected in [
+            home.clone(),
+            dir.path().to_path_buf(),
+            home.join("..").join("elsewhere"),
+            home.join("missing").join("..").join(".."),
+            PathBuf::from("relative/themes"),
+        ] {
+            assert!(
+                matches!(
+                    check_theme_directory_within(&rejected, &allowed),
+                    Err(ThemeError::DirectoryNotAllowed(_))
+                ),
+                "{} should be rejected",
+                rejected.display()
+            );
+        }
+    }
 }
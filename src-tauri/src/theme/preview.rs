@@ -0,0 +1,107 @@
+use regex::Regex;
+use resvg::{tiny_skia, usvg};
+
+use super::{parse_hex, Theme, ThemeError};
+
+/// Simplified arranger mockup with `{{Theme Key|#fallback}}` fill placeholders
+const PREVIEW_TEMPLATE: &str = include_str!("../../templates/arranger-preview.svg");
+
+/// The preview template with a theme's colors substituted in
+#[derive(Debug, Clone)]
+pub struct PreviewSvg {
+    pub svg: String,
+    /// Template keys the theme doesn't set (or sets to an invalid color), drawn with the fallback
+    pub missing: Vec<String>,
+}
+
+/// Format a theme hex value as an SVG paint, using `rgba()` when it carries alpha
+fn svg_color(value: &str) -> Option<String> {
+    let color = parse_hex(value)?;
+    Some(match color.a {
+        Some(a) => format!(
+            "rgba({},{},{},{:.3})",
+            color.r,
+            color.g,
+            color.b,
+            a as f64 / 255.0
+        ),
+        None => color.to_hex(),
+    })
+}
+
+/// Replace each placeholder in the template with the theme's color for that key
+pub fn fill_preview_template(template: &str, theme: &Theme) -> PreviewSvg {
+    let placeholder = Regex::new(r"\{\{([^|}]+)\|([^}]+)\}\}").unwrap();
+    let mut missing: Vec<String> = Vec::new();
+
+    let svg = placeholder
+        .replace_all(template, |caps: &regex::Captures| {
+            let key = caps[1].trim();
+            let fallback = &caps[2];
+
+            if let Some(color) = theme.colors.get(key).and_then(|v| svg_color(v)) {
+                return color;
+            }
+            if !missing.iter().any(|k| k == key) {
+                missing.push(key.to_string());
+            }
+            svg_color(fallback).unwrap_or_else(|| "none".to_string())
+        })
+        .into_owned();
+
+    PreviewSvg { svg, missing }
+}
+
+/// Render the theme over the bundled arranger mockup and return PNG bytes
+pub fn render_theme_preview(theme: &Theme) -> Result<Vec<u8>, ThemeError> {
+    let filled = fill_preview_template(PREVIEW_TEMPLATE, theme);
+
+    let tree = usvg::Tree::from_str(&filled.svg, &usvg::Options::default())
+        .map_err(|e| ThemeError::Render(e.to_string()))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| ThemeError::Render("invalid preview size".to_string()))?;
+
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap
+        .encode_png()
+        .map_err(|e| ThemeError::Render(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_preview_template() {
+        let template = r#"<rect fill="{{Window background|#2e2e2e}}"/>
+<rect fill="{{Panel body|#303030}}"/>
+<rect fill="{{Shadow|#0000004d}}"/>
+<rect fill="{{Window background|#2e2e2e}}"/>"#;
+        let theme = Theme::with_name("Test")
+            .color("Window background", "#1A1A2E")
+            .color("Panel body", "not a color")
+            .color("Unused Key", "#ffffff");
+
+        let filled = fill_preview_template(template, &theme);
+
+        assert_eq!(
+            filled.svg,
+            r#"<rect fill="#1a1a2e"/>
+<rect fill="#303030"/>
+<rect fill="rgba(0,0,0,0.302)"/>
+<rect fill="#1a1a2e"/>"#
+        );
+        assert_eq!(filled.missing, vec!["Panel body", "Shadow"]);
+    }
+
+    #[test]
+    fn test_render_theme_preview_png() {
+        let theme = Theme::with_name("Test").color("Window background", "#1a1a2e");
+        let png = render_theme_preview(&theme).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(!fill_preview_template(PREVIEW_TEMPLATE, &theme).svg.contains("{{"));
+    }
+}
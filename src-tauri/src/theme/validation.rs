@@ -0,0 +1,302 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use super::{parse_hex, parse_theme_file, theme_key_schema, Theme};
+
+/// Upper bound on threads used to check a library
+const MAX_VALIDATION_WORKERS: usize = 8;
+
+/// Problems found when checking a theme for a Bitwig version
+/// The key lists come from the community reference palette, which isn't Bitwig's own key
+/// table, so they're hints for the user and don't make a theme invalid
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct ThemeValidation {
+    /// Keys the reference palette sets that the theme doesn't
+    pub missing_keys: Vec<String>,
+    /// Keys the theme sets that the reference palette doesn't
+    pub unknown_keys: Vec<String>,
+    /// Keys whose value isn't a valid `#rrggbb` or `#rrggbbaa` color
+    pub invalid_values: Vec<String>,
+}
+
+impl ThemeValidation {
+    /// Whether every value is a color Bitwig can read
+    pub fn is_valid(&self) -> bool {
+        self.invalid_values.is_empty()
+    }
+}
+
+/// Outcome of checking one theme file
+#[derive(Debug, Clone, Serialize)]
+pub enum ThemeCheck {
+    Checked(ThemeValidation),
+    /// The file couldn't be read or parsed
+    ParseError(String),
+}
+
+/// Validation result for a theme file in the library
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeValidationReport {
+    pub path: String,
+    pub result: ThemeCheck,
+}
+
+/// Check a theme's values, and compare its keys with a Bitwig version's reference palette
+/// Returns None if no reference palette is bundled for the version
+pub fn validate_theme(theme: &Theme, bitwig_version: &str) -> Option<ThemeValidation> {
+    let schema = theme_key_schema(bitwig_version)?;
+    let known: HashSet<&str> = schema.iter().map(|k| k.key.as_str()).collect();
+
+    let missing_keys = schema
+        .iter()
+        .filter(|k| !theme.colors.contains_key(&k.key))
+        .map(|k| k.key.clone())
+        .collect();
+
+    let mut unknown_keys: Vec<String> = theme
+        .colors
+        .keys()
+        .filter(|key| !known.contains(key.as_str()))
+        .cloned()
+        .collect();
+    unknown_keys.sort();
+
+    let mut invalid_values: Vec<String> = theme
+        .colors
+        .iter()
+        .filter(|(_, value)| parse_hex(value).is_none())
+        .map(|(key, _)| key.clone())
+        .collect();
+    invalid_values.sort();
+
+    Some(ThemeValidation {
+        missing_keys,
+        unknown_keys,
+        invalid_values,
+    })
+}
+
+fn check_theme_file(path: &Path, bitwig_version: &str) -> ThemeValidationReport {
+    let result = match parse_theme_file(path) {
+        // The version was checked up front, so the reference palette exists
+        Ok(theme) => {
+            ThemeCheck::Checked(validate_theme(&theme, bitwig_version).unwrap_or_default())
+        }
+        Err(e) => ThemeCheck::ParseError(e.to_string()),
+    };
+
+    ThemeValidationReport {
+        path: path.to_string_lossy().to_string(),
+        result,
+    }
+}
+
+/// Text of a panic payload, for reporting a worker that panicked
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("worker panicked")
+}
+
+/// Validate many theme files in parallel, returning reports in input order
+/// Returns None if no reference palette is bundled for the version
+pub fn validate_theme_files(
+    paths: &[PathBuf],
+    bitwig_version: &str,
+) -> Option<Vec<ThemeValidationReport>> {
+    theme_key_schema(bitwig_version)?;
+    if paths.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_VALIDATION_WORKERS);
+    let chunk_size = paths.len().div_ceil(workers);
+
+    let reports = thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let handle = scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|path| check_theme_file(path, bitwig_version))
+                        .collect::<Vec<_>>()
+                });
+                (chunk, handle)
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|(chunk, handle)| {
+                // A panicking worker still accounts for every file it was given
+                handle.join().unwrap_or_else(|panic| {
+                    let message = format!("Validation failed: {}", panic_message(&*panic));
+                    chunk
+                        .iter()
+                        .map(|path| ThemeValidationReport {
+                            path: path.to_string_lossy().to_string(),
+                            result: ThemeCheck::ParseError(message.clone()),
+                        })
+                        .collect()
+                })
+            })
+            .collect()
+    });
+
+    Some(reports)
+}
+
+/// How well a theme's keys match a Bitwig version
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum Compatibility {
+    /// Every key appears in the version's reference palette
+    Full,
+    /// Some keys don't appear in the reference palette and may be ignored
+    Partial,
+    /// The theme couldn't be read, or none of its keys appear in the reference palette
+    Unknown,
+}
+
+/// A library theme with its compatibility with a Bitwig version
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibleTheme {
+    pub path: String,
+    pub name: Option<String>,
+    pub compatibility: Compatibility,
+    /// Number of the theme's keys the reference palette doesn't set
+    pub incompatible_keys: usize,
+}
+
+/// Rate a theme against a Bitwig version's reference palette
+/// Returns None if no reference palette is bundled for the version
+pub fn theme_compatibility(theme: &Theme, bitwig_version: &str) -> Option<(Compatibility, usize)> {
+    let unknown = validate_theme(theme, bitwig_version)?.unknown_keys.len();
+    let compatibility = if theme.colors.is_empty() || unknown == theme.colors.len() {
+        Compatibility::Unknown
+    } else if unknown == 0 {
+        Compatibility::Full
+    } else {
+        Compatibility::Partial
+    };
+    Some((compatibility, unknown))
+}
+
+/// Rate each theme file against a Bitwig version, in input order
+/// Returns None if no reference palette is bundled for the version
+pub fn list_compatible_themes(
+    paths: &[PathBuf],
+    bitwig_version: &str,
+) -> Option<Vec<CompatibleTheme>> {
+    theme_key_schema(bitwig_version)?;
+
+    let themes = paths
+        .iter()
+        .map(|path| {
+            let theme = parse_theme_file(path).ok();
+            let (compatibility, incompatible_keys) = theme
+                .as_ref()
+                .and_then(|t| theme_compatibility(t, bitwig_version))
+                .unwrap_or((Compatibility::Unknown, 0));
+
+            CompatibleTheme {
+                path: path.to_string_lossy().to_string(),
+                name: theme.and_then(|t| t.metadata.name),
+                compatibility,
+                incompatible_keys,
+            }
+        })
+        .collect();
+
+    Some(themes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::{default_theme, save_theme};
+
+    #[test]
+    fn test_validate_theme() {
+        let mut theme = default_theme("5.2").unwrap();
+        assert!(validate_theme(&theme, "5.2").unwrap().is_valid());
+
+        theme.colors.remove("Window background");
+        theme.colors.insert("Made Up Key".to_string(), "#ffffff".to_string());
+        theme.colors.insert("Panel body".to_string(), "#zzzzzz".to_string());
+
+        let result = validate_theme(&theme, "5.2").unwrap();
+        assert_eq!(result.missing_keys, vec!["Window background"]);
+        assert_eq!(result.unknown_keys, vec!["Made Up Key"]);
+        assert_eq!(result.invalid_values, vec!["Panel body"]);
+        assert!(!result.is_valid());
+
+        // Keys that differ from the reference palette alone don't make a theme invalid
+        theme.colors.insert("Panel body".to_string(), "#123456".to_string());
+        assert!(validate_theme(&theme, "5.2").unwrap().is_valid());
+
+        assert!(validate_theme(&theme, "2.0").is_none());
+    }
+
+    #[test]
+    fn test_validate_theme_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.bte");
+        let broken = dir.path().join("broken.bte");
+        let missing = dir.path().join("missing.bte");
+
+        save_theme(&default_theme("5.2").unwrap(), &good).unwrap();
+        std::fs::write(&broken, "{ not json }").unwrap();
+
+        let paths = vec![good, broken, missing];
+        let reports = validate_theme_files(&paths, "5.2").unwrap();
+
+        assert_eq!(reports.len(), 3);
+        assert!(matches!(&reports[0].result, ThemeCheck::Checked(v) if v.is_valid()));
+        assert!(matches!(reports[1].result, ThemeCheck::ParseError(_)));
+        assert!(matches!(reports[2].result, ThemeCheck::ParseError(_)));
+        assert!(reports[2].path.ends_with("missing.bte"));
+
+        assert!(validate_theme_files(&paths, "1.0").is_none());
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("bad theme {}", 3)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "bad theme 3");
+
+        let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(&*payload), "static");
+
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(7)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "worker panicked");
+    }
+
+    #[test]
+    fn test_theme_compatibility() {
+        let full = Theme::with_name("Full").color("Window background", "#111111");
+        let partial = full.clone().color("Made Up Key", "#222222");
+        let foreign = Theme::with_name("Foreign").color("Made Up Key", "#222222");
+
+        assert_eq!(theme_compatibility(&full, "5.2"), Some((Compatibility::Full, 0)));
+        assert_eq!(theme_compatibility(&partial, "5.2"), Some((Compatibility::Partial, 1)));
+        assert_eq!(theme_compatibility(&foreign, "5.2"), Some((Compatibility::Unknown, 1)));
+        assert_eq!(theme_compatibility(&full, "1.0"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("partial.bte");
+        save_theme(&partial, &path).unwrap();
+
+        let listed = list_compatible_themes(&[path, dir.path().join("gone.bte")], "5.2").unwrap();
+        assert_eq!(listed[0].name.as_deref(), Some("Full"));
+        assert_eq!(listed[0].compatibility, Compatibility::Partial);
+        assert_eq!(listed[1].compatibility, Compatibility::Unknown);
+    }
+}
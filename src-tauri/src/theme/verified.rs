@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::parser::write_atomic;
+use super::{content_hash, ThemeError};
+
+/// A theme the user confirmed works with a Bitwig version
+/// Tied to a hash of the whole file, so any edit to the theme drops the flag
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VerifiedTheme {
+    pub content_hash: String,
+    pub version: String,
+    /// Where the theme was when it was verified, for display only
+    pub path: PathBuf,
+    /// Unix timestamp (seconds)
+    pub verified_at: u64,
+}
+
+/// Get the file holding verified themes
+pub fn get_verified_themes_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("bitwig-theme-manager").join("verified-themes.json"))
+}
+
+/// Read all verified themes; a missing or unreadable store is empty
+pub fn load_verified_themes(store: &Path) -> Vec<VerifiedTheme> {
+    fs::read_to_string(store)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Mark a theme file as working with a Bitwig version
+/// Replaces any earlier flag for the same file and version
+pub fn mark_theme_verified(store: &Path, theme: &Path, version: &str) -> Result<(), ThemeError> {
+    let hash = content_hash(&fs::read_to_string(theme)?);
+    let mut entries = load_verified_themes(store);
+    entries.retain(|e| e.version != version || (e.path != theme && e.content_hash != hash));
+    entries.push(VerifiedTheme {
+        content_hash: hash,
+        version: version.to_string(),
+        path: theme.to_path_buf(),
+        verified_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    });
+
+    if let Some(parent) = store.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&entries)
+        .map_err(|e| ThemeError::InvalidFormat(e.to_string()))?;
+    write_atomic(store, &json)?;
+    Ok(())
+}
+
+/// Check whether a theme file, as it is now, was verified for a Bitwig version
+pub fn is_theme_verified(store: &Path, theme: &Path, version: &str) -> bool {
+    let Ok(content) = fs::read_to_string(theme) else {
+        return false;
+    };
+    let hash = content_hash(&content);
+    load_verified_themes(store)
+        .iter()
+        .any(|e| e.version == version && e.content_hash == hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editing_theme_clears_verified_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("verified-themes.json");
+        let theme = dir.path().join("Dark.bte");
+        fs::write(&theme, "Background: #111111\n").unwrap();
+
+        assert!(!is_theme_verified(&store, &theme, "5.2"));
+        mark_theme_verified(&store, &theme, "5.2").unwrap();
+        assert!(is_theme_verified(&store, &theme, "5.2"));
+        assert!(!is_theme_verified(&store, &theme, "5.3"));
+
+        fs::write(&theme, "Background: #222222\n").unwrap();
+        assert!(!is_theme_verified(&store, &theme, "5.2"));
+
+        // Re-verifying the edited theme replaces the stale entry
+        mark_theme_verified(&store, &theme, "5.2").unwrap();
+        assert!(is_theme_verified(&store, &theme, "5.2"));
+        assert_eq!(load_verified_themes(&store).len(), 1);
+    }
+}
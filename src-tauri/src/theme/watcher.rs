@@ -2,9 +2,10 @@ use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 use thiserror::Error;
 
@@ -30,11 +31,28 @@ pub struct ThemeChangeEvent {
     pub watched_path: String,
 }
 
+/// Resolve symlinks in a watched path so the backend watches the real directory
+/// Falls back to the original path if it can't be canonicalized
+fn canonical_watch_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Map a path reported under the canonical directory back under the path the user chose
+fn to_display_path(path: &Path, canonical: &Path, display: &Path) -> PathBuf {
+    match path.strip_prefix(canonical) {
+        Ok(relative) => display.join(relative),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
 /// A file watcher for theme files
 pub struct ThemeWatcher {
     watcher: RecommendedWatcher,
     receiver: Receiver<Result<Event, notify::Error>>,
+    /// Canonical path handed to the backend
     watched_path: PathBuf,
+    /// Path as given by the caller, used for reported files
+    display_path: PathBuf,
 }
 
 impl ThemeWatcher {
@@ -57,7 +75,8 @@ impl ThemeWatcher {
         Ok(Self {
             watcher,
             receiver: rx,
-            watched_path: path.to_path_buf(),
+            watched_path: canonical_watch_path(path),
+            display_path: path.to_path_buf(),
         })
     }
 
@@ -74,6 +93,11 @@ impl ThemeWatcher {
         Ok(())
     }
 
+    /// Report a changed file under the caller's path rather than the canonical one
+    fn display(&self, path: &Path) -> PathBuf {
+        to_display_path(path, &self.watched_path, &self.display_path)
+    }
+
     /// Check for file changes (non-blocking)
     pub fn poll(&self) -> Option<Vec<PathBuf>> {
         let mut changed_files = Vec::new();
@@ -83,7 +107,7 @@ impl ThemeWatcher {
                 notify::EventKind::Modify(_) | notify::EventKind::Create(_) => {
                     for path in event.paths {
                         if path.extension().is_some_and(|ext| ext == "bte") {
-                            changed_files.push(path);
+                            changed_files.push(self.display(&path));
                         }
                     }
                 }
@@ -108,6 +132,7 @@ impl ThemeWatcher {
                             .paths
                             .into_iter()
                             .filter(|p| p.extension().is_some_and(|ext| ext == "bte"))
+                            .map(|p| self.display(&p))
                             .collect();
 
                         if !changed_files.is_empty() {
@@ -121,16 +146,227 @@ impl ThemeWatcher {
     }
 }
 
+/// Event payload sent to the frontend when watching stops unexpectedly
+#[derive(Clone, Serialize)]
+pub struct WatcherErrorEvent {
+    pub message: String,
+    pub watched_path: String,
+}
+
+/// Why a watch loop ended
+#[derive(Debug, PartialEq)]
+enum WatchExit {
+    /// A stop was requested
+    Stopped,
+    /// Watching failed and can't continue
+    Failed(String),
+}
+
+/// Whether a backend error leaves the watch itself intact
+/// A missing watched directory is caught separately, by checking the path
+fn is_recoverable(error: &notify::Error) -> bool {
+    matches!(
+        error.kind,
+        notify::ErrorKind::Generic(_) | notify::ErrorKind::Io(_) | notify::ErrorKind::PathNotFound
+    )
+}
+
+/// Watch `path` until a stop is requested or watching fails,
+/// calling `on_change` with the theme files touched by each event
+/// Changed files are reported under `display_path`, the path the user chose
+fn run_watch_loop(
+    path: &Path,
+    display_path: &Path,
+    recursive: bool,
+    stop_rx: &Receiver<()>,
+    mut on_change: impl FnMut(Vec<String>),
+) -> WatchExit {
+    let (tx, rx) = channel();
+
+    let mut watcher = match RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default().with_poll_interval(Duration::from_millis(500)),
+    ) {
+        Ok(w) => w,
+        Err(e) => return WatchExit::Failed(format!("Failed to create watcher: {}", e)),
+    };
+
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+
+    if let Err(e) = watcher.watch(path, mode) {
+        return WatchExit::Failed(format!("Failed to start watching: {}", e));
+    }
+
+    let removed = || {
+        WatchExit::Failed(format!("Watched directory was removed: {}", path.display()))
+    };
+
+    loop {
+        // Check for stop signal (non-blocking)
+        if stop_rx.try_recv().is_ok() {
+            return WatchExit::Stopped;
+        }
+
+        // Check for file events with timeout
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(Ok(event)) => match event.kind {
+                notify::EventKind::Modify(_)
+                | notify::EventKind::Create(_)
+                | notify::EventKind::Remove(_) => {
+                    if matches!(event.kind, notify::EventKind::Remove(_)) && !path.exists() {
+                        return removed();
+                    }
+
+                    let changed_files: Vec<String> = event
+                        .paths
+                        .iter()
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "bte"))
+                        .map(|p| to_display_path(p, path, display_path))
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect();
+
+                    if !changed_files.is_empty() {
+                        on_change(changed_files);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Err(e)) if is_recoverable(&e) => {
+                // e.g. a dropped event or a file vanishing mid-scan; keep watching
+                eprintln!("Watch error (continuing): {}", e);
+            }
+            Ok(Err(e)) => {
+                return WatchExit::Failed(format!("Watch error: {}", e));
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                // Not every backend reports the watched directory itself going away
+                if !path.exists() {
+                    return removed();
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                return WatchExit::Failed("Watcher disconnected".to_string());
+            }
+        }
+    }
+}
+
 /// Internal state for the watcher thread
 struct WatcherThreadState {
     stop_signal: Sender<()>,
     handle: JoinHandle<()>,
     watched_path: PathBuf,
+    /// Cleared by the thread when watching fails
+    alive: Arc<AtomicBool>,
+}
+
+impl WatcherThreadState {
+    fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+}
+
+/// Longest a pause holds back events; a caller that never resumed (e.g. a frontend
+/// reloaded mid-operation) can't silence the watcher for good
+const MAX_PAUSE: Duration = Duration::from_secs(5 * 60);
+
+/// Holds back change events while bulk operations run
+/// Pauses nest; files seen meanwhile are released as one batch on the last resume
+#[derive(Default)]
+struct EventGate {
+    pauses: usize,
+    pending: Vec<String>,
+    /// When the outermost pause began
+    paused_at: Option<Instant>,
+}
+
+impl EventGate {
+    /// Pass changed files through, or buffer them while paused
+    /// Once the pause has outlived `MAX_PAUSE` it is dropped and the buffer released too
+    fn offer(&mut self, mut files: Vec<String>, now: Instant) -> Option<Vec<String>> {
+        if self.expire(now) {
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.retain(|file| !files.contains(file));
+            pending.append(&mut files);
+            return Some(pending);
+        }
+        if self.pauses == 0 {
+            return Some(files);
+        }
+        for file in files {
+            if !self.pending.contains(&file) {
+                self.pending.push(file);
+            }
+        }
+        None
+    }
+
+    fn pause(&mut self, now: Instant) {
+        // A new operation shouldn't inherit an abandoned pause's deadline
+        self.expire(now);
+        if self.pauses == 0 {
+            self.paused_at = Some(now);
+        }
+        self.pauses += 1;
+    }
+
+    /// End one pause, returning the buffered files once no pause remains
+    fn resume(&mut self) -> Option<Vec<String>> {
+        self.pauses = self.pauses.saturating_sub(1);
+        if self.pauses > 0 {
+            return None;
+        }
+        self.paused_at = None;
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    /// Drop a pause that has lasted longer than `MAX_PAUSE`
+    /// Returns true if one was dropped
+    fn expire(&mut self, now: Instant) -> bool {
+        let stale = self
+            .paused_at
+            .is_some_and(|since| now.saturating_duration_since(since) > MAX_PAUSE);
+        if stale {
+            self.pauses = 0;
+            self.paused_at = None;
+        }
+        stale
+    }
+
+    fn is_paused(&self, now: Instant) -> bool {
+        self.pauses > 0
+            && self
+                .paused_at
+                .is_none_or(|since| now.saturating_duration_since(since) <= MAX_PAUSE)
+    }
+}
+
+/// Keeps change events held back until dropped, see `WatcherManager::pause_guard`
+pub struct PauseGuard<'a, R: tauri::Runtime> {
+    manager: &'a WatcherManager,
+    app_handle: AppHandle<R>,
+}
+
+impl<R: tauri::Runtime> Drop for PauseGuard<'_, R> {
+    fn drop(&mut self) {
+        self.manager.resume(&self.app_handle);
+    }
 }
 
 /// Manages theme file watching with Tauri event integration
 pub struct WatcherManager {
     state: Arc<Mutex<Option<WatcherThreadState>>>,
+    /// Shared with the watcher thread, and kept across restarts
+    gate: Arc<Mutex<EventGate>>,
 }
 
 impl Default for WatcherManager {
@@ -143,12 +379,57 @@ impl WatcherManager {
     pub fn new() -> Self {
         Self {
             state: Arc::new(Mutex::new(None)),
+            gate: Arc::new(Mutex::new(EventGate::default())),
+        }
+    }
+
+    /// Check if change events are being held back
+    pub fn is_paused(&self) -> bool {
+        self.gate.lock().unwrap().is_paused(Instant::now())
+    }
+
+    /// Hold back `theme-changed` events without stopping the watcher
+    /// Wrap bulk operations in `pause`/`resume` to avoid flooding the frontend
+    /// A pause that isn't resumed within five minutes ends on its own
+    pub fn pause(&self) {
+        self.gate.lock().unwrap().pause(Instant::now());
+    }
+
+    /// Pause until the returned guard is dropped, so every exit path resumes
+    pub fn pause_guard<R: tauri::Runtime>(&self, app_handle: &AppHandle<R>) -> PauseGuard<'_, R> {
+        self.pause();
+        PauseGuard {
+            manager: self,
+            app_handle: app_handle.clone(),
+        }
+    }
+
+    /// End a pause; once none remain, files changed meanwhile are emitted
+    /// as a single `theme-changed` event
+    pub fn resume<R: tauri::Runtime>(&self, app_handle: &AppHandle<R>) {
+        let Some(changed_files) = self.gate.lock().unwrap().resume() else {
+            return;
+        };
+        let Some(watched_path) = self.watched_path() else {
+            return;
+        };
+
+        let event = ThemeChangeEvent {
+            changed_files,
+            watched_path: watched_path.to_string_lossy().to_string(),
+        };
+        if let Err(e) = app_handle.emit("theme-changed", &event) {
+            eprintln!("Failed to emit theme-changed event: {}", e);
         }
     }
 
     /// Check if the watcher is currently running
     pub fn is_running(&self) -> bool {
-        self.state.lock().unwrap().is_some()
+        self.state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(WatcherThreadState::is_alive)
     }
 
     /// Get the currently watched path, if any
@@ -157,18 +438,22 @@ impl WatcherManager {
             .lock()
             .unwrap()
             .as_ref()
+            .filter(|s| s.is_alive())
             .map(|s| s.watched_path.clone())
     }
 
-    /// Start watching a directory for theme file changes
+    /// Start watching a directory for theme file changes, including subdirectories
+    /// when `recursive` is set
+    /// Emits `theme-changed` on changes and `theme-watcher-error` if watching dies
     pub fn start<R: tauri::Runtime>(
         &self,
         app_handle: AppHandle<R>,
         path: PathBuf,
+        recursive: bool,
     ) -> Result<(), WatcherError> {
         let mut state = self.state.lock().unwrap();
 
-        if state.is_some() {
+        if state.as_ref().is_some_and(WatcherThreadState::is_alive) {
             return Err(WatcherError::AlreadyRunning);
         }
 
@@ -176,76 +461,47 @@ impl WatcherManager {
             return Err(WatcherError::PathNotFound(path));
         }
 
+        // Reap a watcher thread that already died
+        if let Some(dead) = state.take() {
+            let _ = dead.handle.join();
+        }
+
         let (stop_tx, stop_rx) = channel::<()>();
         let watched_path = path.clone();
+        let canonical_path = canonical_watch_path(&path);
+        let alive = Arc::new(AtomicBool::new(true));
+        let thread_alive = Arc::clone(&alive);
+        let gate = Arc::clone(&self.gate);
 
         let handle = thread::spawn(move || {
-            let (tx, rx) = channel();
-
-            let mut watcher = match RecommendedWatcher::new(
-                move |res| {
-                    let _ = tx.send(res);
-                },
-                notify::Config::default().with_poll_interval(Duration::from_millis(500)),
-            ) {
-                Ok(w) => w,
-                Err(e) => {
-                    eprintln!("Failed to create watcher: {}", e);
+            let exit = run_watch_loop(&canonical_path, &path, recursive, &stop_rx, |changed_files| {
+                let offered = gate.lock().unwrap().offer(changed_files, Instant::now());
+                let Some(changed_files) = offered else {
                     return;
+                };
+                let event = ThemeChangeEvent {
+                    changed_files,
+                    watched_path: path.to_string_lossy().to_string(),
+                };
+
+                // Emit Tauri event to frontend
+                if let Err(e) = app_handle.emit("theme-changed", &event) {
+                    eprintln!("Failed to emit theme-changed event: {}", e);
                 }
-            };
-
-            if let Err(e) = watcher.watch(&path, RecursiveMode::NonRecursive) {
-                eprintln!("Failed to start watching: {}", e);
-                return;
-            }
-
-            loop {
-                // Check for stop signal (non-blocking)
-                if stop_rx.try_recv().is_ok() {
-                    break;
-                }
-
-                // Check for file events with timeout
-                match rx.recv_timeout(Duration::from_millis(100)) {
-                    Ok(Ok(event)) => {
-                        match event.kind {
-                            notify::EventKind::Modify(_)
-                            | notify::EventKind::Create(_)
-                            | notify::EventKind::Remove(_) => {
-                                let changed_files: Vec<String> = event
-                                    .paths
-                                    .iter()
-                                    .filter(|p| {
-                                        p.extension().is_some_and(|ext| ext == "bte")
-                                    })
-                                    .map(|p| p.to_string_lossy().to_string())
-                                    .collect();
-
-                                if !changed_files.is_empty() {
-                                    let event = ThemeChangeEvent {
-                                        changed_files,
-                                        watched_path: path.to_string_lossy().to_string(),
-                                    };
-
-                                    // Emit Tauri event to frontend
-                                    if let Err(e) = app_handle.emit("theme-changed", &event) {
-                                        eprintln!("Failed to emit theme-changed event: {}", e);
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        eprintln!("Watch error: {}", e);
-                    }
-                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                        // Continue loop
-                    }
-                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                        break;
-                    }
+            });
+
+            if let WatchExit::Failed(message) = exit {
+                // Mark as stopped before telling the frontend, so a status
+                // query in response to the event is already accurate
+                thread_alive.store(false, Ordering::SeqCst);
+                eprintln!("{}", message);
+
+                let event = WatcherErrorEvent {
+                    message,
+                    watched_path: path.to_string_lossy().to_string(),
+                };
+                if let Err(e) = app_handle.emit("theme-watcher-error", &event) {
+                    eprintln!("Failed to emit theme-watcher-error event: {}", e);
                 }
             }
         });
@@ -254,27 +510,25 @@ impl WatcherManager {
             stop_signal: stop_tx,
             handle,
             watched_path,
+            alive,
         });
 
         Ok(())
     }
 
     /// Stop watching for theme file changes
+    /// Stopping a watcher that isn't running (or already died) succeeds
     pub fn stop(&self) -> Result<(), WatcherError> {
         let mut state = self.state.lock().unwrap();
 
-        match state.take() {
-            Some(thread_state) => {
-                // Send stop signal
-                let _ = thread_state.stop_signal.send(());
+        if let Some(thread_state) = state.take() {
+            // Send stop signal
+            let _ = thread_state.stop_signal.send(());
 
-                // Wait for thread to finish (with timeout)
-                let _ = thread_state.handle.join();
-
-                Ok(())
-            }
-            None => Err(WatcherError::NotRunning),
+            // Wait for the thread to finish
+            let _ = thread_state.handle.join();
         }
+        Ok(())
     }
 }
 
@@ -283,6 +537,7 @@ impl WatcherManager {
 pub struct WatcherStatus {
     pub is_running: bool,
     pub watched_path: Option<String>,
+    pub is_paused: bool,
 }
 
 #[cfg(test)]
@@ -313,4 +568,149 @@ mod tests {
         // No changes yet
         assert!(watcher.poll().is_none());
     }
+
+    #[test]
+    fn test_recoverable_watch_errors() {
+        assert!(is_recoverable(&notify::Error::generic("queue overflow")));
+        assert!(is_recoverable(&notify::Error::io(std::io::Error::other("busy"))));
+        assert!(is_recoverable(&notify::Error::path_not_found()));
+        assert!(!is_recoverable(&notify::Error::watch_not_found()));
+    }
+
+    #[test]
+    fn test_stop_is_idempotent() {
+        let manager = WatcherManager::new();
+        assert!(manager.stop().is_ok());
+        assert!(manager.stop().is_ok());
+    }
+
+    #[test]
+    fn test_watch_loop_stops_on_request() {
+        let dir = tempdir().unwrap();
+        let (stop_tx, stop_rx) = channel();
+        stop_tx.send(()).unwrap();
+
+        assert_eq!(
+            run_watch_loop(dir.path(), dir.path(), false, &stop_rx, |_| {}),
+            WatchExit::Stopped
+        );
+    }
+
+    #[test]
+    fn test_watch_loop_fails_when_directory_deleted() {
+        let dir = tempdir().unwrap();
+        let watched = dir.path().join("themes");
+        std::fs::create_dir(&watched).unwrap();
+
+        let (_stop_tx, stop_rx) = channel::<()>();
+        let loop_path = watched.clone();
+        let handle =
+            thread::spawn(move || run_watch_loop(&loop_path, &loop_path, false, &stop_rx, |_| {}));
+
+        thread::sleep(Duration::from_millis(200));
+        std::fs::remove_dir_all(&watched).unwrap();
+
+        assert!(matches!(handle.join().unwrap(), WatchExit::Failed(_)));
+    }
+
+    #[test]
+    fn test_watch_loop_recursive_detects_nested_changes() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("dark");
+        std::fs::create_dir(&nested).unwrap();
+
+        let (stop_tx, stop_rx) = channel::<()>();
+        let (change_tx, change_rx) = channel();
+        let loop_path = dir.path().to_path_buf();
+        let handle = thread::spawn(move || {
+            run_watch_loop(&loop_path, &loop_path, true, &stop_rx, |files| {
+                let _ = change_tx.send(files);
+            })
+        });
+
+        thread::sleep(Duration::from_millis(200));
+        std::fs::write(nested.join("nested.bte"), "Window.Background: #000000").unwrap();
+        std::fs::write(nested.join("notes.txt"), "ignored").unwrap();
+
+        let changed = change_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(changed.iter().any(|f| f.ends_with("nested.bte")));
+        assert!(changed.iter().all(|f| f.ends_with(".bte")));
+
+        stop_tx.send(()).unwrap();
+        assert_eq!(handle.join().unwrap(), WatchExit::Stopped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watcher_follows_symlinked_directory() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("real-themes");
+        let link = dir.path().join("themes");
+        std::fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut watcher = ThemeWatcher::new(&link).unwrap();
+        assert_eq!(watcher.watched_path, target.canonicalize().unwrap());
+        watcher.start().unwrap();
+
+        thread::sleep(Duration::from_millis(200));
+        std::fs::write(target.join("linked.bte"), "Window.Background: #000000").unwrap();
+
+        let mut changed = Vec::new();
+        for _ in 0..50 {
+            changed.extend(watcher.poll().unwrap_or_default());
+            if !changed.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        assert!(changed.contains(&link.join("linked.bte")));
+    }
+
+    #[test]
+    fn test_event_gate_coalesces_while_paused() {
+        let mut gate = EventGate::default();
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let now = Instant::now();
+
+        assert_eq!(gate.offer(files(&["a.bte"]), now), Some(files(&["a.bte"])));
+
+        gate.pause(now);
+        gate.pause(now);
+        assert_eq!(gate.offer(files(&["a.bte", "b.bte"]), now), None);
+        assert_eq!(gate.offer(files(&["b.bte"]), now), None);
+        assert_eq!(gate.offer(files(&["c.bte"]), now), None);
+
+        // Still paused by the outer operation
+        assert_eq!(gate.resume(), None);
+        assert!(gate.is_paused(now));
+
+        assert_eq!(gate.resume(), Some(files(&["a.bte", "b.bte", "c.bte"])));
+        assert!(!gate.is_paused(now));
+        assert_eq!(gate.offer(files(&["d.bte"]), now), Some(files(&["d.bte"])));
+
+        // Resuming with nothing buffered emits nothing
+        gate.pause(now);
+        assert_eq!(gate.resume(), None);
+    }
+
+    #[test]
+    fn test_event_gate_drops_stale_pause() {
+        let mut gate = EventGate::default();
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let start = Instant::now();
+        let later = start + MAX_PAUSE + Duration::from_secs(1);
+
+        gate.pause(start);
+        assert_eq!(gate.offer(files(&["a.bte"]), start), None);
+        assert!(!gate.is_paused(later));
+
+        // The next change releases the held-back files along with it
+        assert_eq!(gate.offer(files(&["b.bte"]), later), Some(files(&["a.bte", "b.bte"])));
+        assert_eq!(gate.offer(files(&["c.bte"]), later), Some(files(&["c.bte"])));
+
+        // A late resume from the abandoned operation is harmless
+        assert_eq!(gate.resume(), None);
+        assert!(!gate.is_paused(later));
+    }
 }
@@ -0,0 +1,158 @@
+use regex::Regex;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// Release notes for a pending update
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseNotes {
+    pub version: String,
+    /// The release body exactly as published
+    pub raw: String,
+    /// The body with markdown syntax stripped for plain display
+    pub formatted: String,
+}
+
+/// Convert release-note markdown into plain readable text
+pub fn format_release_notes(markdown: &str) -> String {
+    let image = Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap();
+    let link = Regex::new(r"\[([^\]]+)\]\([^)]*\)").unwrap();
+    let html = Regex::new(r"<[^>]+>").unwrap();
+    let emphasis = Regex::new(r"(\*\*|__|~~|\*)([^*~]+?)(\*\*|__|~~|\*)").unwrap();
+    let code = Regex::new(r"`([^`]*)`").unwrap();
+
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") {
+            continue;
+        }
+
+        let mut text = trimmed.trim_start_matches('#').trim_start().to_string();
+        if let Some(rest) = text
+            .strip_prefix("- ")
+            .or_else(|| text.strip_prefix("* "))
+            .or_else(|| text.strip_prefix("+ "))
+        {
+            text = format!("• {}", rest);
+        }
+
+        let text = image.replace_all(&text, "");
+        let text = link.replace_all(&text, "$1");
+        let text = html.replace_all(&text, "");
+        let text = emphasis.replace_all(&text, "$2");
+        let text = code.replace_all(&text, "$1");
+        let text = text.trim().to_string();
+
+        // Collapse runs of blank lines
+        if text.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(text);
+    }
+
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Split a version into its numeric release part and its pre-release identifiers
+/// "v1.2.0-beta.3" gives ([1, 2, 0], ["beta", "3"]) and "6.0 Beta 6" gives ([6, 0], ["beta", "6"])
+fn split_version(version: &str) -> (Vec<u64>, Vec<String>) {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let core_end = version
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(version.len());
+    let (core, pre) = version.split_at(core_end);
+
+    let release = core
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    let pre_release = pre
+        .split(['-', '.', ' ', '+', '_'])
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    (release, pre_release)
+}
+
+/// Compare two version strings, semver style
+/// Missing release components count as zero, a pre-release sorts before its release,
+/// and numeric pre-release identifiers compare numerically ("rc.10" > "rc.2")
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre) = split_version(a);
+    let (b_release, b_pre) = split_version(b);
+
+    let len = a_release.len().max(b_release.len());
+    for i in 0..len {
+        let x = a_release.get(i).copied().unwrap_or(0);
+        let y = b_release.get(i).copied().unwrap_or(0);
+        match x.cmp(&y) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+    }
+
+    match (a_pre.is_empty(), b_pre.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+
+    for (x, y) in a_pre.iter().zip(&b_pre) {
+        let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a_pre.len().cmp(&b_pre.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_release_notes() {
+        let markdown = "## What's Changed\n\n\n* **Fixed** theme apply on [Windows](https://example.com)\n- Added `get_changelog`\n\n![shot](https://example.com/a.png)\n<br>\n";
+        assert_eq!(
+            format_release_notes(markdown),
+            "What's Changed\n\n• Fixed theme apply on Windows\n• Added get_changelog"
+        );
+    }
+
+    #[test]
+    fn test_format_plain_text_unchanged() {
+        assert_eq!(format_release_notes("Bug fixes"), "Bug fixes");
+        assert_eq!(format_release_notes(""), "");
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.5.0", "0.4.2"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.10.0", "0.9.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_compare_pre_release_versions() {
+        assert_eq!(compare_versions("1.0.0-beta.2", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0", "1.0.0-rc.1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-rc.10", "1.0.0-rc.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-alpha", "1.0.0-beta"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0-alpha", "1.0.0-alpha.1"), Ordering::Less);
+        assert_eq!(compare_versions("6.0 Beta 6", "6.0"), Ordering::Less);
+        assert_eq!(compare_versions("6.0 Beta 6", "5.2"), Ordering::Greater);
+    }
+}
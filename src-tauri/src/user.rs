@@ -0,0 +1,153 @@
+use crate::log_event;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The user who ran the app through sudo
+#[derive(Debug, Clone, PartialEq)]
+pub struct SudoUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+}
+
+/// The invoking user when the app runs under sudo, looked up once per process
+pub fn sudo_user() -> Option<&'static SudoUser> {
+    static SUDO_USER: OnceLock<Option<SudoUser>> = OnceLock::new();
+    SUDO_USER.get_or_init(lookup_sudo_user).as_ref()
+}
+
+/// Home directory of the user the app acts for
+/// Under sudo, `dirs::home_dir()` is root's home, but Bitwig runs as the invoking user
+/// and only reads themes and its version file from that user's home
+pub fn home_dir() -> Option<PathBuf> {
+    match sudo_user() {
+        Some(user) => Some(user.home.clone()),
+        None => dirs::home_dir(),
+    }
+}
+
+/// Give a path created under the invoking user's home back to that user
+/// Under sudo new files are root-owned, which would stop the next normal run from writing
+/// them. `path` and its parents up to the home directory are changed; outside sudo, or
+/// outside that home, nothing happens
+pub fn hand_back(path: &Path) {
+    #[cfg(unix)]
+    if let Some(user) = sudo_user() {
+        chown_below(path, &user.home, user.uid, user.gid);
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Like `hand_back`, also covering everything inside a directory
+pub fn hand_back_tree(dir: &Path) {
+    #[cfg(unix)]
+    if let Some(user) = sudo_user() {
+        chown_below(dir, &user.home, user.uid, user.gid);
+        for entry in walkdir::WalkDir::new(dir).min_depth(1).into_iter().flatten() {
+            chown_below(entry.path(), &user.home, user.uid, user.gid);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(unix)]
+fn chown_below(path: &Path, home: &Path, uid: u32, gid: u32) {
+    if !path.starts_with(home) {
+        return;
+    }
+    for ancestor in path.ancestors().take_while(|p| *p != home) {
+        if let Err(e) = std::os::unix::fs::lchown(ancestor, Some(uid), Some(gid)) {
+            log_event(&format!("Could not give {} back to uid {}: {}", ancestor.display(), uid, e));
+            return;
+        }
+    }
+}
+
+/// `SUDO_USER` if it names someone other than root
+#[cfg_attr(not(unix), allow(dead_code))]
+fn invoking_user_name(sudo_user: Option<&str>) -> Option<&str> {
+    sudo_user.filter(|u| !u.is_empty() && *u != "root")
+}
+
+#[cfg(unix)]
+fn lookup_sudo_user() -> Option<SudoUser> {
+    let sudo_user = std::env::var("SUDO_USER").ok();
+    let name = invoking_user_name(sudo_user.as_deref())?;
+    let Some(user) = passwd_entry(name) else {
+        log_event(&format!("Running under sudo, but no account was found for {}", name));
+        return None;
+    };
+    log_event(&format!(
+        "Running under sudo; using {}'s home {} for themes",
+        user.name,
+        user.home.display()
+    ));
+    Some(user)
+}
+
+#[cfg(not(unix))]
+fn lookup_sudo_user() -> Option<SudoUser> {
+    None
+}
+
+/// Look up an account through the system's user database
+/// `getpwnam_r` goes through NSS, so LDAP and NIS accounts resolve as well
+#[cfg(unix)]
+fn passwd_entry(name: &str) -> Option<SudoUser> {
+    use std::ffi::{CStr, CString};
+
+    let c_name = CString::new(name).ok()?;
+    let mut buf: Vec<libc::c_char> = vec![0; 4096];
+    loop {
+        // SAFETY: passwd is plain data, and every pointer passed lives across the call
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        let rc = unsafe {
+            libc::getpwnam_r(c_name.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+        if rc == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() || pwd.pw_dir.is_null() {
+            return None;
+        }
+
+        // SAFETY: on success pw_dir points into `buf` as a NUL-terminated string
+        let home = unsafe { CStr::from_ptr(pwd.pw_dir) }.to_string_lossy().into_owned();
+        if home.is_empty() {
+            return None;
+        }
+        return Some(SudoUser {
+            name: name.to_string(),
+            uid: pwd.pw_uid,
+            gid: pwd.pw_gid,
+            home: PathBuf::from(home),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invoking_user_name() {
+        assert_eq!(invoking_user_name(Some("alice")), Some("alice"));
+        assert_eq!(invoking_user_name(Some("root")), None);
+        assert_eq!(invoking_user_name(Some("")), None);
+        assert_eq!(invoking_user_name(None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_passwd_entry() {
+        let root = passwd_entry("root").expect("root account");
+        assert_eq!(root.uid, 0);
+        assert!(root.home.is_absolute());
+        assert_eq!(passwd_entry("no-such-user-btm"), None);
+    }
+}
@@ -863,7 +863,7 @@ function EditorView() {
 }
 
 function PatchView() {
-  const { installations, loading, error, javaAvailable, backups, addManualPath, patchInstallation, restoreInstallation, refresh } = useBitwigInstallations();
+  const { installations, loading, error, javaAvailable, elevation, backups, addManualPath, patchInstallation, restoreInstallation, refresh } = useBitwigInstallations();
   const [manualPath, setManualPath] = useState("");
   const [actionLoading, setActionLoading] = useState<string | null>(null);
   const [patchResult, setPatchResult] = useState<{ success: boolean; message: string } | null>(null);
@@ -931,6 +931,21 @@ function PatchView() {
         </div>
       )}
 
+      {/* Elevation Status Banner */}
+      {elevation && !elevation.available && installations.some((i) => i.needs_sudo) && (
+        <div className="bg-yellow-900/50 border border-yellow-700 rounded-lg p-4">
+          <div className="flex items-center gap-2 text-yellow-400">
+            <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+              <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M12 9v2m0 4h.01m-6.938 4h13.856c1.54 0 2.502-1.667 1.732-3L13.732 4c-.77-1.333-2.694-1.333-3.464 0L3.34 16c-.77 1.333.192 3 1.732 3z" />
+            </svg>
+            <span className="font-semibold">Elevation Unavailable</span>
+          </div>
+          <p className="mt-2 text-sm text-gray-300">
+            {elevation.reason ?? "Administrator rights can't be requested, so installations that need them can't be patched."}
+          </p>
+        </div>
+      )}
+
       {/* Patch Result Banner */}
       {patchResult && (
         <div className={`${patchResult.success ? 'bg-green-900/50 border-green-700' : 'bg-red-900/50 border-red-700'} border rounded-lg p-4`}>
@@ -1,10 +1,57 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { BitwigInstallation, Theme, RepositoryTheme } from "./types";
+import type {
+  ActiveThemeInfo,
+  BitwigInstallation,
+  Theme,
+  RepositoryTheme,
+  CvdType,
+  ColorSort,
+  ThemeColor,
+  ConflictPolicy,
+  ThemeFormat,
+  CacheHealth,
+  CacheSummary,
+  DebugDump,
+  DownloadSizeEstimate,
+  ElevationStatus,
+  FormatStats,
+  AuthorSummary,
+  RateLimitStatus,
+  Connectivity,
+  FavoriteTheme,
+  GroupedColors,
+  PatchRequirements,
+  LibrarySnapshot,
+  NormalizeResult,
+  KeyRemapResult,
+  KeySchema,
+  ThemeValidationReport,
+  CompatibleTheme,
+  ThemeDiff,
+  AppPaths,
+  PatchStatusDetails,
+  PatcherStatus,
+  PatchLogEntry,
+  SearchPathInfo,
+  PatchResult,
+} from "./types";
 
 export async function detectBitwigInstallations(): Promise<BitwigInstallation[]> {
   return invoke<BitwigInstallation[]>("detect_bitwig_installations");
 }
 
+export async function getSearchPaths(): Promise<SearchPathInfo[]> {
+  return invoke<SearchPathInfo[]>("get_search_paths");
+}
+
+export async function addSearchPath(path: string): Promise<string[]> {
+  return invoke<string[]>("add_search_path", { path });
+}
+
+export async function removeSearchPath(path: string): Promise<string[]> {
+  return invoke<string[]>("remove_search_path", { path });
+}
+
 export async function validateBitwigPath(path: string): Promise<BitwigInstallation | null> {
   return invoke<BitwigInstallation | null>("validate_bitwig_path", { path });
 }
@@ -13,6 +60,10 @@ export async function getPatchStatus(jarPath: string): Promise<boolean> {
   return invoke<boolean>("get_patch_status", { jarPath });
 }
 
+export async function patchStatusDetailed(jarPath: string): Promise<PatchStatusDetails> {
+  return invoke<PatchStatusDetails>("patch_status_detailed", { jarPath });
+}
+
 export async function getLatestBitwigVersion(): Promise<string> {
   return invoke<string>("get_latest_bitwig_version");
 }
@@ -21,6 +72,14 @@ export async function patchBitwig(jarPath: string): Promise<void> {
   return invoke<void>("patch_bitwig", { jarPath });
 }
 
+export async function readPatchHistory(): Promise<PatchLogEntry[]> {
+  return invoke<PatchLogEntry[]>("read_patch_history");
+}
+
+export async function checkElevationAvailable(): Promise<ElevationStatus> {
+  return invoke<ElevationStatus>("check_elevation_available");
+}
+
 export async function restoreBitwig(jarPath: string): Promise<void> {
   return invoke<void>("restore_bitwig", { jarPath });
 }
@@ -37,6 +96,14 @@ export async function ensurePatcherAvailable(): Promise<string> {
   return invoke<string>("ensure_patcher_available");
 }
 
+export async function getPatcherStatus(): Promise<PatcherStatus> {
+  return invoke<PatcherStatus>("get_patcher_status");
+}
+
+export async function patchRequirements(jarPath: string): Promise<PatchRequirements> {
+  return invoke<PatchRequirements>("patch_requirements", { jarPath });
+}
+
 
 export async function getThemeDirectory(bitwigVersion: string): Promise<string | null> {
   return invoke<string | null>("get_theme_directory", { bitwigVersion });
@@ -46,36 +113,168 @@ export async function listThemes(bitwigVersion: string): Promise<string[]> {
   return invoke<string[]>("list_themes", { bitwigVersion });
 }
 
+export async function validateAllThemes(bitwigVersion: string): Promise<ThemeValidationReport[]> {
+  return invoke<ThemeValidationReport[]>("validate_all_themes", { bitwigVersion });
+}
+
+export async function normalizeLibrary(bitwigVersion: string): Promise<NormalizeResult[]> {
+  return invoke<NormalizeResult[]>("normalize_library", { bitwigVersion });
+}
+
+export async function listCompatibleThemes(bitwigVersion: string): Promise<CompatibleTheme[]> {
+  return invoke<CompatibleTheme[]>("list_compatible_themes", { bitwigVersion });
+}
+
 export async function loadTheme(path: string): Promise<Theme> {
   return invoke<Theme>("load_theme", { path });
 }
 
+export async function previewTheme(theme: Theme): Promise<GroupedColors> {
+  return invoke<GroupedColors>("preview_theme", { theme });
+}
+
 export async function saveTheme(theme: Theme, path: string): Promise<void> {
   return invoke<void>("save_theme", { theme, path });
 }
 
+export async function verifyThemeIntegrity(path: string): Promise<boolean> {
+  return invoke<boolean>("verify_theme_integrity", { path });
+}
+
 export async function getActiveThemePath(bitwigVersion: string): Promise<string | null> {
   return invoke<string | null>("get_active_theme_path", { bitwigVersion });
 }
 
-export async function applyTheme(themePath: string, bitwigVersion: string): Promise<string> {
-  return invoke<string>("apply_theme", { themePath, bitwigVersion });
+export async function getActiveThemeSource(bitwigVersion: string): Promise<string | null> {
+  return invoke<string | null>("get_active_theme_source", { bitwigVersion });
+}
+
+export async function markThemeVerified(path: string, version: string): Promise<void> {
+  return invoke<void>("mark_theme_verified", { path, version });
+}
+
+export async function isThemeVerified(path: string, version: string): Promise<boolean> {
+  return invoke<boolean>("is_theme_verified", { path, version });
+}
+
+export async function reapplyLastTheme(
+  bitwigVersion?: string,
+  installationPath?: string
+): Promise<PatchResult> {
+  return invoke<PatchResult>("reapply_last_theme", { bitwigVersion, installationPath });
+}
+
+export async function getActiveThemeInfo(bitwigVersion: string): Promise<ActiveThemeInfo> {
+  return invoke<ActiveThemeInfo>("get_active_theme_info", { bitwigVersion });
+}
+
+export async function themeDirectoryMatchesInstallation(bitwigVersion: string): Promise<boolean> {
+  return invoke<boolean>("theme_directory_matches_installation", { bitwigVersion });
+}
+
+export async function lockActiveTheme(bitwigVersion: string): Promise<void> {
+  return invoke<void>("lock_active_theme", { bitwigVersion });
+}
+
+export async function unlockActiveTheme(bitwigVersion: string): Promise<void> {
+  return invoke<void>("unlock_active_theme", { bitwigVersion });
 }
 
-export async function resetTheme(bitwigVersion: string): Promise<string> {
-  return invoke<string>("reset_theme", { bitwigVersion });
+export async function applyTheme(
+  themePath: string,
+  bitwigVersion: string,
+  installationPath?: string,
+  remapFromVersion?: string,
+  force?: boolean,
+  normalizeKeys?: boolean
+): Promise<string> {
+  return invoke<string>("apply_theme", {
+    themePath,
+    bitwigVersion,
+    installationPath,
+    remapFromVersion,
+    force,
+    normalizeKeys,
+  });
+}
+
+export async function listActiveSnapshots(bitwigVersion: string): Promise<string[]> {
+  return invoke<string[]>("list_active_snapshots", { bitwigVersion });
+}
+
+export async function restoreActiveSnapshot(
+  bitwigVersion: string,
+  snapshotPath: string,
+  force?: boolean
+): Promise<void> {
+  return invoke<void>("restore_active_snapshot", {
+    bitwigVersion,
+    snapshotPath,
+    force,
+  });
+}
+
+export async function applyThemePartial(
+  sourcePath: string,
+  bitwigVersion: string,
+  groups: string[],
+  force?: boolean
+): Promise<string> {
+  return invoke<string>("apply_theme_partial", { sourcePath, bitwigVersion, groups, force });
+}
+
+export async function resetTheme(bitwigVersion: string, force?: boolean): Promise<string> {
+  return invoke<string>("reset_theme", { bitwigVersion, force });
 }
 
 export async function createTheme(name: string, bitwigVersion: string): Promise<Theme> {
   return invoke<Theme>("create_theme", { name, bitwigVersion });
 }
 
-export async function importTheme(sourcePath: string, bitwigVersion: string): Promise<string> {
-  return invoke<string>("import_theme", { sourcePath, bitwigVersion });
+export async function importTheme(
+  sourcePath: string,
+  bitwigVersion: string,
+  onConflict?: ConflictPolicy
+): Promise<string> {
+  return invoke<string>("import_theme", { sourcePath, bitwigVersion, onConflict });
+}
+
+export async function importThemeFromText(
+  content: string,
+  name: string,
+  bitwigVersion: string
+): Promise<string> {
+  return invoke<string>("import_theme_from_text", { content, name, bitwigVersion });
+}
+
+export async function exportTheme(
+  themePath: string,
+  destPath: string,
+  onConflict?: ConflictPolicy,
+  includePreview?: boolean
+): Promise<string> {
+  return invoke<string>("export_theme", { themePath, destPath, onConflict, includePreview });
+}
+
+export async function exportThemeOverlay(
+  basePath: string,
+  modifiedPath: string,
+  dest: string
+): Promise<void> {
+  return invoke<void>("export_theme_overlay", { basePath, modifiedPath, dest });
 }
 
-export async function exportTheme(themePath: string, destPath: string): Promise<void> {
-  return invoke<void>("export_theme", { themePath, destPath });
+export async function applyThemeOverlay(basePath: string, overlayPath: string): Promise<Theme> {
+  return invoke<Theme>("apply_theme_overlay", { basePath, overlayPath });
+}
+
+export async function convertThemeFile(
+  source: string,
+  dest: string,
+  targetFormat: ThemeFormat,
+  overwrite?: boolean
+): Promise<void> {
+  return invoke<void>("convert_theme_file", { source, dest, targetFormat, overwrite });
 }
 
 export async function deleteTheme(themePath: string): Promise<void> {
@@ -90,6 +289,57 @@ export async function saveDownloadedTheme(
   return invoke<string>("save_downloaded_theme", { themeName, content, bitwigVersion });
 }
 
+export async function saveActiveAs(name: string, bitwigVersion: string): Promise<string> {
+  return invoke<string>("save_active_as", { name, bitwigVersion });
+}
+
+export async function sortColorsBy(theme: Theme, order: ColorSort): Promise<ThemeColor[]> {
+  return invoke<ThemeColor[]>("sort_colors_by", { theme, order });
+}
+
+export async function themeDominantColor(theme: Theme): Promise<string | null> {
+  return invoke<string | null>("theme_dominant_color", { theme });
+}
+
+export async function simulateColorblindness(
+  theme: Theme,
+  kind: CvdType
+): Promise<Record<string, string>> {
+  return invoke<Record<string, string>>("simulate_colorblindness", { theme, kind });
+}
+
+export async function customizationPercentage(theme: Theme, version: string): Promise<number> {
+  return invoke<number>("customization_percentage", { theme, version });
+}
+
+export async function themeToCssVariables(theme: Theme): Promise<string> {
+  return invoke<string>("theme_to_css_variables", { theme });
+}
+
+export async function renderThemePreview(theme: Theme): Promise<string> {
+  return invoke<string>("render_theme_preview", { theme });
+}
+
+export async function diffAgainstDefault(theme: Theme, version: string): Promise<ThemeDiff> {
+  return invoke<ThemeDiff>("diff_against_default", { theme, version });
+}
+
+export async function getThemeKeySchema(version: string): Promise<KeySchema[]> {
+  return invoke<KeySchema[]>("get_theme_key_schema", { version });
+}
+
+export async function remapThemeKeys(
+  theme: Theme,
+  fromVersion: string,
+  toVersion: string
+): Promise<KeyRemapResult> {
+  return invoke<KeyRemapResult>("remap_theme_keys", { theme, fromVersion, toVersion });
+}
+
+export async function generateRandomTheme(base: Theme, seed?: number): Promise<Theme> {
+  return invoke<Theme>("generate_random_theme", { seed, base });
+}
+
 // Repository API
 
 export async function fetchRepositoryThemes(forceRefresh: boolean = false): Promise<RepositoryTheme[]> {
@@ -100,22 +350,154 @@ export async function getCachedRepositoryThemes(): Promise<RepositoryTheme[]> {
   return invoke<RepositoryTheme[]>("get_cached_repository_themes");
 }
 
+export async function getRepositoryReadme(): Promise<string | null> {
+  return invoke<string | null>("get_repository_readme");
+}
+
+export async function filterThemesByTag(tag: string): Promise<RepositoryTheme[]> {
+  return invoke<RepositoryTheme[]>("filter_themes_by_tag", { tag });
+}
+
+export async function listFavorites(): Promise<FavoriteTheme[]> {
+  return invoke<FavoriteTheme[]>("list_favorites");
+}
+
+export async function addFavorite(theme: RepositoryTheme): Promise<void> {
+  return invoke<void>("add_favorite", { theme });
+}
+
+export async function removeFavorite(theme: FavoriteTheme): Promise<void> {
+  return invoke<void>("remove_favorite", { theme });
+}
+
+export async function exportFavorites(dest: string): Promise<number> {
+  return invoke<number>("export_favorites", { dest });
+}
+
+export async function importFavorites(src: string): Promise<number> {
+  return invoke<number>("import_favorites", { src });
+}
+
 export async function downloadRepositoryTheme(themeName: string, repoUrl: string, downloadUrl?: string): Promise<string> {
   return invoke<string>("download_repository_theme", { themeName, repoUrl, downloadUrl });
 }
 
+export async function warmCache(): Promise<number> {
+  return invoke<number>("warm_cache");
+}
+
+export async function diffDownloadedVsLibrary(
+  themeName: string,
+  repoUrl: string,
+  downloadUrl: string | undefined,
+  libraryPath: string
+): Promise<ThemeDiff> {
+  return invoke<ThemeDiff>("diff_downloaded_vs_library", {
+    themeName,
+    repoUrl,
+    downloadUrl,
+    libraryPath,
+  });
+}
+
+export async function estimateDownloadSize(themeNames: string[]): Promise<DownloadSizeEstimate> {
+  return invoke<DownloadSizeEstimate>("estimate_download_size", { themeNames });
+}
+
+export async function repositoryFormatStats(): Promise<FormatStats> {
+  return invoke<FormatStats>("repository_format_stats");
+}
+
+export async function listThemeAuthors(): Promise<AuthorSummary[]> {
+  return invoke<AuthorSummary[]>("list_theme_authors");
+}
+
+export async function githubRateLimitStatus(): Promise<RateLimitStatus | null> {
+  return invoke<RateLimitStatus | null>("github_rate_limit_status");
+}
+
+export async function checkConnectivity(): Promise<Connectivity> {
+  return invoke<Connectivity>("check_connectivity");
+}
+
+export async function networkRequestsInFlight(): Promise<number> {
+  return invoke<number>("network_requests_in_flight");
+}
+
 export async function cacheThemePreview(themeName: string, previewUrl: string): Promise<string> {
   return invoke<string>("cache_theme_preview", { themeName, previewUrl });
 }
 
+export async function cachePreviews(
+  entries: [string, string][]
+): Promise<[string, string | null][]> {
+  return invoke<[string, string | null][]>("cache_previews", { entries });
+}
+
+export async function fetchThemeReadme(repoUrl: string): Promise<string | null> {
+  return invoke<string | null>("fetch_theme_readme", { repoUrl });
+}
+
+export async function fetchThemePreviews(themeName: string, repoUrl: string): Promise<string[]> {
+  return invoke<string[]>("fetch_theme_previews", { themeName, repoUrl });
+}
+
 export async function getCachedPreviewPath(themeName: string): Promise<string | null> {
   return invoke<string | null>("get_cached_preview_path", { themeName });
 }
 
+export async function getThemePreviewBase64(themeName: string): Promise<string | null> {
+  return invoke<string | null>("get_theme_preview_base64", { themeName });
+}
+
 export async function listCachedThemes(): Promise<string[]> {
   return invoke<string[]>("list_cached_themes");
 }
 
+export async function compactCache(): Promise<string[]> {
+  return invoke<string[]>("compact_cache");
+}
+
+export async function validateCache(): Promise<CacheHealth> {
+  return invoke<CacheHealth>("validate_cache");
+}
+
+export async function repairCache(): Promise<string[]> {
+  return invoke<string[]>("repair_cache");
+}
+
+export async function listDebugDumps(): Promise<DebugDump[]> {
+  return invoke<DebugDump[]>("list_debug_dumps");
+}
+
+export async function clearDebugDumps(): Promise<number> {
+  return invoke<number>("clear_debug_dumps");
+}
+
+export async function snapshotThemeLibrary(bitwigVersion: string): Promise<string> {
+  return invoke<string>("snapshot_theme_library", { bitwigVersion });
+}
+
+export async function restoreThemeLibrary(
+  snapshotPath: string,
+  bitwigVersion: string,
+  conflictPolicy?: ConflictPolicy
+): Promise<string[]> {
+  return invoke<string[]>("restore_theme_library", {
+    snapshotPath,
+    bitwigVersion,
+    conflictPolicy,
+  });
+}
+
+export async function listThemeLibrarySnapshots(): Promise<LibrarySnapshot[]> {
+  return invoke<LibrarySnapshot[]>("list_theme_library_snapshots");
+}
+
+export async function cacheSummary(): Promise<CacheSummary> {
+  return invoke<CacheSummary>("cache_summary");
+}
+
 export async function clearCache(): Promise<void> {
   return invoke<void>("clear_cache");
 }
@@ -125,6 +507,7 @@ export async function clearCache(): Promise<void> {
 export interface WatcherStatus {
   is_running: boolean;
   watched_path: string | null;
+  is_paused: boolean;
 }
 
 export interface ThemeChangeEvent {
@@ -132,14 +515,27 @@ export interface ThemeChangeEvent {
   watched_path: string;
 }
 
-export async function startWatching(path: string): Promise<void> {
-  return invoke<void>("start_watching", { path });
+export interface WatcherErrorEvent {
+  message: string;
+  watched_path: string;
+}
+
+export async function startWatching(path: string, recursive?: boolean): Promise<void> {
+  return invoke<void>("start_watching", { path, recursive });
 }
 
 export async function stopWatching(): Promise<void> {
   return invoke<void>("stop_watching");
 }
 
+export async function pauseWatching(): Promise<void> {
+  return invoke<void>("pause_watching");
+}
+
+export async function resumeWatching(): Promise<void> {
+  return invoke<void>("resume_watching");
+}
+
 export async function getWatcherStatus(): Promise<WatcherStatus> {
   return invoke<WatcherStatus>("get_watcher_status");
 }
@@ -152,10 +548,19 @@ export interface Settings {
   watch_theme_directory: boolean;
   selected_bitwig_version: string | null;
   custom_theme_directory: string | null;
+  custom_search_paths: string[];
   cache_duration_hours: number;
   show_preview_images: boolean;
   last_view: string;
   skipped_version: string | null;
+  snapshot_before_apply: boolean;
+  max_concurrent_requests: number;
+  embed_theme_checksum: boolean;
+  patch_history_log: boolean;
+  rollback_on_patch_failure: boolean;
+  proxy_url: string | null;
+  max_theme_keys: number;
+  safe_mode: boolean;
 }
 
 export async function loadSettings(): Promise<Settings> {
@@ -166,6 +571,21 @@ export async function saveSettings(settings: Settings): Promise<void> {
   return invoke<void>("save_settings", { newSettings: settings });
 }
 
+export async function setSetting<K extends keyof Settings>(
+  key: K,
+  value: Settings[K]
+): Promise<Settings> {
+  return invoke<Settings>("set_setting", { key, value });
+}
+
+export async function getAppPaths(): Promise<AppPaths> {
+  return invoke<AppPaths>("get_app_paths");
+}
+
+export async function openPath(path: string): Promise<void> {
+  return invoke<void>("open_path", { path });
+}
+
 export async function getSettingsPath(): Promise<string> {
   return invoke<string>("get_settings_path");
 }
@@ -174,6 +594,15 @@ export async function getLogPath(): Promise<string | null> {
   return invoke<string | null>("get_log_path");
 }
 
+// Appended log lines arrive as "log-line" events while the stream runs
+export async function startLogStream(): Promise<void> {
+  return invoke<void>("start_log_stream");
+}
+
+export async function stopLogStream(): Promise<void> {
+  return invoke<void>("stop_log_stream");
+}
+
 // Update API
 
 export interface UpdateInfo {
@@ -181,6 +610,18 @@ export interface UpdateInfo {
   current_version: string;
   body: string | null;
   date: string | null;
+  is_newer: boolean;
+  is_downgrade: boolean;
+}
+
+export interface ReleaseNotes {
+  version: string;
+  raw: string;
+  formatted: string;
+}
+
+export async function getPendingUpdateNotes(): Promise<ReleaseNotes | null> {
+  return invoke<ReleaseNotes | null>("get_pending_update_notes");
 }
 
 export async function checkForUpdates(): Promise<UpdateInfo | null> {
@@ -194,3 +635,7 @@ export async function getAppVersion(): Promise<string> {
 export async function installUpdate(): Promise<void> {
   return invoke<void>("install_update");
 }
+
+export async function cancelUpdateDownload(): Promise<boolean> {
+  return invoke<boolean>("cancel_update_download");
+}
@@ -9,6 +9,35 @@ export interface BitwigInstallation {
   needs_sudo: boolean;
 }
 
+export interface SearchPathInfo {
+  path: string;
+  installation_type: InstallationType;
+  exists: boolean;
+}
+
+export interface PatchStatusDetails {
+  marker_present: boolean;
+  jar_actually_patched: boolean;
+  backup_present: boolean;
+  backup_valid: boolean;
+}
+
+export interface PatcherStatus {
+  installed: boolean;
+  version: string;
+  checksum_ok: boolean;
+  path: string | null;
+}
+
+export interface PatchRequirements {
+  jar_size_bytes: number;
+  backup_space_needed: number;
+  needs_elevation: boolean;
+  java_available: boolean;
+  patcher_downloaded: boolean;
+  estimated_seconds: number;
+}
+
 export interface ThemeMetadata {
   name?: string;
   author?: string;
@@ -20,6 +49,14 @@ export interface Theme {
   metadata: ThemeMetadata;
   colors: Record<string, string>;
   path?: string;
+  extras?: unknown;
+  color_sections?: Record<string, string>;
+  conflicts?: KeyConflict[];
+}
+
+export interface KeyConflict {
+  key: string;
+  candidates: [string, string][];
 }
 
 export interface ThemeColor {
@@ -28,6 +65,70 @@ export interface ThemeColor {
   group?: string;
 }
 
+export interface ColorGroup {
+  name: string;
+  colors: ThemeColor[];
+}
+
+export interface GroupedColors {
+  groups: ColorGroup[];
+  color_count: number;
+}
+
+export type CvdType = "Protanopia" | "Deuteranopia" | "Tritanopia";
+
+export type ColorSort = "Hue" | "Luminance" | "Saturation";
+
+export type ConflictPolicy = "Error" | "Overwrite" | "Rename";
+
+export type ThemeFormat = "Bte" | "Json";
+
+export type ApplyError =
+  | { kind: "SourceMissing"; path: string }
+  | { kind: "TargetUnresolvable"; bitwig_version: string }
+  | { kind: "ConversionFailed"; reason: string }
+  | { kind: "WriteFailed"; path: string; reason: string }
+  | { kind: "PatchFailed"; jar_path: string; reason: string }
+  | { kind: "PatchFailedRolledBack"; jar_path: string; reason: string }
+  | { kind: "NoInstallations" }
+  | { kind: "ThemeLocked"; path: string }
+  | { kind: "NoVersionSelected" }
+  | { kind: "NothingToReapply" };
+
+export type ApplyFailure = ApplyError & {
+  details: string;
+  message: string;
+};
+
+export interface PatchResult {
+  theme_path: string;
+  patched: boolean;
+  message: string;
+}
+
+export interface ActiveThemeInfo {
+  path: string;
+  exists: boolean;
+  locked: boolean;
+  source: string | null;
+  installation: string | null;
+}
+
+export interface ElevationStatus {
+  available: boolean;
+  mechanism: string;
+  reason: string | null;
+}
+
+export interface PatchLogEntry {
+  ts: number;
+  op: "patch" | "restore";
+  jar_path: string;
+  result: "ok" | "error";
+  duration_ms: number;
+  error: string | null;
+}
+
 export interface AppError {
   message: string;
 }
@@ -38,6 +139,154 @@ export interface RepositoryTheme {
   author_url?: string;
   repo_url: string;
   preview_url?: string;
+  preview_urls: string[];
   description?: string;
   download_url?: string;
+  tags: string[];
+}
+
+export interface FormatStats {
+  bte: number;
+  json: number;
+  zip: number;
+  unknown: number;
+  skipped: number;
+}
+
+export interface AuthorSummary {
+  author: string;
+  author_url: string | null;
+  theme_count: number;
+}
+
+export interface CorruptCacheEntry {
+  path: string;
+  reason: string;
+}
+
+export interface DebugDump {
+  path: string;
+  size_bytes: number;
+}
+
+export interface LibrarySnapshot {
+  path: string;
+  bitwig_version: string;
+  created_at: number;
+  size_bytes: number;
+}
+
+export interface CacheSummary {
+  repository_entries: number;
+  cached_theme_files: number;
+  preview_images: number;
+  total_bytes: number;
+  backups_bytes: number;
+}
+
+export interface CacheHealth {
+  repository_valid: boolean;
+  themes_checked: number;
+  previews_checked: number;
+  corrupt: CorruptCacheEntry[];
+}
+
+export interface ThemeDownloadSize {
+  name: string;
+  bytes: number | null;
+}
+
+export interface DownloadSizeEstimate {
+  total_bytes: number;
+  themes: ThemeDownloadSize[];
+  unknown: string[];
+}
+
+export interface RateLimitStatus {
+  limit: number;
+  remaining: number;
+  reset: number;
+}
+
+export interface Connectivity {
+  github_reachable: boolean;
+  codeberg_reachable: boolean;
+  github_latency_ms: number | null;
+  codeberg_latency_ms: number | null;
+  github_rate_limited: boolean;
+}
+
+export interface KeySchema {
+  key: string;
+  group: string;
+}
+
+export interface ThemeValidation {
+  missing_keys: string[];
+  unknown_keys: string[];
+  invalid_values: string[];
+}
+
+export type ThemeCheck = { Checked: ThemeValidation } | { ParseError: string };
+
+export interface ThemeValidationReport {
+  path: string;
+  result: ThemeCheck;
+}
+
+export interface ColorChange {
+  key: string;
+  old: string;
+  new: string;
+}
+
+export interface ThemeDiff {
+  added: [string, string][];
+  removed: [string, string][];
+  changed: ColorChange[];
+}
+
+export type Compatibility = "Full" | "Partial" | "Unknown";
+
+export interface CompatibleTheme {
+  path: string;
+  name: string | null;
+  compatibility: Compatibility;
+  incompatible_keys: number;
+}
+
+export interface CacheWarmProgress {
+  done: number;
+  total: number;
+  theme_name: string;
+}
+
+export interface KeyRemapResult {
+  theme: Theme;
+  remapped: [string, string][];
+  unmapped: string[];
+}
+
+export interface AppPaths {
+  config_dir: string | null;
+  cache_dir: string | null;
+  log_path: string | null;
+  patcher_jar: string | null;
+  backups_root: string | null;
+  previews_dir: string | null;
+  themes_cache_dir: string | null;
+}
+
+export interface FavoriteTheme {
+  name: string;
+  repo_url: string;
+  author: string;
+}
+
+export interface NormalizeResult {
+  path: string;
+  changed_colors: number;
+  dropped_keys: string[];
+  rewritten: boolean;
+  error: string | null;
 }
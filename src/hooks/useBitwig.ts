@@ -1,5 +1,5 @@
 import { useState, useEffect, useCallback } from "react";
-import type { BitwigInstallation } from "../api/types";
+import type { BitwigInstallation, ElevationStatus } from "../api/types";
 import * as api from "../api/bitwig";
 
 export function useBitwigInstallations() {
@@ -7,18 +7,21 @@ export function useBitwigInstallations() {
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [javaAvailable, setJavaAvailable] = useState<boolean | null>(null);
+  const [elevation, setElevation] = useState<ElevationStatus | null>(null);
   const [backups, setBackups] = useState<Record<string, boolean>>({});
 
   const refresh = useCallback(async () => {
     setLoading(true);
     setError(null);
     try {
-      const [detected, hasJava] = await Promise.all([
+      const [detected, hasJava, elevationStatus] = await Promise.all([
         api.detectBitwigInstallations(),
         api.hasJava(),
+        api.checkElevationAvailable(),
       ]);
       setInstallations(detected);
       setJavaAvailable(hasJava);
+      setElevation(elevationStatus);
 
       // Check for backups for each installation
       const backupStatus: Record<string, boolean> = {};
@@ -90,6 +93,7 @@ export function useBitwigInstallations() {
     loading,
     error,
     javaAvailable,
+    elevation,
     backups,
     refresh,
     addManualPath,
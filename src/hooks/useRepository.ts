@@ -31,6 +31,7 @@ export function useRepositoryThemes() {
       const themesWithAssetUrls = fetchedThemes.map((theme) => ({
         ...theme,
         preview_url: theme.preview_url ? convertFileSrc(theme.preview_url) : undefined,
+        preview_urls: theme.preview_urls.map((url) => convertFileSrc(url)),
       }));
       setThemes(themesWithAssetUrls);
     } catch (e) {
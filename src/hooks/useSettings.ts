@@ -30,10 +30,10 @@ export function useSettings() {
       const newSettings = { ...settings, [key]: value };
       setSettings(newSettings);
 
-      // Save immediately
+      // Save immediately; the backend applies the change to the stored settings
       setSaving(true);
       try {
-        await api.saveSettings(newSettings);
+        setSettings(await api.setSetting(key, value));
       } catch (e) {
         setError(e instanceof Error ? e.message : String(e));
         // Revert on error
@@ -75,10 +75,19 @@ export function useSettings() {
       watch_theme_directory: true,
       selected_bitwig_version: null,
       custom_theme_directory: null,
+      custom_search_paths: [],
       cache_duration_hours: 1,
       show_preview_images: true,
       last_view: "browse",
       skipped_version: null,
+      snapshot_before_apply: false,
+      max_concurrent_requests: 8,
+      embed_theme_checksum: false,
+      patch_history_log: false,
+      rollback_on_patch_failure: false,
+      proxy_url: null,
+      max_theme_keys: 5000,
+      safe_mode: false,
     };
 
     setSettings(defaults);
@@ -115,7 +115,8 @@ export function useThemes(bitwigVersion: string = "5.2") {
   const exportTheme = useCallback(async (themePath: string, destPath: string) => {
     setError(null);
     try {
-      await api.exportTheme(themePath, destPath);
+      // The native save dialog has already confirmed any overwrite
+      await api.exportTheme(themePath, destPath, "Overwrite");
       return true;
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
@@ -132,16 +132,32 @@ export function useUpdater(checkOnMount: boolean = false, skippedVersion: string
       return true;
     } catch (e) {
       const errorMessage = e instanceof Error ? e.message : String(e);
+      const cancelled =
+        (e as { message?: string } | null)?.message === "Update download cancelled";
       setState((prev) => ({
         ...prev,
         downloading: false,
         downloadProgress: null,
-        error: errorMessage,
+        error: cancelled ? null : errorMessage,
       }));
       return false;
     }
   }, [state.updateInfo]);
 
+  // Abort an in-progress download; the update stays available for a retry
+  const cancelDownload = useCallback(async () => {
+    const cancelled = await api.cancelUpdateDownload();
+    if (cancelled) {
+      setState((prev) => ({
+        ...prev,
+        downloading: false,
+        downloadProgress: null,
+        error: null,
+      }));
+    }
+    return cancelled;
+  }, []);
+
   // Restart the app to apply the update
   const restartApp = useCallback(async () => {
     setState((prev) => ({ ...prev, installing: true }));
@@ -184,6 +200,7 @@ export function useUpdater(checkOnMount: boolean = false, skippedVersion: string
     ...state,
     checkForUpdates,
     downloadUpdate,
+    cancelDownload,
     installUpdate,
     restartApp,
     dismissUpdate,
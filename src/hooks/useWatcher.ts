@@ -15,6 +15,7 @@ export function useWatcher(options: UseWatcherOptions = {}) {
   const [currentPath, setCurrentPath] = useState<string | null>(null);
   const [error, setError] = useState<string | null>(null);
   const unlistenRef = useRef<UnlistenFn | null>(null);
+  const unlistenErrorRef = useRef<UnlistenFn | null>(null);
   const callbackRef = useRef(onThemeChange);
 
   // Keep callback ref up to date
@@ -35,6 +36,20 @@ export function useWatcher(options: UseWatcherOptions = {}) {
           }
         }
       );
+
+      if (unlistenErrorRef.current) {
+        await unlistenErrorRef.current();
+      }
+
+      // Watching died in the backend (e.g. the directory was deleted)
+      unlistenErrorRef.current = await listen<api.WatcherErrorEvent>(
+        "theme-watcher-error",
+        (event) => {
+          setIsRunning(false);
+          setCurrentPath(null);
+          setError(event.payload.message);
+        }
+      );
     };
 
     setupListener();
@@ -43,6 +58,9 @@ export function useWatcher(options: UseWatcherOptions = {}) {
       if (unlistenRef.current) {
         unlistenRef.current();
       }
+      if (unlistenErrorRef.current) {
+        unlistenErrorRef.current();
+      }
     };
   }, []);
 